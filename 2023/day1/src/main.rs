@@ -1,93 +1,275 @@
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
-use std::collections::HashMap;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day1/src/input.txt";
 
+    // Words passed as `word=value` arguments replace the English digit names
+    let custom_words: Vec<(String, u32)> = std::env::args()
+        .skip(1)
+        .map(|arg| {
+            parse_word(&arg).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("Wrong word \"{arg}\""))
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    let decoder = if custom_words.is_empty() {
+        CalibrationDecoder::english()
+    } else {
+        CalibrationDecoder::new(&custom_words)
+    };
+
     // Open the file
     let file = File::open(input_path)?;
     let reader = BufReader::new(file);
 
-    // Prapare data structure for efficient lookup
-    let mut names = HashMap::new();
-    names.insert("one", 1);
-    names.insert("two", 2);
-    names.insert("three", 3);
-    names.insert("four", 4);
-    names.insert("five", 5);
-    names.insert("six", 6);
-    names.insert("seven", 7);
-    names.insert("eight", 8);
-    names.insert("nine", 9);
-    names.insert("1", 1);
-    names.insert("2", 2);
-    names.insert("3", 3);
-    names.insert("4", 4);
-    names.insert("5", 5);
-    names.insert("6", 6);
-    names.insert("7", 7);
-    names.insert("8", 8);
-    names.insert("9", 9);
-
-    // Iterate over each line in the file
-    let mut result = 0;
-    let mut counter = 10;
-    let print_lines = 20;
+    // Both modes are decoded in the same pass over every line
+    let mut part1_result = 0;
+    let mut part2_result = 0;
     for line in reader.lines() {
-        counter += 1;
-        let line_content = line?;
-        if counter < print_lines {
-            print!("{} ", line_content);
+        let calibration = decoder.decode(&line?);
+        part1_result += calibration.digits.unwrap_or(0);
+        part2_result += calibration.words.unwrap_or(0);
+    }
+
+    println!("Part1: {}", part1_result);
+    println!("Part2: {}", part2_result);
+
+    Ok(())
+}
+
+// `word=value`, the word can't be empty as it would match everywhere,
+// and the value is a single digit so a calibration value stays below 100
+fn parse_word(input: &str) -> Option<(String, u32)> {
+    let (word, value) = input.split_once('=')?;
+    if word.is_empty() {
+        return None;
+    }
+    let value = value.parse().ok().filter(|value| *value <= 9)?;
+    Some((word.to_string(), value))
+}
+
+// Calibration values of a line, `None` if there is nothing to decode
+pub(crate) struct Calibration {
+    // only the digits are counted
+    digits: Option<u32>,
+    // spelled out words are counted as well
+    words: Option<u32>,
+}
+
+#[derive(Clone, Copy)]
+struct Match {
+    value: u32,
+    len: usize,
+    is_word: bool,
+}
+
+// Aho-Corasick automaton over the digits and the words of a vocabulary.
+// Every state has a transition for every byte, so a line is read
+// byte by byte without going back and overlapping words like `oneight`
+// are all found.
+pub(crate) struct CalibrationDecoder {
+    transitions: Vec<[usize; 256]>,
+    // all the patterns ending in the state, including the ones found through the fail links
+    outputs: Vec<Vec<Match>>,
+}
+
+const ROOT: usize = 0;
+
+impl CalibrationDecoder {
+    pub(crate) fn english() -> CalibrationDecoder {
+        let words = [
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ];
+        let vocabulary: Vec<(String, u32)> = words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| (word.to_string(), i as u32 + 1))
+            .collect();
+        CalibrationDecoder::new(&vocabulary)
+    }
+
+    // Words of the vocabulary must not be empty
+    fn new(vocabulary: &[(String, u32)]) -> CalibrationDecoder {
+        assert!(
+            vocabulary.iter().all(|(word, _)| !word.is_empty()),
+            "Empty words can't be decoded"
+        );
+        let mut decoder = CalibrationDecoder {
+            transitions: vec![[ROOT; 256]],
+            outputs: vec![vec![]],
+        };
+
+        // build the trie, a missing transition is the one back to the root.
+        // `0` isn't a calibration digit, but it can still be added as a word
+        for digit in 1..=9 {
+            let pattern = digit.to_string();
+            decoder.add_pattern(
+                pattern.as_bytes(),
+                Match {
+                    value: digit,
+                    len: 1,
+                    is_word: false,
+                },
+            );
+        }
+        for (word, value) in vocabulary.iter() {
+            decoder.add_pattern(
+                word.as_bytes(),
+                Match {
+                    value: *value,
+                    len: word.len(),
+                    is_word: true,
+                },
+            );
         }
-        // Process each line as needed
-        let mut first: Option<i32> = None;
-        let mut last: Option<i32> = None;
-        let len = line_content.len();
-        // look for the first digit
-        let mut i = 0;
-        while i < len {
-            let sub = &line_content[i..];
-            for (name, value) in &names {
-                if sub.starts_with(name) {
-                    first = Some(*value);
-                    break;
+
+        // turn the trie into the automaton going breadth-first:
+        // the missing transitions of a state are the ones of its fail state
+        let mut fail: Vec<usize> = vec![ROOT; decoder.transitions.len()];
+        let mut queue: VecDeque<usize> = decoder.transitions[ROOT]
+            .iter()
+            .copied()
+            .filter(|state| *state != ROOT)
+            .collect();
+        while let Some(state) = queue.pop_front() {
+            let mut fail_outputs = decoder.outputs[fail[state]].clone();
+            decoder.outputs[state].append(&mut fail_outputs);
+            for byte in 0..256 {
+                let next = decoder.transitions[state][byte];
+                let fail_next = decoder.transitions[fail[state]][byte];
+                if next == ROOT {
+                    decoder.transitions[state][byte] = fail_next;
+                } else {
+                    fail[next] = fail_next;
+                    queue.push_back(next);
                 }
             }
-            match first {
-                Some(_) => break,
-                None => i += 1
-            }
         }
-        // look for the last digit
-        i = len;
-        while i > 0 {
-            let sub = &line_content[0..i];
-            for (name, value) in &names {
-                if sub.ends_with(name) {
-                    last = Some(*value);
-                    break;
+        decoder
+    }
+
+    fn add_pattern(&mut self, pattern: &[u8], found: Match) {
+        let mut state = ROOT;
+        for byte in pattern.iter() {
+            let next = self.transitions[state][*byte as usize];
+            state = if next == ROOT {
+                self.transitions.push([ROOT; 256]);
+                self.outputs.push(vec![]);
+                let new_state = self.transitions.len() - 1;
+                self.transitions[state][*byte as usize] = new_state;
+                new_state
+            } else {
+                next
+            };
+        }
+        self.outputs[state].push(found);
+    }
+
+    pub(crate) fn decode(&self, line: &str) -> Calibration {
+        let mut digits = FirstAndLast::default();
+        let mut words = FirstAndLast::default();
+        let mut state = ROOT;
+        for (pos, byte) in line.bytes().enumerate() {
+            state = self.transitions[state][byte as usize];
+            for found in self.outputs[state].iter() {
+                let start = pos + 1 - found.len;
+                if !found.is_word {
+                    digits.add(start, found.len, found.value);
                 }
-            }
-            match last {
-                Some(_) => break,
-                None => i -= 1,
+                words.add(start, found.len, found.value);
             }
         }
 
-        if first == None {
-            first = Some(0);
+        Calibration {
+            digits: digits.value(),
+            words: words.value(),
         }
-        if last == None {
-            last = first;
+    }
+}
+
+// Matches are found by their ends, but with a custom vocabulary
+// a longer word can start before a shorter one found earlier,
+// so both the first and the last match are picked by their starts.
+// Of the matches starting at the same position the longest one wins.
+#[derive(Default)]
+struct FirstAndLast {
+    // (start, len, value)
+    first: Option<(usize, usize, u32)>,
+    last: Option<(usize, usize, u32)>,
+}
+
+impl FirstAndLast {
+    fn add(&mut self, start: usize, len: usize, value: u32) {
+        if self
+            .first
+            .is_none_or(|(s, l, _)| start < s || (start == s && len > l))
+        {
+            self.first = Some((start, len, value));
         }
-        if counter < print_lines {
-            println!("{}{}", first.unwrap(), last.unwrap());
+        if self
+            .last
+            .is_none_or(|(s, l, _)| start > s || (start == s && len > l))
+        {
+            self.last = Some((start, len, value));
         }
-        result += first.unwrap() * 10 + last.unwrap();
     }
-    println!("{}", result);
 
-    Ok(())
+    fn value(&self) -> Option<u32> {
+        Some(self.first?.2 * 10 + self.last?.2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_words(decoder: &CalibrationDecoder, line: &str) -> Option<u32> {
+        decoder.decode(line).words
+    }
+
+    #[test]
+    fn example() {
+        let decoder = CalibrationDecoder::english();
+        let calibration = decoder.decode("pqr3stu8vwx");
+        assert_eq!(
+            (calibration.digits, calibration.words),
+            (Some(38), Some(38))
+        );
+        let calibration = decoder.decode("xtwone3four");
+        assert_eq!(
+            (calibration.digits, calibration.words),
+            (Some(33), Some(24))
+        );
+        assert_eq!(decode_words(&decoder, "eightwothree"), Some(83));
+        assert_eq!(decode_words(&decoder, "oneight"), Some(18));
+        assert_eq!(decoder.decode("abc").words, None);
+    }
+
+    #[test]
+    fn overlapping_custom_words() {
+        let vocabulary = [("abcd".to_string(), 1), ("bc".to_string(), 2)];
+        let decoder = CalibrationDecoder::new(&vocabulary);
+        // `bc` ends first, but `abcd` starts first,
+        // and it's `bc` that starts last even though `abcd` ends last
+        assert_eq!(decode_words(&decoder, "abcd"), Some(12));
+        assert_eq!(decode_words(&decoder, "9abcd"), Some(92));
+
+        // `ab` and `abcd` start at the same position, the longer one wins
+        let vocabulary = [("ab".to_string(), 3), ("abcd".to_string(), 4)];
+        let decoder = CalibrationDecoder::new(&vocabulary);
+        assert_eq!(decode_words(&decoder, "abcd"), Some(44));
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(parse_word("uno=1"), Some(("uno".to_string(), 1)));
+        assert_eq!(parse_word("=5"), None);
+        assert_eq!(parse_word("uno"), None);
+        assert_eq!(parse_word("nulla=0"), Some(("nulla".to_string(), 0)));
+        assert_eq!(parse_word("x=10"), None);
+        assert_eq!(parse_word("x=4294967295"), None);
+    }
 }
@@ -1,351 +1,268 @@
-use core::panic;
-use std::collections::{HashSet, VecDeque};
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use grid::{Grid, Input};
+use std::collections::HashSet;
+use std::io;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day10/src/input.txt";
+    let render = std::env::args().any(|arg| arg == "--render");
 
     // Part1
-    // open the file and parse input
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let mut data: Vec<Vec<char>> = reader
-        .lines()
-        .map(|l| {
-            let input = l.unwrap();
-            let seq: Vec<char> = input.chars().collect();
-            return seq;
-        })
-        .collect();
+    // map the input into memory and use it as a grid without copying
+    let input = Input::open(input_path)?;
+    let data =
+        Grid::parse(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
-    // find the start and go in both directions until we meet again
-    let start_pos = find_start(&data);
-    let mut main_loop = find_loop(&data, start_pos);
+    // find the start and follow the pipes until we get back
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let start_pos = find_start(&data).ok_or_else(|| invalid("Start is not found"))?;
+    let main_loop = find_main_loop(&data, start_pos)
+        .ok_or_else(|| invalid("There is no loop going through the start"))?;
+    println!("Start is {}", main_loop.start_pipe);
 
-    println!("Part1: {}", main_loop.len() / 2);
+    println!("Part1: {}", main_loop.tiles.len() / 2);
 
     // Part2
-    // update data: replace S with actual pipe
-    data = replace_start_with_pipe(&data, &main_loop);
-    let part2 = count_enclosed_cells(&data, &mut main_loop);
+    let part2 = enclosed_area(&main_loop.tiles);
     println!("Part2: {}", part2);
 
-    return Ok(());
+    if render {
+        print!("{}", render_maze(&data, &main_loop));
+    }
+
+    Ok(())
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
-struct Coordinates(usize, usize);
+pub(crate) struct Coordinates(usize, usize);
 
-impl Coordinates {
-    fn connected_neighbors(&self, data: &Vec<Vec<char>>) -> Vec<Coordinates> {
-        let mut neibs: Vec<Coordinates> = vec![];
-        // top
-        if self.0 > 0 && ['|', 'F', '7'].contains(&data[self.0 - 1][self.1]) {
-            neibs.push(Coordinates(self.0 - 1, self.1));
-        }
-        // right
-        if self.1 < data[0].len() - 1 && ['-', '7', 'J'].contains(&data[self.0][self.1 + 1]) {
-            neibs.push(Coordinates(self.0, self.1 + 1));
-        }
-        // bottom
-        if self.0 < data.len() - 1 && ['|', 'J', 'L'].contains(&data[self.0 + 1][self.1]) {
-            neibs.push(Coordinates(self.0 + 1, self.1));
-        }
-        // left
-        if self.1 > 0 && ['-', 'L', 'F'].contains(&data[self.0][self.1 - 1]) {
-            neibs.push(Coordinates(self.0, self.1 - 1));
-        }
-        return neibs;
-    }
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
 
-    // Find the next connected pipe taking into account the previous pipe
-    fn next(&self, visited: Coordinates, data: &Vec<Vec<char>>) -> (Coordinates, Coordinates) {
-        let neibs = match data[self.0][self.1] {
-            '|' => [
-                Coordinates(self.0 - 1, self.1),
-                Coordinates(self.0 + 1, self.1),
-            ],
-            'F' => [
-                Coordinates(self.0, self.1 + 1),
-                Coordinates(self.0 + 1, self.1),
-            ],
-            'L' => [
-                Coordinates(self.0 - 1, self.1),
-                Coordinates(self.0, self.1 + 1),
-            ],
-            'J' => [
-                Coordinates(self.0 - 1, self.1),
-                Coordinates(self.0, self.1 - 1),
-            ],
-            '-' => [
-                Coordinates(self.0, self.1 - 1),
-                Coordinates(self.0, self.1 + 1),
-            ],
-            '7' => [
-                Coordinates(self.0, self.1 - 1),
-                Coordinates(self.0 + 1, self.1),
-            ],
-            _ => panic!("Wrong neighbor"),
-        };
-        if neibs[0] == visited {
-            (self.to_owned(), neibs[1].to_owned())
-        } else {
-            (self.to_owned(), neibs[0].to_owned())
-        }
-    }
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Cell {
+    Loop,
+    Inside,
+    Outside,
+}
 
-    // Get neighbor tiles that aren't part of the main loop
-    fn neib_tiles(&self, data: &Vec<Vec<char>>, main_loop: &Vec<Coordinates>) -> Vec<Coordinates> {
-        let mut result: Vec<Coordinates> = vec![];
-        // top
-        if self.0 > 0 && !main_loop.contains(&Coordinates(self.0 - 1, self.1)) {
-            result.push(Coordinates(self.0 - 1, self.1));
-        }
-        // right
-        if self.1 < data[0].len() - 1 && !main_loop.contains(&Coordinates(self.0, self.1 + 1)) {
-            result.push(Coordinates(self.0, self.1 + 1));
-        }
-        // bottom
-        if self.0 < data.len() - 1 && !main_loop.contains(&Coordinates(self.0 + 1, self.1)) {
-            result.push(Coordinates(self.0 + 1, self.1));
-        }
-        // left
-        if self.1 > 0 && !main_loop.contains(&Coordinates(self.0, self.1 - 1)) {
-            result.push(Coordinates(self.0, self.1 - 1));
-        }
+// The loop going through the start.
+// Tiles are ordered as they are visited so they also form a polygon.
+pub(crate) struct PipeLoop {
+    tiles: Vec<Coordinates>,
+    // the pipe hidden under `S`
+    start_pipe: char,
+}
 
-        return result;
-    }
+const PIPES: [char; 6] = ['|', '-', 'L', 'J', '7', 'F'];
 
-    fn search_enclosed_tiles(
-        &self,
-        data: &Vec<Vec<char>>,
-        main_loop: &Vec<Coordinates>,
-        visited: &mut HashSet<Coordinates>,
-    ) -> u32 {
-        if visited.contains(self) {
-            return 0;
-        }
-        let mut result: u32 = 0;
-        let mut queue: VecDeque<Coordinates> = VecDeque::new();
-        queue.push_back(self.to_owned());
-        visited.insert(self.to_owned());
-        while !queue.is_empty() {
-            let curr = queue.pop_front().unwrap();
-            result += 1;
-            let neibs: Vec<Coordinates> = curr
-                .neib_tiles(data, main_loop)
-                .iter()
-                .filter_map(|x| {
-                    if visited.contains(x) {
-                        None
-                    } else {
-                        Some(x.to_owned())
-                    }
-                })
-                .collect();
-            for neib in neibs.iter() {
-                visited.insert(neib.to_owned());
-                queue.push_back(neib.to_owned());
-            }
+impl Direction {
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
         }
-        return result;
     }
 }
 
-fn find_start(data: &Vec<Vec<char>>) -> Coordinates {
-    for i in 0..data.len() {
-        for j in 0..data[0].len() {
-            if data[i][j] == 'S' {
-                return Coordinates(i, j);
-            }
+impl Coordinates {
+    // Neighbor in the given direction if it's still on the grid
+    fn step(&self, direction: Direction, data: &Grid) -> Option<Coordinates> {
+        match direction {
+            Direction::North if self.0 > 0 => Some(Coordinates(self.0 - 1, self.1)),
+            Direction::East if self.1 < data.width() - 1 => Some(Coordinates(self.0, self.1 + 1)),
+            Direction::South if self.0 < data.height() - 1 => Some(Coordinates(self.0 + 1, self.1)),
+            Direction::West if self.1 > 0 => Some(Coordinates(self.0, self.1 - 1)),
+            _ => None,
         }
     }
-    panic!("Start is not found")
 }
 
-fn find_loop(data: &Vec<Vec<char>>, start_pos: Coordinates) -> Vec<Coordinates> {
-    let mut result: Vec<Coordinates> = vec![start_pos.to_owned()];
-    let neighbors = start_pos.connected_neighbors(&data);
-    let mut curr: Coordinates = neighbors[0].to_owned();
-    let mut prev: Coordinates = start_pos.to_owned();
-    while curr != start_pos {
-        result.push(curr.to_owned());
+// Directions a pipe connects
+fn pipe_directions(pipe: char) -> Option<[Direction; 2]> {
+    match pipe {
+        '|' => Some([Direction::North, Direction::South]),
+        '-' => Some([Direction::East, Direction::West]),
+        'L' => Some([Direction::North, Direction::East]),
+        'J' => Some([Direction::North, Direction::West]),
+        '7' => Some([Direction::South, Direction::West]),
+        'F' => Some([Direction::East, Direction::South]),
+        _ => None,
+    }
+}
+
+pub(crate) fn find_start(data: &Grid) -> Option<Coordinates> {
+    data.find(b'S').map(|(i, j)| Coordinates(i, j))
+}
+
+// Try every pipe in place of `S` and keep the first one that closes a loop
+pub(crate) fn find_main_loop(data: &Grid, start_pos: Coordinates) -> Option<PipeLoop> {
+    PIPES.iter().find_map(|pipe| {
+        follow_loop(data, start_pos, *pipe).map(|tiles| PipeLoop {
+            tiles,
+            start_pipe: *pipe,
+        })
+    })
+}
+
+fn follow_loop(data: &Grid, start_pos: Coordinates, start_pipe: char) -> Option<Vec<Coordinates>> {
+    let [mut direction, last_direction] = pipe_directions(start_pipe)?;
+    let mut result: Vec<Coordinates> = vec![start_pos];
+    let mut curr = start_pos;
+    loop {
+        curr = curr.step(direction, data)?;
+        let came_from = direction.opposite();
+        if curr == start_pos {
+            return if came_from == last_direction {
+                Some(result)
+            } else {
+                None
+            };
+        }
+
         // Go to the next pipe
-        (prev, curr) = curr.next(prev, &data);
+        let [a, b] = pipe_directions(data[(curr.0, curr.1)] as char)?;
+        direction = match (a == came_from, b == came_from) {
+            (true, _) => b,
+            (_, true) => a,
+            _ => return None,
+        };
+        result.push(curr);
     }
-    return result;
 }
 
-fn replace_start_with_pipe(data: &Vec<Vec<char>>, main_loop: &Vec<Coordinates>) -> Vec<Vec<char>> {
-    let curr = main_loop[0];
-    let next = main_loop[1];
-    let prev = main_loop[main_loop.len() - 1];
-    let new_val = if prev.0 == next.0 {
-        '-'
-    } else if prev.1 == next.1 {
-        '|'
-    } else if (prev.1 == curr.1 && next.0 == curr.0 && next.1 > curr.1 && prev.0 > curr.0)
-        || (prev.0 == curr.0 && prev.1 > curr.1 && curr.0 < next.0 && curr.1 == next.1)
-    {
-        'F'
-    } else if (prev.0 < curr.0 && prev.1 == curr.1 && curr.0 == next.0 && curr.1 < next.1)
-        || (prev.0 == curr.0 && prev.1 > curr.1 && curr.0 > next.0 && curr.1 == next.1)
-    {
-        'L'
-    } else if (prev.0 < curr.0 && prev.1 == curr.1 && curr.0 == next.0 && curr.1 > next.1)
-        || (prev.0 == curr.0 && prev.1 < curr.1 && curr.0 > next.0 && curr.1 == next.1)
-    {
-        'J'
-    } else if (prev.0 == curr.0 && prev.1 < curr.1 && curr.0 < next.0 && curr.1 == next.1)
-        || (prev.0 > curr.0 && prev.1 == curr.1 && curr.0 == next.0 && curr.1 > next.1)
-    {
-        '7'
+// The tile with the pipe hidden under `S` put in its place
+fn tile(data: &Grid, main_loop: &PipeLoop, pos: Coordinates) -> char {
+    if pos == main_loop.tiles[0] {
+        main_loop.start_pipe
     } else {
-        panic!("Unknown pipe")
-    };
-    println!("New value instead of S: {new_val}");
+        data[(pos.0, pos.1)] as char
+    }
+}
 
-    let result: Vec<Vec<char>> = data
+// Number of tiles enclosed by the loop.
+// Shoelace formula gives the area of the polygon going through the tile centers
+// and Pick's theorem turns it into the number of the inner points.
+fn enclosed_area(tiles: &[Coordinates]) -> u64 {
+    let double_area = tiles
         .iter()
-        .enumerate()
-        .map(|(i, row)| {
-            if i == curr.0 {
-                let updated_row: Vec<char> = row
-                    .iter()
-                    .enumerate()
-                    .map(|(j, val)| {
-                        if j == curr.1 {
-                            new_val.to_owned()
+        .zip(tiles.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64)
+        .sum::<i64>()
+        .unsigned_abs();
+    (double_area + 2 - tiles.len() as u64) / 2
+}
+
+// Mark every tile as a part of the loop, inside or outside of it.
+// Going along a row we are inside after crossing an odd number of
+// pipes connected to the north.
+fn classify_cells(data: &Grid, main_loop: &PipeLoop) -> Vec<Vec<Cell>> {
+    let loop_tiles: HashSet<&Coordinates> = main_loop.tiles.iter().collect();
+    (0..data.height())
+        .map(|i| {
+            let mut is_inside = false;
+            (0..data.width())
+                .map(|j| {
+                    if !loop_tiles.contains(&Coordinates(i, j)) {
+                        return if is_inside {
+                            Cell::Inside
                         } else {
-                            val.to_owned()
-                        }
-                    })
-                    .collect();
-                return updated_row;
-            } else {
-                row.to_owned()
-            }
+                            Cell::Outside
+                        };
+                    }
+                    let ch = tile(data, main_loop, Coordinates(i, j));
+                    if pipe_directions(ch).is_some_and(|dirs| dirs.contains(&Direction::North)) {
+                        is_inside = !is_inside;
+                    }
+                    Cell::Loop
+                })
+                .collect()
         })
-        .collect();
-    return result;
+        .collect()
 }
 
-fn count_enclosed_cells(data: &Vec<Vec<char>>, main_loop: &mut Vec<Coordinates>) -> u32 {
-    reorder_clockwise(data, main_loop);
-
-    // for each pipe go right and find enclosing tiles
-    let mut visited: HashSet<Coordinates> = HashSet::new();
-    let mut tiles_count: u32 = 0;
-
-    for (idx, _) in main_loop.iter().enumerate() {
-        let neibs = get_right_tiles(idx, data, main_loop);
-        // println!("Pipe: {:?}, Neibs: {:?}", pipe, neibs);
-        for tile in neibs.iter() {
-            tiles_count += tile.search_enclosed_tiles(data, main_loop, &mut visited);
+// Loop drawn with box-drawing characters, inside tiles as `I` and outside ones as `O`
+fn render_maze(data: &Grid, main_loop: &PipeLoop) -> String {
+    let cells = classify_cells(data, main_loop);
+    let mut result = String::new();
+    for (i, cells_row) in cells.iter().enumerate() {
+        for (j, cell) in cells_row.iter().enumerate() {
+            result.push(match cell {
+                Cell::Loop => match tile(data, main_loop, Coordinates(i, j)) {
+                    '|' => '│',
+                    '-' => '─',
+                    'L' => '└',
+                    'J' => '┘',
+                    '7' => '┐',
+                    'F' => '┌',
+                    _ => panic!("Wrong pipe type"),
+                },
+                Cell::Inside => 'I',
+                Cell::Outside => 'O',
+            });
         }
+        result.push('\n');
     }
-    return tiles_count;
+    result
 }
 
-fn reorder_clockwise(data: &Vec<Vec<char>>, main_loop: &mut Vec<Coordinates>) -> () {
-    // 1. find the top-left pipe
-    // it's always an 'F'-corner
-    let (idx, _pipe) = main_loop
-        .iter()
-        .enumerate()
-        .min_by_key(|(_idx, &Coordinates(i, j))| i * data[0].len() + j)
-        .unwrap();
-    // 2. get next pipe in the loop
-    let next = if idx < main_loop.len() - 1 {
-        main_loop[idx + 1]
-    } else {
-        main_loop[0]
-    };
-    // 3. detect if the upper outer node was to the left from the found pipe
-    // going from the pipe to the next
-    let is_clockwise = match data[next.0][next.1] {
-        '7' => true,
-        '-' => true,
-        '|' => false,
-        'L' => false,
-        'J' => false,
-        _ => panic!("Wrong pipe value"),
-    };
-    // 4. if it was on the right - reverse the loop
-    if !is_clockwise {
-        main_loop.reverse();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_loop(data: &Grid) -> PipeLoop {
+        find_main_loop(data, find_start(data).unwrap()).unwrap()
     }
-}
 
-fn get_right_tiles(
-    tile_idx: usize,
-    data: &Vec<Vec<char>>,
-    main_loop: &Vec<Coordinates>,
-) -> Vec<Coordinates> {
-    // Get coordinates to the right of the current pipe (in the clockwise direction)
-    let curr = main_loop[tile_idx];
-    let next = if tile_idx < main_loop.len() - 1 {
-        main_loop[tile_idx + 1]
-    } else {
-        main_loop[0]
-    };
+    #[test]
+    fn start_on_the_edge() {
+        let data = Grid::parse(b"S-7\n|.|\nL-J\n").unwrap();
+        let main_loop = find_loop(&data);
+        assert_eq!(main_loop.start_pipe, 'F');
+        assert_eq!(main_loop.tiles.len() / 2, 4);
+        assert_eq!(enclosed_area(&main_loop.tiles), 1);
+        assert_eq!(render_maze(&data, &main_loop), "┌─┐\n│I│\n└─┘\n");
+    }
 
-    let mut result: Vec<Coordinates> = vec![];
-    match data[curr.0][curr.1] {
-        '-' => {
-            if next.1 > curr.1 {
-                result.push(Coordinates(curr.0 + 1, curr.1))
-            } else {
-                result.push(Coordinates(curr.0 - 1, curr.1))
+    #[test]
+    fn inside_and_outside() {
+        // tiles marked `I` are enclosed and `O` aren't, even squeezed between pipes
+        let input = include_bytes!("input_test2.txt");
+        let data = Grid::parse(input).unwrap();
+        let main_loop = find_loop(&data);
+        assert_eq!(enclosed_area(&main_loop.tiles), 4);
+        let cells = classify_cells(&data, &main_loop);
+        for (i, row) in data.rows().enumerate() {
+            for (j, ch) in row.iter().enumerate() {
+                match ch {
+                    b'I' => assert_eq!(cells[i][j], Cell::Inside),
+                    b'O' => assert_eq!(cells[i][j], Cell::Outside),
+                    _ => {}
+                }
             }
         }
-        '7' => {
-            if next.1 < curr.1 {
-                result.push(Coordinates(curr.0, curr.1 + 1));
-                result.push(Coordinates(curr.0 - 1, curr.1));
-            }
-        }
-        'F' => {
-            if next.0 > curr.0 {
-                result.push(Coordinates(curr.0 - 1, curr.1));
-                result.push(Coordinates(curr.0, curr.1 - 1));
-            }
-        }
-        '|' => {
-            if next.0 < curr.0 {
-                result.push(Coordinates(curr.0, curr.1 + 1));
-            } else {
-                result.push(Coordinates(curr.0, curr.1 - 1));
-            }
-        }
-        'L' => {
-            if next.1 > curr.1 {
-                result.push(Coordinates(curr.0, curr.1 - 1));
-                result.push(Coordinates(curr.0 + 1, curr.1));
-            }
-        }
-        'J' => {
-            if next.0 < curr.0 {
-                result.push(Coordinates(curr.0, curr.1 + 1));
-                result.push(Coordinates(curr.0 + 1, curr.1));
-            }
-        }
-        _ => panic!("Wrong pipe type"),
-    };
 
-    // exclude main_loop nodes from the result
-    result = result
-        .iter()
-        .filter_map(|c| {
-            if main_loop.contains(c) {
-                None
-            } else {
-                Some(*c)
-            }
-        })
-        .collect();
-    return result;
+        let data = Grid::parse(include_bytes!("input_test.txt")).unwrap();
+        let main_loop = find_loop(&data);
+        assert_eq!(enclosed_area(&main_loop.tiles), 10);
+        let inside = classify_cells(&data, &main_loop)
+            .iter()
+            .flatten()
+            .filter(|cell| **cell == Cell::Inside)
+            .count();
+        assert_eq!(inside, 10);
+    }
+
+    #[test]
+    fn no_loop() {
+        let data = Grid::parse(b"S-.\n|.|\nL-J\n").unwrap();
+        assert!(find_main_loop(&data, find_start(&data).unwrap()).is_none());
+    }
 }
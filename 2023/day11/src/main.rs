@@ -1,141 +1,231 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use grid::{Grid, Input};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::io;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day11/src/input.txt";
+    let print_pairs = std::env::args().any(|arg| arg == "--pairs");
+    // Expansion factor for part 2
+    let factor = match std::env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+        Some(arg) => parse_factor(&arg).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Wrong expansion factor {arg}"),
+            )
+        })?,
+        None => 1_000_000,
+    };
 
     // Part1
-    // open the file and parse input
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let data: Vec<Vec<bool>> = reader
-        .lines()
-        .map(|l| {
-            let input = l.unwrap();
-            let seq: Vec<bool> = input.chars().map(|ch| ch == '#').collect();
-            return seq;
-        })
-        .collect();
-
-    // expand the universe
-    let part1_data = expand_universe(&data);
+    // map the input into memory and use it as a grid without copying
+    let input = Input::open(input_path)?;
+    let data =
+        Grid::parse(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    // find galaxies's coordinates in the expanded universe
+    // and calc the shortest distances for each pair of galaxies
+    let part1 = get_expanded_galaxies(&data, 2)
+        .and_then(|galaxies| Some((calc_sum_of_distances(&galaxies)?, galaxies)));
+    match part1 {
+        Some((part1_result, galaxies)) => {
+            println!("Part1: {}", part1_result);
+            // every distance is a part of the sum, so none of them overflows
+            if print_pairs {
+                for ((i, j), distance) in calc_pair_distances(&galaxies) {
+                    println!("{} - {}: {}", i + 1, j + 1, distance);
+                }
+            }
+        }
+        None => println!("Part1: overflow"),
+    }
 
-    // find galaxies's coordinates
-    let galaxies = get_galaxies(&part1_data);
-    // calc the shortest distances for each pair of galaxies
-    let part1_result = calc_sum_of_distances(&galaxies);
+    // Part2
+    let part2_result = get_expanded_galaxies(&data, factor)
+        .as_deref()
+        .and_then(calc_sum_of_distances);
+    match part2_result {
+        Some(result) => println!("Part2: {}", result),
+        None => println!("Part2: overflow"),
+    }
 
-    println!("Part1: {}", part1_result);
+    Ok(())
+}
 
-    // Part2
-    let mut part2_galaxies = get_galaxies(&data);
-    update_galaxies_after_expasion(&data, &mut part2_galaxies);
-    let part2_result = calc_sum_of_distances(&part2_galaxies);
+// Every empty row or column becomes `factor` of them, so it can't be 0
+fn parse_factor(input: &str) -> Option<u64> {
+    input.parse().ok().filter(|factor| *factor > 0)
+}
 
-    println!("Part2: {}", part2_result);
+#[derive(Clone, Copy)]
+struct Coords(u64, u64);
+
+// Coordinates of the galaxies after every empty row and column
+// has been replaced with `factor` empty ones, `factor` is positive.
+// `None` if a coordinate doesn't fit into u64.
+fn get_expanded_galaxies(universe: &Grid, factor: u64) -> Option<Vec<Coords>> {
+    // number of empty rows and columns before each index
+    let empty_rows_before = count_empty_before(universe.height(), |i| {
+        universe.row(i).iter().all(|x| *x != b'#')
+    });
+    let empty_cols_before = count_empty_before(universe.width(), |j| {
+        universe.rows().all(|row| row[j] != b'#')
+    });
 
-    return Ok(());
+    let expand = |idx: usize, empty_before: u64| {
+        (factor - 1)
+            .checked_mul(empty_before)?
+            .checked_add(idx as u64)
+    };
+    universe
+        .positions(b'#')
+        .map(|(i, j)| {
+            Some(Coords(
+                expand(i, empty_rows_before[i])?,
+                expand(j, empty_cols_before[j])?,
+            ))
+        })
+        .collect()
 }
 
-fn expand_universe(data: &Vec<Vec<bool>>) -> Vec<Vec<bool>> {
-    let mut tmp: Vec<Vec<bool>> = vec![];
-    // double empty rows
-    for row in data.iter() {
-        tmp.push(row.to_owned());
-        if row.iter().all(|x| *x == false) {
-            tmp.push(row.to_owned());
-        }
+fn count_empty_before(len: usize, is_empty: impl Fn(usize) -> bool) -> Vec<u64> {
+    let mut result: Vec<u64> = vec![0; len];
+    for idx in 1..len {
+        result[idx] = result[idx - 1] + is_empty(idx - 1) as u64;
     }
+    result
+}
 
-    // double empty columns
-    let mut result: Vec<Vec<bool>> = tmp.iter().map(|_| vec![]).collect();
-
-    for j in 0..tmp[0].len() {
-        let mut is_empty = true;
-        for i in 0..tmp.len() {
-            result[i].push(tmp[i][j]);
-            if tmp[i][j] == true {
-                is_empty = false;
-            }
-        }
+// Sum of Manhattan distances between all pairs of galaxies.
+// Axes are independent, and in a sorted list of coordinates the k-th one
+// is subtracted from all the values after it and added for all the values before.
+// `None` if the sum doesn't fit into u64.
+fn calc_sum_of_distances(galaxies: &[Coords]) -> Option<u64> {
+    let axis_sum = |axis: fn(&Coords) -> u64| {
+        let mut values: Vec<u64> = galaxies.iter().map(axis).collect();
+        values.sort_unstable();
+        sum_of_sorted_differences(&values)
+    };
+    #[cfg(feature = "parallel")]
+    let (rows, cols) = rayon::join(|| axis_sum(|g| g.0), || axis_sum(|g| g.1));
+    #[cfg(not(feature = "parallel"))]
+    let (rows, cols) = (axis_sum(|g| g.0), axis_sum(|g| g.1));
+    rows?.checked_add(cols?)
+}
 
-        if is_empty {
-            // insert another column
-            for i in 0..tmp.len() {
-                result[i].push(false);
-            }
-        }
+fn sum_of_sorted_differences(values: &[u64]) -> Option<u64> {
+    let mut prefix_sum: u64 = 0;
+    let mut result: u64 = 0;
+    for (k, value) in values.iter().enumerate() {
+        // the values are sorted, so none of the previous ones is larger
+        let differences = value.checked_mul(k as u64)? - prefix_sum;
+        result = result.checked_add(differences)?;
+        prefix_sum = prefix_sum.checked_add(*value)?;
     }
+    Some(result)
+}
 
-    return result;
+// Distance for every pair of galaxies by their indices,
+// the order is the same with and without the `parallel` feature
+fn calc_pair_distances(galaxies: &[Coords]) -> Vec<((usize, usize), u64)> {
+    #[cfg(feature = "parallel")]
+    let firsts = galaxies.par_iter().enumerate();
+    #[cfg(not(feature = "parallel"))]
+    let firsts = galaxies.iter().enumerate();
+    firsts
+        .flat_map(|(i, g1)| {
+            galaxies
+                .iter()
+                .enumerate()
+                .skip(i + 1)
+                .map(|(j, g2)| ((i, j), g2.0.abs_diff(g1.0) + g2.1.abs_diff(g1.1)))
+                .collect::<Vec<_>>()
+        })
+        .collect()
 }
 
-#[derive(Clone, Copy)]
-struct Coords(usize, usize);
-
-fn get_galaxies(data: &Vec<Vec<bool>>) -> Vec<Coords> {
-    let mut result: Vec<Coords> = vec![];
-    for (i, row) in data.iter().enumerate() {
-        for (j, val) in row.iter().enumerate() {
-            if *val == true {
-                result.push(Coords(i, j));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // The naive way: the image with every empty row and column actually repeated
+    // `factor` times, and the galaxies found in it again
+    fn expand_image(universe: &Grid, factor: usize) -> Vec<Coords> {
+        let empty_cols: Vec<bool> = (0..universe.width())
+            .map(|j| universe.rows().all(|row| row[j] != b'#'))
+            .collect();
+        let mut image: Vec<u8> = vec![];
+        for row in universe.rows() {
+            let mut expanded: Vec<u8> = vec![];
+            for (cell, is_empty) in row.iter().zip(empty_cols.iter()) {
+                let copies = if *is_empty { factor } else { 1 };
+                expanded.extend(std::iter::repeat_n(*cell, copies));
+            }
+            expanded.push(b'\n');
+            let copies = if row.contains(&b'#') { 1 } else { factor };
+            for _ in 0..copies {
+                image.extend_from_slice(&expanded);
             }
         }
+        let image = Grid::parse(&image).unwrap();
+        image
+            .positions(b'#')
+            .map(|(i, j)| Coords(i as u64, j as u64))
+            .collect()
+    }
+
+    // rows of `.` and `#`, all of the same length
+    fn universe() -> impl Strategy<Value = Vec<u8>> {
+        (1usize..12, 1usize..12).prop_flat_map(|(height, width)| {
+            prop::collection::vec(prop::bool::weighted(0.2), height * width).prop_map(
+                move |cells| {
+                    let mut image: Vec<u8> = vec![];
+                    for row in cells.chunks(width) {
+                        image.extend(
+                            row.iter()
+                                .map(|is_galaxy| if *is_galaxy { b'#' } else { b'.' }),
+                        );
+                        image.push(b'\n');
+                    }
+                    image
+                },
+            )
+        })
     }
-    return result;
-}
 
-fn calc_sum_of_distances(galaxies: &Vec<Coords>) -> u64 {
-    // get all pairs
-    let mut pairs: Vec<(Coords, Coords)> = vec![];
-    for (i, g1) in galaxies.iter().enumerate() {
-        for g2 in galaxies[i + 1..].iter() {
-            pairs.push((g1.to_owned(), g2.to_owned()));
+    proptest! {
+        #[test]
+        fn matches_expanded_image(image in universe(), factor in 1usize..5) {
+            let universe = Grid::parse(&image).unwrap();
+            let expected: u64 = calc_pair_distances(&expand_image(&universe, factor))
+                .iter()
+                .map(|(_, distance)| distance)
+                .sum();
+            let galaxies = get_expanded_galaxies(&universe, factor as u64).unwrap();
+            prop_assert_eq!(calc_sum_of_distances(&galaxies), Some(expected));
         }
     }
 
-    // for each pair calc the distance
-    return pairs.iter().fold(0, |acc, &(g1, g2)| {
-        let distance = g2.0.abs_diff(g1.0) + g2.1.abs_diff(g1.1);
+    #[test]
+    fn huge_factor() {
+        // galaxies on rows 0, factor + 1 and factor + 2
+        let universe = Grid::parse(b"#\n.\n#\n#\n").unwrap();
+        let galaxies = get_expanded_galaxies(&universe, 1 << 62).unwrap();
+        assert_eq!(calc_sum_of_distances(&galaxies), Some((1 << 63) + 4));
 
-        return acc + distance as u64;
-    });
-}
+        let galaxies = get_expanded_galaxies(&universe, 1 << 63).unwrap();
+        assert_eq!(galaxies[2].0, (1 << 63) + 2);
+        assert_eq!(calc_sum_of_distances(&galaxies), None);
 
-// Apply 1000000 expansion to the coordinates of the galaxies
-fn update_galaxies_after_expasion(universe: &Vec<Vec<bool>>, galaxies: &mut Vec<Coords>) -> () {
-    // find empty rows
-    let mut empty_rows: Vec<usize> = vec![];
-    for (i, row) in universe.iter().enumerate() {
-        if row.iter().all(|x| *x == false) {
-            empty_rows.push(i);
-        }
+        assert!(get_expanded_galaxies(&universe, u64::MAX).is_none());
     }
-    // find empty columns
-    let mut empty_columns: Vec<usize> = vec![];
-    for j in 0..universe[0].len() {
-        let mut is_empty = true;
-        for i in 0..universe.len() {
-            if universe[i][j] == true {
-                is_empty = false;
-                break;
-            }
-        }
 
-        if is_empty {
-            // insert another column
-            empty_columns.push(j);
-        }
+    #[test]
+    fn factor() {
+        assert_eq!(parse_factor("10"), Some(10));
+        assert_eq!(parse_factor("0"), None);
+        assert_eq!(parse_factor("-1"), None);
     }
-
-    // update coordinates of the galaxies according
-    // to the number of empty rows and columns before them
-    galaxies.iter_mut().for_each(|g| {
-        let empty_rows_before = empty_rows.iter().filter(|i| **i < g.0).count();
-        let empty_cols_before = empty_columns.iter().filter(|j| **j < g.1).count();
-
-        g.0 += 999999 * empty_rows_before;
-        g.1 += 999999 * empty_cols_before;
-    });
 }
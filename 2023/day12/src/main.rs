@@ -1,4 +1,6 @@
-use memoize::memoize;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::time::Instant;
@@ -6,6 +8,12 @@ use std::time::Instant;
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day12/src/input.txt";
+    // Unfold factor for part 2 and whether to list arrangements of the folded rows
+    let unfold_factor: usize = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(5);
+    let list_arrangements = std::env::args().any(|arg| arg == "--list");
 
     let now = Instant::now();
 
@@ -13,165 +21,342 @@ fn main() -> io::Result<()> {
     // open the file and parse input
     let file = File::open(input_path)?;
     let reader = BufReader::new(file);
-    let data: Vec<(Vec<char>, Vec<u16>)> = reader
-        .lines()
-        .map(|l| {
-            let input = l.unwrap();
-            let parts: Vec<&str> = input.split_whitespace().collect();
-            let row: Vec<char> = parts[0].chars().collect();
-            let condition_records: Vec<u16> = parts[1]
-                .split(',')
-                .map(|x| u16::from_str_radix(x, 10).unwrap())
-                .collect();
-            return (row, condition_records);
+    let mut data: Vec<(Vec<char>, Vec<u16>)> = vec![];
+    for line in reader.lines() {
+        let row =
+            parse_row(&line?).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        data.push(row);
+    }
+
+    match sum_arrangements(&data, 1) {
+        Some(value) => println!("Part1: {}", value),
+        None => println!("Part1: overflow"),
+    }
+
+    if list_arrangements {
+        for (row, condition_records) in data.iter() {
+            println!("{} {:?}", row.iter().collect::<String>(), condition_records);
+            for arrangement in Arrangements::new(row, condition_records) {
+                println!("  {}", arrangement.iter().collect::<String>());
+            }
+        }
+    }
+
+    let part2_result = sum_arrangements(&data, unfold_factor);
+    match part2_result {
+        Some(value) => println!("Part2: {}", value),
+        None => println!("Part2: overflow with unfold factor {unfold_factor}"),
+    }
+
+    println!("Elapsed: {:?}", now.elapsed());
+    Ok(())
+}
+
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    WrongFormat(String),
+    UnknownSpring(char),
+    WrongRecord(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFormat(input) => write!(f, "Wrong format of \"{input}\""),
+            ParseError::UnknownSpring(ch) => write!(f, "Unknown spring '{ch}'"),
+            ParseError::WrongRecord(record) => write!(f, "Wrong condition record \"{record}\""),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// `???.### 1,1,3`: the springs and the sizes of the groups of broken ones
+pub(crate) fn parse_row(input: &str) -> Result<(Vec<char>, Vec<u16>), ParseError> {
+    let parts: Vec<&str> = input.split_whitespace().collect();
+    let [springs, records] = parts[..] else {
+        return Err(ParseError::WrongFormat(input.to_string()));
+    };
+    let row: Vec<char> = springs.chars().collect();
+    if let Some(ch) = row.iter().find(|ch| !matches!(ch, '.' | '#' | '?')) {
+        return Err(ParseError::UnknownSpring(*ch));
+    }
+    let condition_records: Vec<u16> = records
+        .split(',')
+        .map(|x| {
+            x.parse()
+                .map_err(|_| ParseError::WrongRecord(x.to_string()))
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
+    Ok((row, condition_records))
+}
 
-    let part1_result: u64 = data
-        .iter()
+// Rows are independent, so with the `parallel` feature they are counted on all the cores.
+// The counts are collected in the input order and summed afterwards,
+// so the result and the overflow check are the same in both modes.
+fn sum_arrangements(data: &[(Vec<char>, Vec<u16>)], unfold_factor: usize) -> Option<u128> {
+    #[cfg(feature = "parallel")]
+    let rows = data.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let rows = data.iter();
+    let counts: Vec<u128> = rows
         .map(|(row, condition_records)| {
-            calc_arrangements(row.to_owned(), condition_records.to_owned())
+            let (unfolded_row, unfolded_records) = unfold(row, condition_records, unfold_factor);
+            calc_arrangements(&unfolded_row, &unfolded_records)
         })
-        .sum();
+        .collect::<Option<_>>()?;
+    counts
+        .iter()
+        .try_fold(0u128, |acc, count| acc.checked_add(*count))
+}
 
-    println!("Part1: {}", part1_result);
+// Repeat the row `factor` times separated by `?` and the records `factor` times
+fn unfold(row: &[char], condition_records: &[u16], factor: usize) -> (Vec<char>, Vec<u16>) {
+    let mut unfolded_row: Vec<char> = vec![];
+    for i in 0..factor {
+        unfolded_row.extend(row);
+        if i < factor - 1 {
+            unfolded_row.push('?');
+        }
+    }
+    (unfolded_row, condition_records.repeat(factor))
+}
 
-    let part2_result: u64 = data
-        .iter()
-        .map(|(row, condition_records)| {
-            let mut unfolded_row: Vec<char> = vec![];
-            const CYCLES: usize = 5;
-            for i in 0..CYCLES {
-                unfolded_row.extend(row);
-                if i < CYCLES - 1 {
-                    unfolded_row.push('?');
-                }
-            }
-            let unfolded_condition_records = condition_records.repeat(CYCLES);
-            calc_arrangements(unfolded_row, unfolded_condition_records)
-        })
-        .sum();
+// Number of ways to replace `?` in the row so that it matches the condition records,
+// `None` if it doesn't fit into u128.
+// It's the recurrence of `build_table`, but only the columns of the current record
+// and the next one are kept, so the memory is linear in the length of the row.
+fn calc_arrangements(row: &[char], condition_records: &[u16]) -> Option<u128> {
+    let (n, m) = (row.len(), condition_records.len());
+    let max_group = max_groups(row);
 
-    println!("Part2: {}", part2_result);
-    println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    let mut next: Vec<Option<u128>> = vec![Some(0); n + 1];
+    let mut curr: Vec<Option<u128>> = vec![Some(0); n + 1];
+    for j in (0..=m).rev() {
+        curr[n] = Some(if j == m { 1 } else { 0 });
+        for i in (0..n).rev() {
+            curr[i] = count_ways(row, condition_records, &max_group, i, j, curr[i + 1], &next);
+        }
+        std::mem::swap(&mut curr, &mut next);
+    }
+    next[0]
 }
 
-#[memoize]
-fn calc_arrangements(row: Vec<char>, condition_records: Vec<u16>) -> u64 {
-    // println!("Row: {row:?}, conditions: {condition_records:?}");
-    match condition_records.split_first() {
-        Some((first_condition, rest_conditions)) => {
-            // try different ways to fulfill the first condition
-            // for each variant calc different ways to
-            // make an arrangement with the rest of the row
-            // and the rest of the condition records
-            let variants = get_arrangement_variants(row.to_owned(), *first_condition);
-            // println!("Variants: {variants:?}");
-            return variants
-                .iter()
-                .map(|rest_of_row| {
-                    calc_arrangements(rest_of_row.to_owned(), rest_conditions.to_vec())
-                })
-                .sum();
+// table[i][j] - number of arrangements of row[i..] that match condition_records[j..]
+// or `None` if the number overflows. The full table is only needed
+// to enumerate the arrangements.
+fn build_table(row: &[char], condition_records: &[u16]) -> Vec<Vec<Option<u128>>> {
+    let (n, m) = (row.len(), condition_records.len());
+    let max_group = max_groups(row);
+
+    // columns of the table, one per record
+    let mut columns: Vec<Vec<Option<u128>>> = vec![vec![Some(0); n + 1]; m + 1];
+    columns[m][n] = Some(1);
+    for j in (0..=m).rev() {
+        for i in (0..n).rev() {
+            let next = if j < m {
+                columns[j + 1].as_slice()
+            } else {
+                &[]
+            };
+            let ways = count_ways(
+                row,
+                condition_records,
+                &max_group,
+                i,
+                j,
+                columns[j][i + 1],
+                next,
+            );
+            columns[j][i] = ways;
         }
-        None => {
-            return if has_broken(&row) { 0 } else { 1 };
+    }
+
+    (0..=n)
+        .map(|i| (0..=m).map(|j| columns[j][i]).collect())
+        .collect()
+}
+
+// Length of the possibly broken group starting at each position
+fn max_groups(row: &[char]) -> Vec<usize> {
+    let mut result: Vec<usize> = vec![0; row.len() + 1];
+    for i in (0..row.len()).rev() {
+        result[i] = if row[i] == '.' { 0 } else { result[i + 1] + 1 };
+    }
+    result
+}
+
+// One cell of the table: `same_record` is table[i + 1][j]
+// and `next_record` the column of table[..][j + 1]
+fn count_ways(
+    row: &[char],
+    condition_records: &[u16],
+    max_group: &[usize],
+    i: usize,
+    j: usize,
+    same_record: Option<u128>,
+    next_record: &[Option<u128>],
+) -> Option<u128> {
+    let n = row.len();
+    let mut ways = Some(0u128);
+    // a. it's a normal spring
+    if row[i] != '#' {
+        ways = add(ways, same_record);
+    }
+    // b. the next group of broken springs starts here
+    if row[i] != '.' && j < condition_records.len() {
+        let group = condition_records[j] as usize;
+        if max_group[i] >= group && (i + group == n || row[i + group] != '#') {
+            ways = add(ways, next_record[(i + group + 1).min(n)]);
         }
     }
+    ways
 }
 
-// find different options to make a row that contains
-// exactly `condition` broken springs in the beginning
-#[memoize]
-fn get_arrangement_variants(row: Vec<char>, condition: u16) -> Vec<Vec<char>> {
-    let mut result: Vec<Vec<char>> = vec![];
-    if row.len() == 0 {
-        return result;
+fn add(a: Option<u128>, b: Option<u128>) -> Option<u128> {
+    a?.checked_add(b?)
+}
+
+// Lazy iterator over all the concrete arrangements of a row.
+// Dead branches are cut using the table, so every step yields a valid arrangement.
+struct Arrangements<'a> {
+    row: &'a [char],
+    condition_records: &'a [u16],
+    table: Vec<Vec<Option<u128>>>,
+    // (position in the row, index of the next record, arrangement so far)
+    stack: Vec<(usize, usize, Vec<char>)>,
+}
+
+impl<'a> Arrangements<'a> {
+    fn new(row: &'a [char], condition_records: &'a [u16]) -> Arrangements<'a> {
+        let table = build_table(row, condition_records);
+        let stack = if table[0][0] != Some(0) {
+            vec![(0, 0, vec![])]
+        } else {
+            vec![]
+        };
+        Arrangements {
+            row,
+            condition_records,
+            table,
+            stack,
+        }
     }
-    // 1. skip all normal springs
-    let trimmed_row: Vec<char> = row
-        .iter()
-        .skip_while(|ch| **ch == '.')
-        .map(|x| *x)
-        .collect();
-    if trimmed_row.is_empty() {
-        return result;
-    }
-    if trimmed_row.starts_with(&['#']) {
-        // println!("Trimmed row: {trimmed_row:?}, condition: {condition}");
-        // we found the broken group - check if we can fulfill the condition
-        if trimmed_row.len() == 1 {
-            if condition == 1 {
-                // we have the last broken spring and
-                // it's fulfilling the condition
-                // push the empty row as we have nothing left
-                result.push(vec![]);
+
+    fn is_feasible(&self, i: usize, j: usize) -> bool {
+        self.table[i][j] != Some(0)
+    }
+}
+
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<char>;
+
+    fn next(&mut self) -> Option<Vec<char>> {
+        let n = self.row.len();
+        while let Some((i, j, arrangement)) = self.stack.pop() {
+            if i == n {
+                return Some(arrangement);
             }
-            return result;
-        }
-        let mut broken_count = 0;
-        for i in 0..trimmed_row.len() {
-            match trimmed_row[i] {
-                '.' => return vec![],
-                '#' | '?' => {
-                    broken_count += 1;
-                    if broken_count == condition {
-                        if i == trimmed_row.len() - 1 {
-                            // it's the end + condition is fulfilled
-                            result.push(vec![]);
-                        } else if trimmed_row[i + 1] == '#' {
-                            // next is # - the condition isn't fulfilled
-                            // we have no variants
-                        } else {
-                            // next is either . or ? - treat them as .
-                            result.push(trimmed_row[i + 2..].to_vec());
-                        }
-                        // c. else - return the only option - rest of the row
-                        return result;
+
+            if self.row[i] != '.' && j < self.condition_records.len() {
+                let group = self.condition_records[j] as usize;
+                let next_i = (i + group + 1).min(n);
+                let fits = i + group <= n
+                    && self.row[i..i + group].iter().all(|ch| *ch != '.')
+                    && (i + group == n || self.row[i + group] != '#');
+                if fits && self.is_feasible(next_i, j + 1) {
+                    let mut next = arrangement.to_owned();
+                    next.extend(std::iter::repeat_n('#', group));
+                    if i + group < n {
+                        next.push('.');
                     }
+                    self.stack.push((next_i, j + 1, next));
                 }
-                _ => panic!("Wrong char"),
+            }
+            if self.row[i] != '#' && self.is_feasible(i + 1, j) {
+                let mut next = arrangement;
+                next.push('.');
+                self.stack.push((i + 1, j, next));
             }
         }
-        // we don't have enough broken chars
-        return result;
+        None
     }
-
-    // TODO: check the edge cases here
-    // we start with ? => we have to consider two options:
-    // a. it's a normal spring
-    let variants_if_normal = get_arrangement_variants(trimmed_row[1..].to_vec(), condition);
-    // b. it's a broken spring
-    let mut modified_row = vec!['#'];
-    modified_row.extend(trimmed_row[1..].to_vec());
-    let variants_if_broken = get_arrangement_variants(modified_row.to_owned(), condition);
-    // println!("If ok: {variants_if_normal:?}, if broken: {variants_if_broken:?}");
-    result.extend(variants_if_normal);
-    result.extend(variants_if_broken);
-
-    return result;
 }
 
-fn has_broken(row: &Vec<char>) -> bool {
-    row.iter().any(|ch| *ch == '#')
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&str; 6] = [
+        "???.### 1,1,3",
+        ".??..??...?##. 1,1,3",
+        "?#?#?#?#?#?#?#? 1,3,1,6",
+        "????.#...#... 4,1,1",
+        "????.######..#####. 1,6,5",
+        "?###???????? 3,2,1",
+    ];
+
+    fn example() -> Vec<(Vec<char>, Vec<u16>)> {
+        EXAMPLE
+            .iter()
+            .map(|line| parse_row(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn example_answers() {
+        let data = example();
+        let counts: Vec<u128> = data
+            .iter()
+            .map(|(row, records)| calc_arrangements(row, records).unwrap())
+            .collect();
+        assert_eq!(counts, vec![1, 4, 1, 1, 4, 10]);
+        assert_eq!(sum_arrangements(&data, 1), Some(21));
+        assert_eq!(sum_arrangements(&data, 5), Some(525152));
+    }
 
-fn find_broken_groups(row: &Vec<char>) -> Vec<u16> {
-    let mut result: Vec<u16> = vec![];
-    let mut curr_count = 0u16;
-    for ch in row.iter() {
-        match ch {
-            '.' | '?' => {
-                if curr_count > 0 {
-                    result.push(curr_count);
+    #[test]
+    fn arrangements_match_records() {
+        for (row, records) in example() {
+            for factor in 1..=2 {
+                let (row, records) = unfold(&row, &records, factor);
+                let arrangements: Vec<Vec<char>> = Arrangements::new(&row, &records).collect();
+                for arrangement in arrangements.iter() {
+                    assert_eq!(arrangement.len(), row.len());
+                    assert_eq!(find_broken_groups(arrangement), records);
                 }
-                curr_count = 0;
+                let count = calc_arrangements(&row, &records).unwrap();
+                assert_eq!(arrangements.len() as u128, count);
+                assert_eq!(build_table(&row, &records)[0][0], Some(count));
             }
-            '#' => curr_count += 1,
-            _ => panic!("Unknown character"),
         }
     }
 
-    return result;
+    #[test]
+    fn overflow() {
+        let (row, records) = unfold(&['?'; 4], &[1], 100);
+        assert_eq!(calc_arrangements(&row, &records), None);
+    }
+
+    fn find_broken_groups(row: &[char]) -> Vec<u16> {
+        let mut result: Vec<u16> = vec![];
+        let mut curr_count = 0u16;
+        for ch in row.iter() {
+            match ch {
+                '.' | '?' => {
+                    if curr_count > 0 {
+                        result.push(curr_count);
+                    }
+                    curr_count = 0;
+                }
+                '#' => curr_count += 1,
+                _ => panic!("Unknown character"),
+            }
+        }
+        if curr_count > 0 {
+            result.push(curr_count);
+        }
+
+        result
+    }
 }
@@ -1,263 +1,232 @@
-use std::fs::File;
-use std::io::{self, Read};
+use grid::{Grid, GridError, Input};
+use std::fmt;
+use std::io;
 use std::time::Instant;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day13/src/input.txt";
+    let verbose = std::env::args().any(|arg| arg == "--verbose");
 
     let now = Instant::now();
 
     // Part1
-    // open the file and parse input
-    let mut file = File::open(input_path)?;
-    let mut input = String::from("");
-    let _ = file.read_to_string(&mut input);
-    let patterns: Vec<Vec<&str>> = input
+    // map the input into memory and parse the patterns without copying them
+    let input = Input::open(input_path)?;
+    let input = std::str::from_utf8(&input)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let patterns: Vec<Pattern> = input
         .split("\n\n")
-        .map(|x| {
-            let rows: Vec<&str> = x.trim().split("\n").collect();
-            return rows;
-        })
-        .collect();
+        .map(|x| Pattern::from_str(x.trim()))
+        .collect::<Result<_, _>>()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
-    let mirrors: Vec<(u64, u64)> = patterns
+    let part1_result: u64 = patterns
         .iter()
-        .map(|pattern| calc_mirror(pattern))
-        .collect();
-    let part1_result: u64 = mirrors.iter().map(|x| x.0).sum();
-
+        .map(|p| summarize(&p.find_reflections(0)))
+        .sum();
     println!("Part1: {}", part1_result);
 
     // Part 2
-    let part2_result: u64 = mirrors.iter().map(|x| x.1).sum();
-
-    // 300 + 100 + 400 + 300 + 0
+    let part2_result: u64 = patterns
+        .iter()
+        .map(|p| summarize(&p.find_reflections(1)))
+        .sum();
     println!("Part2: {}", part2_result);
 
+    if verbose {
+        for (idx, pattern) in patterns.iter().enumerate() {
+            for reflection in pattern.find_reflections(1) {
+                println!(
+                    "Pattern #{idx}: {:?} reflection after {}, smudges at {:?}",
+                    reflection.axis, reflection.position, reflection.smudges
+                );
+            }
+        }
+    }
+
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
 }
 
-fn calc_mirror(pattern: &Vec<&str>) -> (u64, u64) {
-    // check rows - find two rows that are equal
-    let mut row1_indices: Vec<usize> = vec![];
-    for i in 0..pattern.len() - 1 {
-        if pattern[i] == pattern[i + 1] {
-            row1_indices.push(i);
-        }
-    }
-    // then go backwords from the potential mirrors and check if relative mirrored rows are equal
-    let part1_row_mirror = row1_indices.iter().find(|x| is_mirror_row(**x, pattern));
+// Rows and columns of the pattern as bitmasks, bit `i` is set for `#` at position `i`
+pub(crate) struct Pattern {
+    rows: Vec<u64>,
+    cols: Vec<u64>,
+}
 
-    // find part2 mirror indice
-    let mut row2_indices: Vec<usize> = vec![];
-    for i in 0..pattern.len() - 1 {
-        if are_almost_equal_rows(i, i + 1, pattern) != AlmostEqual::NotEqual
-            && (part1_row_mirror.is_none() || *part1_row_mirror.unwrap() != i)
-        {
-            row2_indices.push(i);
-        }
-    }
-    let part2_row_mirror = row2_indices.iter().find(|x| is_mirror_row_2(**x, pattern));
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    Grid(GridError),
+    // the pattern doesn't fit into u64 masks
+    TooLarge { height: usize, width: usize },
+}
 
-    // find columns where necessary
-    let mut part1_col_mirror: Option<&usize> = None;
-    let mut col1_indices: Vec<usize> = vec![];
-    if part1_row_mirror.is_none() {
-        for j in 0..pattern[0].len() - 1 {
-            if pattern
-                .iter()
-                .all(|row| row.as_bytes()[j] == row.as_bytes()[j + 1])
-            {
-                col1_indices.push(j);
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Grid(err) => write!(f, "{err}"),
+            ParseError::TooLarge { height, width } => {
+                write!(f, "Pattern of {height}x{width} is larger than 64x64")
             }
         }
-        part1_col_mirror = col1_indices.iter().find(|x| is_mirror_col(**x, pattern));
     }
+}
 
-    let mut part2_col_mirror: Option<&usize> = None;
-    let mut col2_indices: Vec<usize> = vec![];
-    if part2_row_mirror.is_none() {
-        for j in 0..pattern[0].len() - 1 {
-            if are_almost_equal_cols(j, j + 1, pattern) != AlmostEqual::NotEqual
-                && (part1_col_mirror.is_none() || *part1_col_mirror.unwrap() != j)
-            {
-                col2_indices.push(j);
-            }
-        }
-        part2_col_mirror = col2_indices.iter().find(|x| is_mirror_col_2(**x, pattern));
-    }
+impl std::error::Error for ParseError {}
 
-    let part1 = if part1_row_mirror.is_some() {
-        (*part1_row_mirror.unwrap() + 1) * 100
-    } else if part1_col_mirror.is_some() {
-        *part1_col_mirror.unwrap() + 1
-    } else {
-        0
-    };
-    let part2 = if part2_row_mirror.is_some() {
-        (*part2_row_mirror.unwrap() + 1) * 100
-    } else if part2_col_mirror.is_some() {
-        *part2_col_mirror.unwrap() + 1
-    } else {
-        0
-    };
-    return (part1 as u64, part2 as u64);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    // horizontal line between two rows
+    Row,
+    // vertical line between two columns
+    Col,
 }
 
-fn is_mirror_row(idx: usize, pattern: &Vec<&str>) -> bool {
-    // check that all rows above and below are mirrored
-    let mut upper = idx as i32;
-    let mut lower = idx + 1;
-    while upper >= 0 && lower < pattern.len() {
-        if pattern[upper as usize] != pattern[lower] {
-            return false;
-        }
-        upper -= 1;
-        lower += 1;
-    }
-    return true;
+#[derive(Debug)]
+struct Reflection {
+    axis: Axis,
+    // number of rows above or columns to the left of the line
+    position: usize,
+    // (row, col) of the cells that have to be flipped to make the reflection perfect,
+    // the cell on the upper or left side of the line is reported
+    smudges: Vec<(usize, usize)>,
 }
 
-fn is_mirror_col(idx: usize, pattern: &Vec<&str>) -> bool {
-    // check that all columns to the left and right are mirrored
-    let mut left = idx as i32;
-    let mut right = idx + 1;
-    while left >= 0 && right < pattern[0].len() {
-        if pattern
-            .iter()
-            .any(|row| row.as_bytes()[left as usize] != row.as_bytes()[right])
-        {
-            return false;
+impl Pattern {
+    pub(crate) fn from_str(input: &str) -> Result<Pattern, ParseError> {
+        let grid = Grid::parse(input.as_bytes()).map_err(ParseError::Grid)?;
+        let (height, width) = (grid.height(), grid.width());
+        if height > 64 || width > 64 {
+            return Err(ParseError::TooLarge { height, width });
         }
-        left -= 1;
-        right += 1;
-    }
-    return true;
-}
 
-// Part 2
-#[derive(PartialEq, Eq)]
-enum AlmostEqual {
-    Equal,
-    Almost,
-    NotEqual,
-}
-fn calc_mirror_2(pattern: &Vec<&str>) -> u64 {
-    // check rows - find two rows that are equal or have one smudge
-    let mut mirror_indices: Vec<usize> = vec![];
-    for i in 0..pattern.len() - 1 {
-        if are_almost_equal_rows(i, i + 1, pattern) != AlmostEqual::NotEqual {
-            mirror_indices.push(i);
+        let mut rows = vec![0u64; height];
+        let mut cols = vec![0u64; width];
+        for (i, row) in grid.rows().enumerate() {
+            for (j, ch) in row.iter().enumerate() {
+                if *ch == b'#' {
+                    rows[i] |= 1 << j;
+                    cols[j] |= 1 << i;
+                }
+            }
         }
-    }
-    // then go backwords from the potential mirrors and check if relative mirrored rows are equal
-    let mirror = mirror_indices
-        .iter()
-        .find(|x| is_mirror_row_2(**x, pattern));
-    if mirror.is_some() {
-        return ((mirror.unwrap() + 1) * 100) as u64;
+        Ok(Pattern { rows, cols })
     }
 
-    // repeat for columns if no row-mirror is found
-    mirror_indices = vec![];
-    for j in 0..pattern[0].len() - 1 {
-        if are_almost_equal_cols(j, j + 1, pattern) != AlmostEqual::NotEqual {
-            mirror_indices.push(j);
+    // All the reflection lines that need exactly `smudges` cells to be fixed.
+    // Lines between rows go first, then lines between columns.
+    fn find_reflections(&self, smudges: u32) -> Vec<Reflection> {
+        let mut result: Vec<Reflection> = vec![];
+        for (axis, lines) in [(Axis::Row, &self.rows), (Axis::Col, &self.cols)] {
+            for position in 1..lines.len() {
+                if count_differences(lines, position, smudges) == Some(smudges) {
+                    result.push(Reflection {
+                        axis,
+                        position,
+                        smudges: self.locate_smudges(axis, position),
+                    });
+                }
+            }
         }
+        result
     }
-    let mirror = mirror_indices
-        .iter()
-        .find(|x| is_mirror_col_2(**x, pattern));
-    if mirror.is_some() {
-        return (mirror.unwrap() + 1) as u64;
-    }
-    return 0;
-}
 
-fn are_almost_equal_rows(idx1: usize, idx2: usize, pattern: &Vec<&str>) -> AlmostEqual {
-    if pattern[idx1] == pattern[idx2] {
-        return AlmostEqual::Equal;
-    }
-
-    let mut smudge_found = false;
-    for j in 0..pattern[0].len() {
-        if pattern[idx1].as_bytes()[j] != pattern[idx2].as_bytes()[j] {
-            if smudge_found {
-                return AlmostEqual::NotEqual;
+    fn locate_smudges(&self, axis: Axis, position: usize) -> Vec<(usize, usize)> {
+        let lines = match axis {
+            Axis::Row => &self.rows,
+            Axis::Col => &self.cols,
+        };
+        let mut result: Vec<(usize, usize)> = vec![];
+        for (before, after) in (0..position).rev().zip(position..lines.len()) {
+            let mut diff = lines[before] ^ lines[after];
+            while diff != 0 {
+                let other = diff.trailing_zeros() as usize;
+                result.push(match axis {
+                    Axis::Row => (before, other),
+                    Axis::Col => (other, before),
+                });
+                diff &= diff - 1;
             }
-            smudge_found = true;
         }
+        result
     }
-
-    return AlmostEqual::Almost;
 }
 
-fn is_mirror_row_2(idx: usize, pattern: &Vec<&str>) -> bool {
-    // check that all rows above and below are mirrored
-    let mut upper = idx as i32;
-    let mut lower = idx + 1;
-    let mut has_smudge = false;
-    while upper >= 0 && lower < pattern.len() {
-        match are_almost_equal_rows(upper as usize, lower, pattern) {
-            AlmostEqual::NotEqual => {
-                return false;
-            }
-            AlmostEqual::Almost => {
-                if has_smudge {
-                    return false;
-                } else {
-                    has_smudge = true;
-                }
-            }
-            AlmostEqual::Equal => (),
+// Number of differing cells between the lines mirrored around `position`,
+// `None` as soon as it exceeds the budget
+fn count_differences(lines: &[u64], position: usize, budget: u32) -> Option<u32> {
+    let mut result = 0;
+    for (before, after) in (0..position).rev().zip(position..lines.len()) {
+        result += (lines[before] ^ lines[after]).count_ones();
+        if result > budget {
+            return None;
         }
-        upper -= 1;
-        lower += 1;
     }
-    return true;
+    Some(result)
 }
 
-fn are_almost_equal_cols(idx1: usize, idx2: usize, pattern: &Vec<&str>) -> AlmostEqual {
-    let mut smudge_found = false;
-    for i in 0..pattern.len() {
-        if pattern[i].as_bytes()[idx1] != pattern[i].as_bytes()[idx2] {
-            if smudge_found {
-                return AlmostEqual::NotEqual;
-            }
-            smudge_found = true;
-        }
+// Puzzle answer for a pattern: lines above a horizontal mirror times 100
+// or lines to the left of a vertical one
+fn summarize(reflections: &[Reflection]) -> u64 {
+    match reflections.first() {
+        Some(Reflection {
+            axis: Axis::Row,
+            position,
+            ..
+        }) => *position as u64 * 100,
+        Some(Reflection {
+            axis: Axis::Col,
+            position,
+            ..
+        }) => *position as u64,
+        None => 0,
     }
-
-    return if smudge_found {
-        AlmostEqual::Almost
-    } else {
-        AlmostEqual::Equal
-    };
 }
 
-fn is_mirror_col_2(idx: usize, pattern: &Vec<&str>) -> bool {
-    // check that all cols are mirrored
-    let mut left = idx as i32;
-    let mut right = idx + 1;
-    let mut has_smudge = false;
-    while left >= 0 && right < pattern[0].len() {
-        match are_almost_equal_cols(left as usize, right, pattern) {
-            AlmostEqual::NotEqual => {
-                return false;
-            }
-            AlmostEqual::Almost => {
-                if has_smudge {
-                    return false;
-                } else {
-                    has_smudge = true;
-                }
-            }
-            AlmostEqual::Equal => (),
-        }
-        left -= 1;
-        right += 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the test input starts with the two patterns of the puzzle example
+    fn example_total(smudges: u32) -> u64 {
+        include_str!("input_test.txt")
+            .split("\n\n")
+            .take(2)
+            .map(|x| Pattern::from_str(x.trim()).unwrap())
+            .map(|p| summarize(&p.find_reflections(smudges)))
+            .sum()
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(example_total(0), 405);
+        assert_eq!(example_total(1), 400);
+    }
+
+    #[test]
+    fn no_reflection() {
+        let pattern = Pattern::from_str("#.\n..").unwrap();
+        assert!(pattern.find_reflections(0).is_empty());
+        assert_eq!(summarize(&pattern.find_reflections(0)), 0);
+    }
+
+    #[test]
+    fn widest_pattern() {
+        // identical columns mirror around every vertical line
+        let pattern =
+            Pattern::from_str(&format!("{}\n{}", "#".repeat(64), ".".repeat(64))).unwrap();
+        let reflections = pattern.find_reflections(0);
+        assert_eq!(reflections.len(), 63);
+        assert_eq!(summarize(&reflections), 1);
+
+        let row = "#".repeat(65);
+        assert!(matches!(
+            Pattern::from_str(&format!("{row}\n{row}")),
+            Err(ParseError::TooLarge {
+                height: 2,
+                width: 65
+            })
+        ));
     }
-    return true;
 }
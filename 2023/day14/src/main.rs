@@ -1,203 +1,267 @@
-use memoize::memoize;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
+use grid::{Grid, Input};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
 use std::time::Instant;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day14/src/input.txt";
+    let animate = std::env::args().any(|arg| arg == "--animate");
 
     let now = Instant::now();
 
     // Part1
-    // open the file and parse input
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let data: Vec<Vec<char>> = reader
-        .lines()
-        .map(|l| {
-            let input = l.unwrap();
-            return input.chars().collect();
-        })
-        .collect();
-
-    // roll to north
-    let data_after_roll = roll_to_north(&data);
+    // map the input into memory and use it as a grid without copying
+    let input = Input::open(input_path)?;
+    let data =
+        Grid::parse(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let platform = Platform::from_grid(&data)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
-    // calc total load
-    let part1_result = calc_total_load(&data_after_roll);
+    // roll to north and calc total load
+    let mut tilted = platform.clone();
+    tilted.tilt(Direction::North);
+    let part1_result = tilted.total_load();
 
     println!("Part1: {}", part1_result);
 
     // Part 2
-    let mut updated_data: Vec<Vec<char>> = data.to_owned();
-    let mut results: Vec<u64> = vec![];
-    for _ in 0..1_000 {
-        results.push(calc_total_load(&updated_data));
-        updated_data = spin_cycle(updated_data);
-    }
-    // find cycle in the data and calculate the result based on this cycle
-    // 1 2 1 2 1; cycle: 1, 2
-    // result after 3 cycles has index 3
-    // result value = start + (full_index - start) % len ==
-    // == 1 + (3 - 1) % 2 == 1
-    match find_cycle(&results) {
-        Some((start, len)) => {
-            let result_idx = start + (1_000_000_000 - start) % len;
-            println!("Part2 result: {}", results[result_idx]);
+    if animate {
+        let (start, len) = platform.find_cycle();
+        let mut state = platform.clone();
+        for cycle in 0..start + len {
+            print!("{}", animation_frame(cycle, &state));
+            state.spin_cycle();
         }
-        None => println!("No cycle in the results list"),
     }
+    let part2_result = platform.load_after_cycles(1_000_000_000);
+    println!("Part2: {}", part2_result);
 
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
 }
 
-fn find_cycle(data: &Vec<u64>) -> Option<(usize, usize)> {
-    for start in 0..data.len() {
-        for len in 1..(data.len() / 2) {
-            if is_cycle(data, start, len) {
-                return Some((start, len));
-            }
-        }
-    }
-    return None;
+// Every frame starts by clearing the screen and moving the cursor to the top left corner
+fn animation_frame(cycle: usize, state: &Platform) -> String {
+    format!("\x1b[2J\x1b[HCycle {cycle}\n{}", state.render())
 }
 
-fn is_cycle(data: &Vec<u64>, start: usize, len: usize) -> bool {
-    if (data.len() - start) % len != 0 {
-        return false;
-    }
+#[derive(Clone, Copy, Debug)]
+enum Direction {
+    North,
+    West,
+    South,
+    East,
+}
 
-    let cycles_count = (data.len() - start) / len;
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    TooWide(usize),
+    UnknownCell(char),
+}
 
-    for i in 0..len {
-        for j in 1..cycles_count {
-            if data[start + j * len + i] != data[start + i] {
-                // not a cycle
-                return false;
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::TooWide(width) => {
+                write!(f, "Platform of width {width} doesn't fit into u128 rows")
             }
+            ParseError::UnknownCell(ch) => write!(f, "Unknown cell '{ch}'"),
         }
     }
-    return true;
 }
 
-#[memoize]
-fn spin_cycle(data: Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let north = roll_to_north(&data);
-    let west = roll_to_west(&north);
-    let south = roll_to_south(&west);
-    let east = roll_to_east(&south);
-    return east;
+impl std::error::Error for ParseError {}
+
+// The platform as bitboards: one u128 per row, bit `j` stands for column `j`
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Platform {
+    width: usize,
+    round: Vec<u128>,
+    cube: Vec<u128>,
 }
 
-fn roll_to_north(data: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    // for each column move all round stones
-    // upwards untill the edge or sharp rock
-    let mut result: Vec<Vec<char>> = vec![vec!['.'; data[0].len()]; data.len()];
-    for j in 0..data[0].len() {
-        for i in 0..data.len() {
-            if data[i][j] == 'O' {
-                // find the correct row for the stone
-                let mut new_i = i;
-                while new_i > 0 && result[new_i - 1][j] == '.' {
-                    new_i -= 1;
-                }
-                // put the stone into result
-                result[new_i][j] = 'O';
-            } else {
-                result[i][j] = data[i][j];
-            }
+impl Platform {
+    pub(crate) fn from_grid(data: &Grid) -> Result<Platform, ParseError> {
+        let width = data.width();
+        if width > 128 {
+            return Err(ParseError::TooWide(width));
+        }
+        if let Some(ch) = data.rows().flatten().find(|ch| !b"O#.".contains(ch)) {
+            return Err(ParseError::UnknownCell(*ch as char));
         }
+        let to_mask = |row: &[u8], rock: u8| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, ch)| **ch == rock)
+                .fold(0u128, |acc, (j, _)| acc | 1 << j)
+        };
+        Ok(Platform {
+            width,
+            round: data.rows().map(|row| to_mask(row, b'O')).collect(),
+            cube: data.rows().map(|row| to_mask(row, b'#')).collect(),
+        })
     }
 
-    return result;
-}
-fn roll_to_west(data: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    // for each row move all round stones
-    // to the left untill the edge or sharp rock
-    let mut result: Vec<Vec<char>> = vec![vec!['.'; data[0].len()]; data.len()];
-    for i in 0..data.len() {
-        for j in 0..data[0].len() {
-            if data[i][j] == 'O' {
-                // find the correct col for the stone
-                let mut new_j = j;
-                while new_j > 0 && result[i][new_j - 1] == '.' {
-                    new_j -= 1;
+    fn row_mask(&self) -> u128 {
+        if self.width == 128 {
+            u128::MAX
+        } else {
+            (1 << self.width) - 1
+        }
+    }
+
+    // Roll all the round rocks as far as they go.
+    // All the rocks that can move make one step at once until nobody moves.
+    fn tilt(&mut self, direction: Direction) {
+        match direction {
+            Direction::North => self.tilt_vertically((1..self.round.len()).collect(), -1),
+            Direction::South => self.tilt_vertically((0..self.round.len() - 1).rev().collect(), 1),
+            Direction::West | Direction::East => {
+                let mask = self.row_mask();
+                for i in 0..self.round.len() {
+                    loop {
+                        let free = !(self.round[i] | self.cube[i]) & mask;
+                        let movable = match direction {
+                            Direction::West => self.round[i] & (free << 1),
+                            _ => self.round[i] & (free >> 1),
+                        };
+                        if movable == 0 {
+                            break;
+                        }
+                        let moved = match direction {
+                            Direction::West => movable >> 1,
+                            _ => movable << 1,
+                        };
+                        self.round[i] = (self.round[i] & !movable) | moved;
+                    }
                 }
-                // put the stone into result
-                result[i][new_j] = 'O';
-            } else {
-                result[i][j] = data[i][j];
             }
         }
     }
 
-    return result;
-}
-fn roll_to_south(data: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    // for each column
-    // move all round stones (starting from the last row)
-    // downwards untill the edge or sharp rock
-    let mut result: Vec<Vec<char>> = vec![vec!['.'; data[0].len()]; data.len()];
-    for j in 0..data[0].len() {
-        for i in (0..data.len()).rev() {
-            if data[i][j] == 'O' {
-                // find the correct row for the stone
-                let mut new_i = i;
-                while new_i < data.len() - 1 && result[new_i + 1][j] == '.' {
-                    new_i += 1;
+    // `rows` are the ones that can move, `step` is the shift to the next row
+    fn tilt_vertically(&mut self, rows: Vec<usize>, step: isize) {
+        loop {
+            let mut is_changed = false;
+            for i in rows.iter() {
+                let next = (*i as isize + step) as usize;
+                let movable = self.round[*i] & !(self.round[next] | self.cube[next]);
+                if movable != 0 {
+                    self.round[next] |= movable;
+                    self.round[*i] &= !movable;
+                    is_changed = true;
                 }
-                // put the stone into result
-                result[new_i][j] = 'O';
-            } else {
-                result[i][j] = data[i][j];
+            }
+            if !is_changed {
+                break;
             }
         }
     }
 
-    return result;
-}
-fn roll_to_east(data: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    // for each row move all round stones
-    // to the right until the edge or sharp rock
-    let mut result: Vec<Vec<char>> = vec![vec!['.'; data[0].len()]; data.len()];
-    for i in 0..data.len() {
-        for j in (0..data[0].len()).rev() {
-            if data[i][j] == 'O' {
-                // find the correct col for the stone
-                let mut new_j = j;
-                while new_j < data[0].len() - 1 && result[i][new_j + 1] == '.' {
-                    new_j += 1;
-                }
-                // put the stone into result
-                result[i][new_j] = 'O';
-            } else {
-                result[i][j] = data[i][j];
+    fn spin_cycle(&mut self) {
+        for direction in [
+            Direction::North,
+            Direction::West,
+            Direction::South,
+            Direction::East,
+        ] {
+            self.tilt(direction);
+        }
+    }
+
+    fn total_load(&self) -> u64 {
+        let height = self.round.len();
+        self.round
+            .iter()
+            .enumerate()
+            .map(|(i, row)| (height - i) as u64 * row.count_ones() as u64)
+            .sum()
+    }
+
+    // (number of spin cycles before the states start repeating, length of the repetition)
+    fn find_cycle(&self) -> (usize, usize) {
+        self.run_until_repeat().1
+    }
+
+    // All the states until the first repeated one and the cycle they form
+    fn run_until_repeat(&self) -> (Vec<Platform>, (usize, usize)) {
+        let mut seen: HashMap<Platform, usize> = HashMap::new();
+        let mut states: Vec<Platform> = vec![];
+        let mut state = self.clone();
+        loop {
+            if let Some(start) = seen.get(&state) {
+                return (states, (*start, seen.len() - start));
             }
+            seen.insert(state.clone(), states.len());
+            states.push(state.clone());
+            state.spin_cycle();
         }
     }
 
-    return result;
-}
+    fn load_after_cycles(&self, cycles: usize) -> u64 {
+        let (states, (start, len)) = self.run_until_repeat();
+        let idx = if cycles < start {
+            cycles
+        } else {
+            start + (cycles - start) % len
+        };
+        states[idx].total_load()
+    }
 
-fn calc_total_load(data: &Vec<Vec<char>>) -> u64 {
-    let mut result = 0;
-    for i in 0..data.len() {
-        let coeff = (data.len() - i) as u64;
-        for j in 0..data[i].len() {
-            if data[i][j] == 'O' {
-                result += coeff;
+    fn render(&self) -> String {
+        let mut result = String::new();
+        for (round, cube) in self.round.iter().zip(self.cube.iter()) {
+            for j in 0..self.width {
+                result.push(if round >> j & 1 == 1 {
+                    'O'
+                } else if cube >> j & 1 == 1 {
+                    '#'
+                } else {
+                    '.'
+                });
             }
+            result.push('\n');
         }
+        result
     }
-    return result;
 }
 
-fn print_state(data: &Vec<Vec<char>>) -> () {
-    data.iter().for_each(|row| {
-        let str: String = row.iter().collect();
-        println!("{str}");
-    });
-    println!();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Platform {
+        let data = Grid::parse(include_bytes!("input_test.txt")).unwrap();
+        Platform::from_grid(&data).unwrap()
+    }
+
+    #[test]
+    fn cycles() {
+        let platform = example();
+        let mut tilted = platform.clone();
+        tilted.tilt(Direction::North);
+        assert_eq!(tilted.total_load(), 136);
+        assert_eq!(platform.find_cycle(), (3, 7));
+        assert_eq!(platform.load_after_cycles(1_000_000_000), 64);
+    }
+
+    #[test]
+    fn frame() {
+        let mut state = example();
+        state.spin_cycle();
+        let expected = "\x1b[2J\x1b[HCycle 1\n\
+            .....#....\n\
+            ....#...O#\n\
+            ...OO##...\n\
+            .OO#......\n\
+            .....OOO#.\n\
+            .O#...O#.#\n\
+            ....O#....\n\
+            ......OOOO\n\
+            #...O###..\n\
+            #..OO#....\n";
+        assert_eq!(animation_frame(1, &state), expected);
+    }
 }
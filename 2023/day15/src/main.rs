@@ -1,6 +1,5 @@
-use core::panic;
-use regex::Regex;
-use std::collections::VecDeque;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufReader, Read};
 use std::time::Instant;
@@ -8,6 +7,7 @@ use std::time::Instant;
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day15/src/input.txt";
+    let verbose = std::env::args().any(|arg| arg == "--verbose");
 
     let now = Instant::now();
 
@@ -19,104 +19,318 @@ fn main() -> io::Result<()> {
     let _ = reader.read_to_string(&mut input);
     let data: Vec<&str> = input.trim().split(',').collect();
 
-    let part1_result = data.iter().fold(0, |acc, x| acc + calc_hash(*x));
+    let part1_result = data.iter().fold(0, |acc, x| acc + calc_hash(x));
     println!("Part1: {}", part1_result);
 
     // Part 2
-    let init_seq = parse_initialization_sequence(&data);
-    let boxes = initialize(&init_seq);
-    let part2_result = calc_focusing_power(&boxes);
-    println!("Part2: {}", part2_result);
+    let init_seq = parse_initialization_sequence(&data)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let mut boxes = LensHashMap::new();
+    for command in init_seq.iter() {
+        boxes.apply(command);
+        if verbose {
+            println!("After \"{command}\":");
+            print!("{boxes}");
+            println!();
+        }
+    }
+    match boxes.focusing_power() {
+        Some(value) => println!("Part2: {}", value),
+        None => println!("Part2: overflow"),
+    }
+    if verbose {
+        println!("{:?}", boxes.stats);
+    }
 
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
 }
 
-fn calc_hash(str: &str) -> u32 {
+pub(crate) fn calc_hash(str: &str) -> u32 {
     str.chars()
         .fold(0, |acc, x| ((acc + (x as u32)) * 17) % 256)
 }
 
-fn parse_initialization_sequence(data: &Vec<&str>) -> Vec<Command> {
-    let re = Regex::new(r"([a-z]+)([\=|\-])([0-9]*)").unwrap();
-    data.iter()
-        .map(|x| {
-            let captures = re.captures(*x).unwrap();
-            let label = captures.get(1).unwrap().as_str();
-            let operation = captures.get(2).unwrap().as_str();
-            let focal_length = captures.get(3);
-            match operation {
-                "=" => Command::Set(Lense {
-                    label: String::from(label),
-                    focal_length: u32::from_str_radix(focal_length.unwrap().as_str(), 10).unwrap(),
-                }),
-                "-" => Command::Remove(String::from(label)),
-                _ => panic!("Unknown command"),
-            }
-        })
-        .collect()
+pub(crate) fn parse_initialization_sequence(data: &[&str]) -> Result<Vec<Command>, ParseError> {
+    data.iter().map(|x| Command::from_str(x)).collect()
 }
 
-fn initialize(initialization_sequence: &Vec<Command>) -> Vec<VecDeque<Lense>> {
-    let mut result: Vec<VecDeque<Lense>> = vec![VecDeque::new(); 256];
-    for command in initialization_sequence.iter() {
-        apply_command(command, &mut result);
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    FocalLength(String),
+    Label(String),
+    UnknownCommand(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::FocalLength(input) => write!(f, "Wrong focal length in \"{input}\""),
+            ParseError::Label(input) => write!(f, "Wrong label in \"{input}\""),
+            ParseError::UnknownCommand(input) => write!(f, "Unknown command \"{input}\""),
+        }
     }
-    return result;
 }
 
-fn apply_command(command: &Command, boxes: &mut Vec<VecDeque<Lense>>) -> () {
-    match command {
-        Command::Set(Lense {
-            label,
-            focal_length,
-        }) => {
-            let hash = calc_hash(&label);
-            let b = boxes.get_mut(hash as usize).unwrap();
-            match b.iter().position(|l| *l.label == *label) {
-                // lense already present - update with new focal length
-                Some(idx) => {
-                    b[idx].focal_length = *focal_length;
-                }
-                // otherwise - add it to the end
-                None => b.push_back(Lense {
-                    label: label.to_owned(),
-                    focal_length: *focal_length,
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Lense {
+    label: String,
+    focal_length: u32,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Command {
+    Set(Lense),
+    Remove(String),
+}
+
+impl Command {
+    // Either `label=focal_length` or `label-`
+    fn from_str(input: &str) -> Result<Command, ParseError> {
+        let (label, command) = if let Some((label, focal_length)) = input.split_once('=') {
+            let focal_length = focal_length
+                .parse()
+                .map_err(|_| ParseError::FocalLength(input.to_string()))?;
+            (
+                label,
+                Command::Set(Lense {
+                    label: label.to_string(),
+                    focal_length,
                 }),
+            )
+        } else if let Some(label) = input.strip_suffix('-') {
+            (label, Command::Remove(label.to_string()))
+        } else {
+            return Err(ParseError::UnknownCommand(input.to_string()));
+        };
+
+        if label.is_empty() || !label.chars().all(|ch| ch.is_ascii_lowercase()) {
+            return Err(ParseError::Label(input.to_string()));
+        }
+        Ok(command)
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Set(lense) => write!(f, "{}={}", lense.label, lense.focal_length),
+            Command::Remove(label) => write!(f, "{label}-"),
+        }
+    }
+}
+
+const BOXES_COUNT: usize = 256;
+
+// The HASHMAP from the puzzle: 256 boxes of lenses.
+// Lenses live in an arena and every box is a doubly linked list over it,
+// so lookup by label, update, removal and insertion are all O(1)
+// while each box keeps the order the lenses were put into it.
+struct LensHashMap {
+    slots: Vec<Slot>,
+    free_slots: Vec<usize>,
+    // label -> slot in the arena
+    index: HashMap<String, usize>,
+    // (first slot, last slot) of every box
+    boxes: Vec<Option<(usize, usize)>>,
+    stats: Stats,
+}
+
+struct Slot {
+    lense: Lense,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    inserted: usize,
+    updated: usize,
+    removed: usize,
+    missed_removals: usize,
+}
+
+impl LensHashMap {
+    fn new() -> LensHashMap {
+        LensHashMap {
+            slots: vec![],
+            free_slots: vec![],
+            index: HashMap::new(),
+            boxes: vec![None; BOXES_COUNT],
+            stats: Stats::default(),
+        }
+    }
+
+    fn apply(&mut self, command: &Command) {
+        match command {
+            Command::Set(lense) => self.insert(lense.to_owned()),
+            Command::Remove(label) => {
+                self.remove(label);
             }
         }
-        Command::Remove(label) => {
-            let hash = calc_hash(label);
-            let b = boxes.get_mut(hash as usize).unwrap();
-            match b.iter().position(|l| *l.label == *label) {
-                // lense is present - remove it
-                Some(idx) => {
-                    b.remove(idx);
-                }
-                // otherwise - do nothing
-                None => (),
+    }
+
+    // Replace the lense with the same label in place or put the new one to the end of its box
+    fn insert(&mut self, lense: Lense) {
+        if let Some(slot) = self.index.get(&lense.label) {
+            self.slots[*slot].lense.focal_length = lense.focal_length;
+            self.stats.updated += 1;
+            return;
+        }
+
+        let box_idx = calc_hash(&lense.label) as usize;
+        let tail = self.boxes[box_idx].map(|(_, last)| last);
+        let label = lense.label.to_owned();
+        let new_slot = Slot {
+            lense,
+            prev: tail,
+            next: None,
+        };
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                self.slots[slot] = new_slot;
+                slot
+            }
+            None => {
+                self.slots.push(new_slot);
+                self.slots.len() - 1
             }
+        };
+
+        self.boxes[box_idx] = match self.boxes[box_idx] {
+            Some((first, last)) => {
+                self.slots[last].next = Some(slot);
+                Some((first, slot))
+            }
+            None => Some((slot, slot)),
+        };
+        self.index.insert(label, slot);
+        self.stats.inserted += 1;
+    }
+
+    fn remove(&mut self, label: &str) -> Option<Lense> {
+        let Some(slot) = self.index.remove(label) else {
+            self.stats.missed_removals += 1;
+            return None;
+        };
+
+        let box_idx = calc_hash(label) as usize;
+        let (prev, next) = (self.slots[slot].prev, self.slots[slot].next);
+        if let Some(p) = prev {
+            self.slots[p].next = next;
         }
+        if let Some(n) = next {
+            self.slots[n].prev = prev;
+        }
+        self.boxes[box_idx] = match (prev, next, self.boxes[box_idx]) {
+            (None, None, _) => None,
+            (None, Some(n), Some((_, last))) => Some((n, last)),
+            (Some(p), None, Some((first, _))) => Some((first, p)),
+            (_, _, ends) => ends,
+        };
+
+        self.free_slots.push(slot);
+        self.stats.removed += 1;
+        Some(self.slots[slot].lense.to_owned())
+    }
+
+    // Lenses of one box from the front to the back
+    fn box_contents(&self, box_idx: usize) -> impl Iterator<Item = &Lense> {
+        let mut curr = self.boxes[box_idx].map(|(first, _)| first);
+        std::iter::from_fn(move || {
+            let slot = &self.slots[curr?];
+            curr = slot.next;
+            Some(&slot.lense)
+        })
+    }
+
+    // (box, position in the box, lense) for every lense in the final state
+    fn iter(&self) -> impl Iterator<Item = (usize, usize, &Lense)> {
+        (0..BOXES_COUNT).flat_map(move |box_idx| {
+            self.box_contents(box_idx)
+                .enumerate()
+                .map(move |(pos, lense)| (box_idx, pos, lense))
+        })
     }
-}
 
-fn calc_focusing_power(boxes: &Vec<VecDeque<Lense>>) -> u32 {
-    boxes.iter().enumerate().fold(0, |acc, (idx, b)| {
-        acc + (idx as u32 + 1)
-            * b.iter().enumerate().fold(0, |box_acc, (l_idx, l)| {
-                box_acc + (l_idx as u32 + 1) * l.focal_length
-            })
-    })
+    // `None` if it doesn't fit into u64
+    fn focusing_power(&self) -> Option<u64> {
+        self.iter().try_fold(0u64, |acc, (box_idx, pos, lense)| {
+            (box_idx as u64 + 1)
+                .checked_mul(pos as u64 + 1)?
+                .checked_mul(lense.focal_length as u64)?
+                .checked_add(acc)
+        })
+    }
 }
 
-#[derive(Clone)]
-struct Lense {
-    label: String,
-    focal_length: u32,
+// Non-empty boxes in the format of the puzzle description
+impl fmt::Display for LensHashMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for box_idx in 0..BOXES_COUNT {
+            let lenses: Vec<String> = self
+                .box_contents(box_idx)
+                .map(|l| format!("[{} {}]", l.label, l.focal_length))
+                .collect();
+            if !lenses.is_empty() {
+                writeln!(f, "Box {}: {}", box_idx, lenses.join(" "))?;
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Clone)]
-enum Command {
-    Set(Lense),
-    Remove(String),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+
+    fn run(data: &[&str]) -> LensHashMap {
+        let mut boxes = LensHashMap::new();
+        for command in parse_initialization_sequence(data).unwrap().iter() {
+            boxes.apply(command);
+        }
+        boxes
+    }
+
+    #[test]
+    fn example() {
+        let data: Vec<&str> = EXAMPLE.split(',').collect();
+        assert_eq!(data.iter().map(|x| calc_hash(x)).sum::<u32>(), 1320);
+        let boxes = run(&data);
+        assert_eq!(boxes.focusing_power(), Some(145));
+        assert_eq!(
+            boxes.to_string(),
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n"
+        );
+    }
+
+    #[test]
+    fn large_focal_lengths() {
+        let boxes = run(&["ab=4000000000", "cd=4000000000"]);
+        let expected: u64 = ["ab", "cd"]
+            .iter()
+            .map(|label| (calc_hash(label) as u64 + 1) * 4000000000)
+            .sum();
+        assert_eq!(boxes.focusing_power(), Some(expected));
+    }
+
+    #[test]
+    fn wrong_commands() {
+        assert!(matches!(
+            Command::from_str("ab=x"),
+            Err(ParseError::FocalLength(_))
+        ));
+        assert!(matches!(
+            Command::from_str("A1-"),
+            Err(ParseError::Label(_))
+        ));
+        assert!(matches!(
+            Command::from_str("ab"),
+            Err(ParseError::UnknownCommand(_))
+        ));
+    }
 }
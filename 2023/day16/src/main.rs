@@ -1,6 +1,8 @@
-use core::panic;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use grid::{Grid, GridError, Input};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::fmt;
+use std::io;
 use std::time::Instant;
 
 fn main() -> io::Result<()> {
@@ -10,17 +12,10 @@ fn main() -> io::Result<()> {
     let now = Instant::now();
 
     // Part1
-    // open the file and parse input
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let data: Vec<Vec<char>> = reader
-        .lines()
-        .map(|l| {
-            let input = l.unwrap();
-            let seq: Vec<char> = input.chars().collect();
-            return seq;
-        })
-        .collect();
+    // map the input into memory and use it as a grid without copying
+    let input = Input::open(input_path)?;
+    let data =
+        parse_contraption(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
     let energized = energize(&data, (0, 0), BeamDirection::FromLeft);
 
@@ -28,157 +23,179 @@ fn main() -> io::Result<()> {
     println!("Part1: {}", part1_result);
 
     // Part 2
-    let mut part2_result = 0;
-    for i in 0..data.len() {
-        let left = calc_energized(&energize(&data, (i, 0), BeamDirection::FromLeft));
-        let right = calc_energized(&energize(
-            &data,
-            (i, data.len() - 1),
-            BeamDirection::FromRight,
-        ));
-        if left > part2_result {
-            part2_result = left;
-        }
-        if right > part2_result {
-            part2_result = right;
+    let part2_result = max_energized(&data, &entry_points(&data));
+    println!("Part2: {}", part2_result);
+
+    println!("Elapsed: {:?}", now.elapsed());
+    Ok(())
+}
+
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    Grid(GridError),
+    Tile(char),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Grid(err) => write!(f, "{err}"),
+            ParseError::Tile(ch) => write!(f, "Unknown tile {ch:?}"),
         }
     }
-    for j in 0..data[0].len() {
-        let top = calc_energized(&energize(&data, (0, j), BeamDirection::FromTop));
-        let bottom = calc_energized(&energize(
-            &data,
-            (data.len() - 1, j),
-            BeamDirection::FromBottom,
-        ));
-        if top > part2_result {
-            part2_result = top;
-        }
-        if bottom > part2_result {
-            part2_result = bottom;
-        }
+}
+
+impl std::error::Error for ParseError {}
+
+// A grid of empty space `.`, mirrors `/` and `\` and splitters `|` and `-`
+pub(crate) fn parse_contraption(input: &[u8]) -> Result<Grid<'_>, ParseError> {
+    let data = Grid::parse(input).map_err(ParseError::Grid)?;
+    if let Some(cell) = data.rows().flatten().find(|x| !b"./\\|-".contains(x)) {
+        return Err(ParseError::Tile(*cell as char));
     }
-    println!("Part2: {}", part2_result);
+    Ok(data)
+}
 
-    println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+// Every tile on the edge with the beam coming from outside
+fn entry_points(data: &Grid) -> Vec<((usize, usize), BeamDirection)> {
+    let (height, width) = (data.height(), data.width());
+    let mut result: Vec<((usize, usize), BeamDirection)> = vec![];
+    for i in 0..height {
+        result.push(((i, 0), BeamDirection::FromLeft));
+        result.push(((i, width - 1), BeamDirection::FromRight));
+    }
+    for j in 0..width {
+        result.push(((0, j), BeamDirection::FromTop));
+        result.push(((height - 1, j), BeamDirection::FromBottom));
+    }
+    result
 }
 
+// Entry points are independent, so with the `parallel` feature they are tried
+// on all the cores. The maximum doesn't depend on the order they are tried in.
+fn max_energized(data: &Grid, entries: &[((usize, usize), BeamDirection)]) -> u64 {
+    #[cfg(feature = "parallel")]
+    let entries = entries.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let entries = entries.iter();
+    entries
+        .map(|(start, direction)| calc_energized(&energize(data, *start, *direction)))
+        .max()
+        .unwrap_or(0)
+}
+
+// The names say where the beam comes from
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Copy, Debug, PartialEq)]
-enum BeamDirection {
+pub(crate) enum BeamDirection {
     FromLeft, // beam is moving from left to right
     FromTop,
     FromRight,
     FromBottom,
 }
 
-#[derive(Clone, Debug)]
-struct EnergizedTile {
-    kind: char,
-    incoming_beams: Vec<BeamDirection>,
-}
-
-fn energize(
-    data: &Vec<Vec<char>>,
-    start: (usize, usize),
-    direction: BeamDirection,
-) -> Vec<Vec<EnergizedTile>> {
-    let mut result: Vec<Vec<EnergizedTile>> = data
-        .iter()
-        .map(|row| {
-            row.iter()
-                .map(|ch| EnergizedTile {
-                    kind: *ch,
-                    incoming_beams: vec![],
-                })
-                .collect()
-        })
-        .collect();
-
-    result[start.0][start.1].incoming_beams.push(direction);
-    do_energize(&mut result, start);
-
-    return result;
+impl BeamDirection {
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
 }
 
-fn do_energize(data: &mut Vec<Vec<EnergizedTile>>, coords: (usize, usize)) -> () {
-    let tile = &data[coords.0][coords.1];
-    let last_beam = *tile.incoming_beams.last().unwrap();
-    let dimensions = (data.len(), data[0].len());
-    let next_directions = get_next_directions(tile.kind, last_beam);
-    for next_dir in next_directions.iter() {
-        match get_next_coords(coords, *next_dir, dimensions) {
-            Some(c) => {
-                if !data[c.0][c.1].incoming_beams.contains(next_dir) {
-                    data[c.0][c.1].incoming_beams.push(*next_dir);
-                    do_energize(data, c);
+// For every tile, row by row, the set of the beam directions that got into it,
+// one bit per direction, so a tile takes a single byte
+pub(crate) fn energize(data: &Grid, start: (usize, usize), direction: BeamDirection) -> Vec<u8> {
+    let width = data.width();
+    let mut result: Vec<u8> = vec![0; width * data.height()];
+    result[start.0 * width + start.1] |= direction.bit();
+
+    // follow the beams with an explicit stack of (tile, incoming beam),
+    // every tile passes each beam direction only once
+    let mut stack: Vec<((usize, usize), BeamDirection)> = vec![(start, direction)];
+    while let Some((coords, beam)) = stack.pop() {
+        for next_dir in get_next_directions(data[coords], beam) {
+            if let Some(c) = get_next_coords(coords, next_dir, data) {
+                let incoming = &mut result[c.0 * width + c.1];
+                if *incoming & next_dir.bit() == 0 {
+                    *incoming |= next_dir.bit();
+                    stack.push((c, next_dir));
                 }
             }
-            None => (),
         }
     }
+    result
 }
 
-fn get_next_directions(tile_kind: char, curr_direction: BeamDirection) -> Vec<BeamDirection> {
+fn get_next_directions(tile_kind: u8, curr_direction: BeamDirection) -> Vec<BeamDirection> {
     match tile_kind {
-        '.' => vec![curr_direction],
-        '\\' => match curr_direction {
+        b'.' => vec![curr_direction],
+        b'\\' => match curr_direction {
             BeamDirection::FromLeft => vec![BeamDirection::FromTop],
             BeamDirection::FromTop => vec![BeamDirection::FromLeft],
             BeamDirection::FromRight => vec![BeamDirection::FromBottom],
             BeamDirection::FromBottom => vec![BeamDirection::FromRight],
         },
-        '/' => match curr_direction {
+        b'/' => match curr_direction {
             BeamDirection::FromLeft => vec![BeamDirection::FromBottom],
             BeamDirection::FromTop => vec![BeamDirection::FromRight],
             BeamDirection::FromRight => vec![BeamDirection::FromTop],
             BeamDirection::FromBottom => vec![BeamDirection::FromLeft],
         },
-        '|' => match curr_direction {
+        b'|' => match curr_direction {
             BeamDirection::FromLeft => vec![BeamDirection::FromBottom, BeamDirection::FromTop],
             BeamDirection::FromTop => vec![BeamDirection::FromTop],
             BeamDirection::FromRight => vec![BeamDirection::FromBottom, BeamDirection::FromTop],
             BeamDirection::FromBottom => vec![BeamDirection::FromBottom],
         },
-        '-' => match curr_direction {
+        b'-' => match curr_direction {
             BeamDirection::FromLeft => vec![BeamDirection::FromLeft],
             BeamDirection::FromTop => vec![BeamDirection::FromLeft, BeamDirection::FromRight],
             BeamDirection::FromRight => vec![BeamDirection::FromRight],
             BeamDirection::FromBottom => vec![BeamDirection::FromLeft, BeamDirection::FromRight],
         },
-        _ => panic!("Unknown tile"),
+        _ => unreachable!("the tiles are checked by parse_contraption"),
     }
 }
 
 fn get_next_coords(
     curr: (usize, usize),
     direction: BeamDirection,
-    dimensions: (usize, usize),
+    data: &Grid,
 ) -> Option<(usize, usize)> {
-    let next_coords: (i32, i32) = match direction {
-        BeamDirection::FromLeft => (curr.0 as i32, curr.1 as i32 + 1),
-        BeamDirection::FromTop => (curr.0 as i32 + 1, curr.1 as i32),
-        BeamDirection::FromRight => (curr.0 as i32, curr.1 as i32 - 1),
-        BeamDirection::FromBottom => (curr.0 as i32 - 1, curr.1 as i32),
+    let next_coords = match direction {
+        BeamDirection::FromLeft => (curr.0, curr.1 + 1),
+        BeamDirection::FromTop => (curr.0 + 1, curr.1),
+        BeamDirection::FromRight => (curr.0, curr.1.checked_sub(1)?),
+        BeamDirection::FromBottom => (curr.0.checked_sub(1)?, curr.1),
     };
     // verify that we're in bounds
-    if next_coords.0 >= 0
-        && next_coords.0 < dimensions.0 as i32
-        && next_coords.1 >= 0
-        && next_coords.1 < dimensions.1 as i32
-    {
-        Some((next_coords.0 as usize, next_coords.1 as usize))
-    } else {
-        None
-    }
+    data.get(next_coords.0, next_coords.1).map(|_| next_coords)
 }
 
-fn calc_energized(data: &Vec<Vec<EnergizedTile>>) -> u64 {
-    let result: u64 = data
-        .iter()
-        .map(|row| {
-            row.iter()
-                .fold(0, |acc, t| if t.incoming_beams.is_empty() {acc} else {acc + 1} as u64)
-        })
-        .sum();
-    result
+fn calc_energized(data: &[u8]) -> u64 {
+    data.iter().filter(|incoming| **incoming != 0).count() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let input = include_bytes!("input_test.txt");
+        let data = parse_contraption(input).unwrap();
+        let energized = energize(&data, (0, 0), BeamDirection::FromLeft);
+        assert_eq!(calc_energized(&energized), 46);
+        assert_eq!(max_energized(&data, &entry_points(&data)), 51);
+    }
+
+    #[test]
+    fn wrong_contraptions() {
+        assert!(matches!(
+            parse_contraption(b".|.\n.x.\n"),
+            Err(ParseError::Tile('x'))
+        ));
+        assert!(matches!(
+            parse_contraption(b".|.\n..\n"),
+            Err(ParseError::Grid(GridError::Ragged { .. }))
+        ));
+    }
 }
@@ -1,7 +1,7 @@
-use core::panic;
+use grid::{Grid, GridError, Input};
 use pathfinding::prelude::dijkstra;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::fmt;
+use std::io;
 use std::time::Instant;
 
 fn main() -> io::Result<()> {
@@ -11,20 +11,9 @@ fn main() -> io::Result<()> {
     let now = Instant::now();
 
     // Part1
-    // open the file and parse input
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let data: Vec<Vec<u32>> = reader
-        .lines()
-        .map(|l| {
-            let input = l.unwrap();
-            let seq: Vec<u32> = input
-                .chars()
-                .map(|ch| u32::from_str_radix(&ch.to_string(), 10).unwrap())
-                .collect();
-            return seq;
-        })
-        .collect();
+    // map the input into memory and use it as a grid of heat losses without copying
+    let input = Input::open(input_path)?;
+    let data = parse_city(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
     let part1_result = dijkstra(
         &State {
@@ -32,7 +21,7 @@ fn main() -> io::Result<()> {
             direction: (Direction::None, 0),
         },
         |x| x.successors(&data, |_curr_dir, next_dir| next_dir.1 <= 3),
-        |x| x.position == (data.len() - 1, data[0].len() - 1),
+        |x| x.position == (data.height() - 1, data.width() - 1),
     )
     .unwrap();
     println!("Part1: {:?}", part1_result.1);
@@ -54,13 +43,39 @@ fn main() -> io::Result<()> {
                 }
             })
         },
-        |x| x.position == (data.len() - 1, data[0].len() - 1) && x.direction.1 >= 4,
+        |x| x.position == (data.height() - 1, data.width() - 1) && x.direction.1 >= 4,
     )
     .unwrap();
     println!("Part2: {}", part2_result.1);
 
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
+}
+
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    Grid(GridError),
+    HeatLoss(char),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Grid(err) => write!(f, "{err}"),
+            ParseError::HeatLoss(ch) => write!(f, "Wrong heat loss {ch:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// A grid of heat losses, one digit per block
+pub(crate) fn parse_city(input: &[u8]) -> Result<Grid<'_>, ParseError> {
+    let data = Grid::parse(input).map_err(ParseError::Grid)?;
+    if let Some(cell) = data.rows().flatten().find(|x| !x.is_ascii_digit()) {
+        return Err(ParseError::HeatLoss(*cell as char));
+    }
+    Ok(data)
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Hash, Debug)]
@@ -81,7 +96,7 @@ struct State {
 impl State {
     fn successors(
         &self,
-        data: &Vec<Vec<u32>>,
+        data: &Grid,
         is_valid_direction: fn((Direction, u8), (Direction, u8)) -> bool,
     ) -> Vec<(State, u32)> {
         let dirs = [
@@ -116,14 +131,15 @@ impl State {
                     && !is_opposite_direction(dir.0, self.direction.0)
                     && pos.0 >= 0
                     && pos.1 >= 0
-                    && pos.0 < data.len() as i32
-                    && pos.1 < data[0].len() as i32
+                    && pos.0 < data.height() as i32
+                    && pos.1 < data.width() as i32
                 {
                     let neib_state = State {
                         position: (pos.0 as usize, pos.1 as usize),
                         direction: dir,
                     };
-                    Some((neib_state, data[pos.0 as usize][pos.1 as usize]))
+                    let heat_loss = (data[(pos.0 as usize, pos.1 as usize)] - b'0') as u32;
+                    Some((neib_state, heat_loss))
                 } else {
                     None
                 }
@@ -141,3 +157,21 @@ fn is_opposite_direction(dir1: Direction, dir2: Direction) -> bool {
         Direction::None => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrong_cities() {
+        assert!(parse_city(include_str!("input_test.txt").as_bytes()).is_ok());
+        assert!(matches!(
+            parse_city(b"123\n4x6\n"),
+            Err(ParseError::HeatLoss('x'))
+        ));
+        assert!(matches!(
+            parse_city(b"123\n45\n"),
+            Err(ParseError::Grid(GridError::Ragged { .. }))
+        ));
+    }
+}
@@ -1,11 +1,13 @@
-use core::panic;
-use std::fs::File;
+use std::fmt::{self, Write as _};
+use std::fs::{self, File};
 use std::io::{self, BufRead, BufReader};
 use std::time::Instant;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day18/src/input.txt";
+    // the trench is drawn to an SVG file if its path is passed as an argument
+    let svg_path = std::env::args().nth(1);
 
     let now = Instant::now();
 
@@ -13,42 +15,42 @@ fn main() -> io::Result<()> {
     // open the file and parse input
     let file = File::open(input_path)?;
     let reader = BufReader::new(file);
-    let data: Vec<Command> = reader
-        .lines()
-        .map(|l| {
-            let input = l.unwrap();
-            Command::from_string(&input)
-        })
-        .collect();
+    let mut plan: Vec<DigInstruction> = vec![];
+    for line in reader.lines() {
+        let instruction = DigInstruction::from_str(&line?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        plan.push(instruction);
+    }
+    let open_plan = || io::Error::new(io::ErrorKind::InvalidData, "The trench is not closed");
 
-    let trench = build_trench(&data);
-    let volume = get_volume(&trench);
+    let lagoon = Lagoon::dig(plan.iter().map(|x| &x.plain)).ok_or_else(open_plan)?;
+    match lagoon.volume() {
+        Some(volume) => println!("Part1: {}", volume),
+        None => println!("Part1: the trench doesn't enclose a lagoon"),
+    }
 
-    println!("Part1: {:?}", volume);
+    // Part2
+    let decoded_lagoon = Lagoon::dig(plan.iter().map(|x| &x.decoded)).ok_or_else(open_plan)?;
+    match decoded_lagoon.volume() {
+        Some(volume) => println!("Part2: {}", volume),
+        None => println!("Part2: the trench doesn't enclose a lagoon"),
+    }
 
-    let new_data = decode_data(&data);
-    let new_trench = build_trench(&new_data);
-    let new_volume = get_volume(&new_trench);
-    println!("Part2: {}", new_volume);
+    if let Some(path) = svg_path {
+        fs::write(path, to_svg(&lagoon, &plan))?;
+    }
 
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
 }
 
-#[derive(Debug)]
-struct Command {
-    direction: Direction,
-    count: i64,
-    code: String,
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Point {
     x: i64,
     y: i64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
     Up,
     Right,
@@ -56,103 +58,257 @@ enum Direction {
     Left,
 }
 
-impl Command {
-    fn from_string(input: &str) -> Command {
-        let parts: Vec<&str> = input.split_whitespace().collect();
-        let direction = match parts[0].chars().next().unwrap() {
-            'U' => Direction::Up,
-            'R' => Direction::Right,
-            'D' => Direction::Down,
-            'L' => Direction::Left,
-            _ => panic!("Wrong direction"),
-        };
-        let count = i64::from_str_radix(parts[1], 10).unwrap();
-        let code = parts[2].trim_matches(|x| x == '(' || x == ')');
-        Command {
-            direction,
-            count,
-            code: String::from(code),
-        }
-    }
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    direction: Direction,
+    count: i64,
 }
 
-fn build_trench(commands: &Vec<Command>) -> Vec<Point> {
-    let mut result: Vec<Point> = vec![Point { x: 0, y: 0 }];
+// One line of the dig plan with both of its interpretations
+#[derive(Debug)]
+pub(crate) struct DigInstruction {
+    // direction and distance as written in the plan
+    plain: Step,
+    // color of the trench in the `#rrggbb` format
+    color: String,
+    // direction and distance hidden in the color
+    decoded: Step,
+}
 
-    for command in commands.iter() {
-        let prev_point = result.last().unwrap();
-        let next_point = match command.direction {
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    WrongFormat(String),
+    Direction(String),
+    Distance(String),
+    Color(String),
+    DirectionCode(String),
+}
+
+// The lagoon as a polygon going through the centers of the trench corners
+struct Lagoon {
+    vertices: Vec<Point>,
+}
+
+impl Point {
+    fn moved(&self, step: &Step) -> Point {
+        match step.direction {
             Direction::Up => Point {
-                x: prev_point.x,
-                y: prev_point.y + command.count,
+                x: self.x,
+                y: self.y + step.count,
             },
             Direction::Down => Point {
-                x: prev_point.x,
-                y: prev_point.y - command.count,
+                x: self.x,
+                y: self.y - step.count,
             },
             Direction::Right => Point {
-                x: prev_point.x + command.count,
-                y: prev_point.y,
+                x: self.x + step.count,
+                y: self.y,
             },
             Direction::Left => Point {
-                x: prev_point.x - command.count,
-                y: prev_point.y,
+                x: self.x - step.count,
+                y: self.y,
             },
-        };
-        result.push(next_point);
+        }
     }
+}
 
-    if result.last().unwrap().x == 0 && result.last().unwrap().y == 0 {
-        result.pop();
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFormat(input) => write!(f, "Wrong instruction \"{input}\""),
+            ParseError::Direction(input) => write!(f, "Wrong direction in \"{input}\""),
+            ParseError::Distance(input) => write!(f, "Wrong distance in \"{input}\""),
+            ParseError::Color(input) => write!(f, "Wrong color in \"{input}\""),
+            ParseError::DirectionCode(input) => {
+                write!(f, "Unknown direction code in \"{input}\"")
+            }
+        }
     }
-
-    return result;
 }
 
-fn get_volume(trench: &Vec<Point>) -> u64 {
-    // Use Gauss's area formula
-    // and also count the number of points on the boundary
-    let mut surface: i64 = 0;
-    let mut boundary_len: i64 = 0;
-    for i in 0..trench.len() {
-        let curr_point = &trench[i];
-        let next_point = if i < trench.len() - 1 {
-            &trench[i + 1]
-        } else {
-            &trench[0]
+impl std::error::Error for ParseError {}
+
+impl DigInstruction {
+    // `R 6 (#70c710)`: the first 5 hex digits of the color are the distance,
+    // the last one is the direction
+    pub(crate) fn from_str(input: &str) -> Result<DigInstruction, ParseError> {
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        let [direction, count, code] = parts[..] else {
+            return Err(ParseError::WrongFormat(input.to_string()));
         };
-        surface += curr_point.x * next_point.y - curr_point.y * next_point.x;
-        boundary_len += (next_point.y - curr_point.y).abs() + (next_point.x - curr_point.x).abs();
+
+        let direction = match direction {
+            "U" => Direction::Up,
+            "R" => Direction::Right,
+            "D" => Direction::Down,
+            "L" => Direction::Left,
+            _ => return Err(ParseError::Direction(input.to_string())),
+        };
+        // the distance can't be negative
+        let count = count
+            .parse::<u32>()
+            .map_err(|_| ParseError::Distance(input.to_string()))? as i64;
+
+        let color = code
+            .strip_prefix("(#")
+            .and_then(|x| x.strip_suffix(')'))
+            .filter(|x| x.len() == 6 && x.chars().all(|ch| ch.is_ascii_hexdigit()))
+            .ok_or_else(|| ParseError::Color(input.to_string()))?;
+        let decoded_direction = match &color[5..] {
+            "0" => Direction::Right,
+            "1" => Direction::Down,
+            "2" => Direction::Left,
+            "3" => Direction::Up,
+            _ => return Err(ParseError::DirectionCode(input.to_string())),
+        };
+        let decoded_count = i64::from_str_radix(&color[..5], 16).unwrap();
+
+        Ok(DigInstruction {
+            plain: Step { direction, count },
+            color: format!("#{color}"),
+            decoded: Step {
+                direction: decoded_direction,
+                count: decoded_count,
+            },
+        })
     }
-    if surface < 0 {
-        surface = -surface;
+}
+
+impl Lagoon {
+    // `None` if the trench doesn't return to the start
+    fn dig<'a>(steps: impl Iterator<Item = &'a Step>) -> Option<Lagoon> {
+        let mut vertices: Vec<Point> = vec![Point { x: 0, y: 0 }];
+        for step in steps {
+            let next_point = vertices.last().unwrap().moved(step);
+            vertices.push(next_point);
+        }
+
+        if vertices.len() > 1 {
+            if *vertices.last().unwrap() != vertices[0] {
+                return None;
+            }
+            vertices.pop();
+        }
+        Some(Lagoon { vertices })
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (&Point, &Point)> {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+    }
+
+    // Number of cubic meters of the trench itself
+    fn boundary_len(&self) -> u64 {
+        self.edges()
+            .map(|(a, b)| a.x.abs_diff(b.x) + a.y.abs_diff(b.y))
+            .sum()
     }
-    surface = surface / 2;
 
-    // find the number of points inside the boundary using Pick's theorem
-    let inside_count = surface - boundary_len / 2 + 1;
+    // `None` if the trench is degenerate, e.g. goes back along itself,
+    // so the formulas give no sensible volume
+    fn volume(&self) -> Option<u64> {
+        // Use Gauss's area formula
+        let double_area = self
+            .edges()
+            .map(|(a, b)| a.x as i128 * b.y as i128 - a.y as i128 * b.x as i128)
+            .sum::<i128>()
+            .unsigned_abs();
+
+        // find the number of points inside the boundary using Pick's theorem
+        let boundary_len = self.boundary_len() as u128;
+        let inside_count = (double_area + 2).checked_sub(boundary_len)? / 2;
 
-    return boundary_len as u64 + inside_count as u64;
+        u64::try_from(boundary_len + inside_count).ok()
+    }
 }
 
-fn decode_data(data: &Vec<Command>) -> Vec<Command> {
-    data.iter()
-        .map(|old| {
-            let code_chars = old.code.trim_matches('#').chars();
-            let new_count_str: String = code_chars.to_owned().take(5).collect();
-            let new_count = i64::from_str_radix(&new_count_str, 16).unwrap();
-            let new_dir = match code_chars.skip(5).next().unwrap() {
-                '0' => Direction::Right,
-                '1' => Direction::Down,
-                '2' => Direction::Left,
-                '3' => Direction::Up,
-                _ => panic!("Unknown direction code"),
-            };
-            Command {
-                direction: new_dir,
-                count: new_count,
-                code: String::from(""),
-            }
-        })
-        .collect()
+// The trench from the plain interpretation of the plan
+// with every segment painted in its own color
+fn to_svg(lagoon: &Lagoon, plan: &[DigInstruction]) -> String {
+    let min_x = lagoon.vertices.iter().map(|p| p.x).min().unwrap_or(0);
+    let max_x = lagoon.vertices.iter().map(|p| p.x).max().unwrap_or(0);
+    let min_y = lagoon.vertices.iter().map(|p| p.y).min().unwrap_or(0);
+    let max_y = lagoon.vertices.iter().map(|p| p.y).max().unwrap_or(0);
+
+    let mut result = String::new();
+    // SVG's y axis goes down, so it's flipped to keep `U` pointing up;
+    // the half meter margin keeps the cubes on the border visible
+    let _ = writeln!(
+        result,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+        min_x as f64 - 0.5,
+        -max_y as f64 - 0.5,
+        max_x - min_x + 1,
+        max_y - min_y + 1,
+    );
+    let _ = writeln!(
+        result,
+        "<polygon points=\"{}\" fill=\"#eeeeee\"/>",
+        lagoon
+            .vertices
+            .iter()
+            .map(|p| format!("{},{}", p.x, -p.y))
+            .collect::<Vec<String>>()
+            .join(" ")
+    );
+    let mut curr = Point { x: 0, y: 0 };
+    for instruction in plan.iter() {
+        let next = curr.moved(&instruction.plain);
+        let _ = writeln!(
+            result,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"1\" stroke-linecap=\"square\"/>",
+            curr.x, -curr.y, next.x, -next.y, instruction.color
+        );
+        curr = next;
+    }
+    result.push_str("</svg>\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Vec<DigInstruction> {
+        input
+            .lines()
+            .map(|line| DigInstruction::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn example() {
+        let plan = parse(include_str!("input_test.txt"));
+        let lagoon = Lagoon::dig(plan.iter().map(|x| &x.plain)).unwrap();
+        assert_eq!(lagoon.volume(), Some(62));
+        let lagoon = Lagoon::dig(plan.iter().map(|x| &x.decoded)).unwrap();
+        assert_eq!(lagoon.volume(), Some(952408144115));
+    }
+
+    #[test]
+    fn open_and_degenerate_plans() {
+        let plan = parse("R 5 (#000050)");
+        assert!(Lagoon::dig(plan.iter().map(|x| &x.plain)).is_none());
+
+        let plan = parse("R 5 (#000050)\nL 5 (#000052)");
+        let lagoon = Lagoon::dig(plan.iter().map(|x| &x.plain)).unwrap();
+        assert_eq!(lagoon.volume(), None);
+    }
+
+    #[test]
+    fn wrong_instructions() {
+        assert!(matches!(
+            DigInstruction::from_str("R -5 (#000050)"),
+            Err(ParseError::Distance(_))
+        ));
+        assert!(matches!(
+            DigInstruction::from_str("X 5 (#000050)"),
+            Err(ParseError::Direction(_))
+        ));
+        assert!(matches!(
+            DigInstruction::from_str("R 5 (#000058)"),
+            Err(ParseError::DirectionCode(_))
+        ));
+    }
 }
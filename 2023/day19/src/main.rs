@@ -1,5 +1,6 @@
-use core::panic;
-use std::fs::File;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, File};
 use std::io::{self, BufReader, Read};
 use std::time::Instant;
 
@@ -15,28 +16,51 @@ fn main() -> io::Result<()> {
     let mut reader = BufReader::new(file);
     let mut buf = String::from("");
     let _ = reader.read_to_string(&mut buf);
-    let (workflows, details) = process_input(&buf);
+    let (workflows, details) =
+        process_input(&buf).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
-    let part1_result: u64 = details
-        .iter()
-        .filter(|d| d.is_accepted(&workflows, String::from("in")))
-        .map(|d| d.x + d.m + d.a + d.s)
-        .sum();
+    let tree = match DecisionTree::compile(&workflows) {
+        Ok(tree) => tree,
+        Err(err) => {
+            println!("Can't compile workflows: {err}");
+            return Ok(());
+        }
+    };
+
+    // Optionally print the workflows every detail goes through
+    let print_paths = std::env::args().any(|arg| arg == "--trace");
+
+    let mut part1_result: u64 = 0;
+    for detail in details.iter() {
+        let (path, accepted) = tree.trace(detail);
+        if print_paths {
+            let verdict = if accepted { "A" } else { "R" };
+            println!("{detail}: {} -> {verdict}", path.join(" -> "));
+        }
+        if accepted {
+            part1_result += detail.x + detail.m + detail.a + detail.s;
+        }
+    }
     println!("Part1: {:?}", part1_result);
 
-    let accepted_details: Vec<DetailRange> = find_accepted_details(&workflows);
-    println!("{accepted_details:?}");
-    let part2_result = accepted_details.iter().fold(0u64, |acc, r| {
-        acc + (r.x.1 - r.x.0) * (r.m.1 - r.m.0) * (r.a.1 - r.a.0) * (r.s.1 - r.s.0)
-    });
+    let part2_result = tree.count_accepted(&DetailRange::full());
     println!("Part2: {}", part2_result);
 
+    for (workflow, rule_idx) in tree.find_unreachable_rules(&workflows) {
+        println!("Unreachable rule #{rule_idx} in workflow {workflow}");
+    }
+
+    // Optionally save the decision tree in DOT format
+    if let Some(output_path) = std::env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+        fs::write(output_path, tree.to_dot())?;
+    }
+
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
 }
 
 #[derive(Debug)]
-struct Workflow {
+pub(crate) struct Workflow {
     name: String,
     rules: Vec<Rule>,
 }
@@ -47,7 +71,7 @@ struct Rule {
     dest: RuleDestination,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct RuleCondition {
     prop: char,
     comparison: char,
@@ -62,13 +86,14 @@ enum RuleDestination {
 }
 
 #[derive(Debug)]
-struct Detail {
+pub(crate) struct Detail {
     x: u64,
     m: u64,
     a: u64,
     s: u64,
 }
 
+// Box of details, every range includes the start and excludes the end
 #[derive(Debug, Clone, Copy)]
 struct DetailRange {
     x: (u64, u64),
@@ -77,344 +102,531 @@ struct DetailRange {
     s: (u64, u64),
 }
 
+// Workflows compiled into a graph of single comparisons.
+// Workflows referenced from several places share their nodes.
+#[derive(Debug)]
+pub(crate) struct DecisionTree {
+    nodes: Vec<DecisionNode>,
+    root: usize,
+}
+
+#[derive(Debug)]
+enum DecisionNode {
+    Accept,
+    Reject,
+    // conditional rule `origin.1` of the workflow `origin.0`
+    Check {
+        origin: (String, usize),
+        condition: RuleCondition,
+        matching: usize,
+        otherwise: usize,
+    },
+    // unconditional rule
+    Jump {
+        origin: (String, usize),
+        to: usize,
+    },
+}
+
+#[derive(Debug)]
+pub(crate) enum CompileError {
+    UnknownWorkflow(String),
+    Cycle(Vec<String>),
+}
+
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    Workflow(String),
+    Rule(String),
+    Detail(String),
+    NoDetails,
+}
+
+const ACCEPT_NODE: usize = 0;
+const REJECT_NODE: usize = 1;
+
 impl Workflow {
-    fn from_str(input: &str) -> Workflow {
-        let mut workflow = Workflow {
-            name: input.chars().take_while(|ch| *ch != '{').collect(),
-            rules: vec![],
-        };
-        let tmp: String = input.chars().skip_while(|ch| *ch != '{').collect();
-        workflow.rules = tmp
-            .trim_matches(|ch| ch == '{' || ch == '}')
-            .split(',')
-            .map(|x| Rule::from_str(x))
-            .collect();
-
-        workflow
+    // `px{a<2006:qkq,m>2090:A,rfg}`
+    fn from_str(input: &str) -> Result<Workflow, ParseError> {
+        let (name, rules) = input
+            .strip_suffix('}')
+            .and_then(|x| x.split_once('{'))
+            .filter(|(name, _)| !name.is_empty())
+            .ok_or(ParseError::Workflow(input.to_string()))?;
+        Ok(Workflow {
+            name: name.to_string(),
+            rules: rules
+                .split(',')
+                .map(Rule::from_str)
+                .collect::<Result<_, _>>()?,
+        })
     }
 }
 
 impl Rule {
-    fn from_str(input: &str) -> Rule {
+    fn from_str(input: &str) -> Result<Rule, ParseError> {
+        let wrong_rule = || ParseError::Rule(input.to_string());
         let parts: Vec<&str> = input.split(':').collect();
-        if parts.len() == 1 {
+        match parts[..] {
             // has no condition
-            Rule {
+            [dest] => Ok(Rule {
                 condition: None,
-                dest: RuleDestination::from_str(parts[0]),
-            }
-        } else {
+                dest: RuleDestination::from_str(dest).ok_or_else(wrong_rule)?,
+            }),
             // has condition
-            let dest = RuleDestination::from_str(parts[1]);
-            let prop = parts[0].chars().next().unwrap();
-            let comparison = parts[0].chars().skip(1).next().unwrap();
-            let value = u64::from_str_radix(parts[0].trim_matches(|ch: char| !ch.is_digit(10)), 10)
-                .unwrap();
-
-            Rule {
-                condition: Some(RuleCondition {
-                    prop,
-                    comparison,
-                    value,
-                }),
-                dest,
+            [condition, dest] => {
+                let dest = RuleDestination::from_str(dest).ok_or_else(wrong_rule)?;
+                let mut chars = condition.chars();
+                let prop = chars.next().filter(|ch| "xmas".contains(*ch));
+                let comparison = chars.next().filter(|ch| "<>".contains(*ch));
+                let value = chars.as_str().parse::<u32>().ok();
+                let (Some(prop), Some(comparison), Some(value)) = (prop, comparison, value) else {
+                    return Err(wrong_rule());
+                };
+
+                Ok(Rule {
+                    condition: Some(RuleCondition {
+                        prop,
+                        comparison,
+                        value: value as u64,
+                    }),
+                    dest,
+                })
             }
+            _ => Err(wrong_rule()),
         }
     }
 }
 
 impl RuleDestination {
-    fn from_str(input: &str) -> RuleDestination {
+    fn from_str(input: &str) -> Option<RuleDestination> {
         match input {
-            "R" => RuleDestination::Reject,
-            "A" => RuleDestination::Accept,
-            name => RuleDestination::Workflow(String::from(name)),
+            "" => None,
+            "R" => Some(RuleDestination::Reject),
+            "A" => Some(RuleDestination::Accept),
+            name => Some(RuleDestination::Workflow(String::from(name))),
         }
     }
 }
 
 impl RuleCondition {
     fn is_matching(&self, detail: &Detail) -> bool {
-        match (self.prop, self.comparison) {
-            ('x', '>') => detail.x > self.value,
-            ('x', '<') => detail.x < self.value,
-            ('m', '>') => detail.m > self.value,
-            ('m', '<') => detail.m < self.value,
-            ('a', '>') => detail.a > self.value,
-            ('a', '<') => detail.a < self.value,
-            ('s', '>') => detail.s > self.value,
-            ('s', '<') => detail.s < self.value,
+        let value = match self.prop {
+            'x' => detail.x,
+            'm' => detail.m,
+            'a' => detail.a,
+            's' => detail.s,
+            _ => panic!("Unknown property"),
+        };
+        match self.comparison {
+            '>' => value > self.value,
+            '<' => value < self.value,
             _ => panic!("Wrong comparison rule"),
         }
     }
 
-    fn get_matching_range(&self, curr_range: &DetailRange) -> Option<DetailRange> {
-        match (self.prop, self.comparison) {
-            ('x', '>') => {
-                if curr_range.x.1 <= self.value {
-                    None
-                } else {
-                    Some(DetailRange {
-                        x: (self.value + 1, curr_range.x.1),
-                        m: curr_range.m.to_owned(),
-                        a: curr_range.a.to_owned(),
-                        s: curr_range.s.to_owned(),
-                    })
-                }
-            }
-            ('x', '<') => {
-                if curr_range.x.0 > self.value {
-                    None
-                } else {
-                    Some(DetailRange {
-                        x: (curr_range.x.0, self.value),
-                        m: curr_range.m.to_owned(),
-                        a: curr_range.a.to_owned(),
-                        s: curr_range.s.to_owned(),
-                    })
-                }
-            }
-            ('m', '>') => {
-                if curr_range.m.1 <= self.value {
-                    None
-                } else {
-                    Some(DetailRange {
-                        x: curr_range.x.to_owned(),
-                        m: (self.value + 1, curr_range.m.1),
-                        a: curr_range.a.to_owned(),
-                        s: curr_range.s.to_owned(),
-                    })
-                }
-            }
-            ('m', '<') => {
-                if curr_range.m.0 > self.value {
-                    None
-                } else {
-                    Some(DetailRange {
-                        x: curr_range.x.to_owned(),
-                        m: (curr_range.m.0, self.value),
-                        a: curr_range.a.to_owned(),
-                        s: curr_range.s.to_owned(),
-                    })
-                }
-            }
-            ('a', '>') => {
-                if curr_range.a.1 <= self.value {
-                    None
-                } else {
-                    Some(DetailRange {
-                        x: curr_range.x.to_owned(),
-                        m: curr_range.m.to_owned(),
-                        a: (self.value + 1, curr_range.a.1),
-                        s: curr_range.s.to_owned(),
-                    })
-                }
-            }
-            ('a', '<') => {
-                if curr_range.a.0 > self.value {
-                    None
-                } else {
-                    Some(DetailRange {
-                        x: curr_range.x.to_owned(),
-                        m: curr_range.m.to_owned(),
-                        a: (curr_range.a.0, self.value),
-                        s: curr_range.s.to_owned(),
-                    })
-                }
-            }
-            ('s', '>') => {
-                if curr_range.s.1 <= self.value {
-                    None
-                } else {
-                    Some(DetailRange {
-                        x: curr_range.x.to_owned(),
-                        m: curr_range.m.to_owned(),
-                        a: curr_range.a.to_owned(),
-                        s: (self.value + 1, curr_range.s.1),
-                    })
-                }
-            }
-            ('s', '<') => {
-                if curr_range.s.0 > self.value {
-                    None
-                } else {
-                    Some(DetailRange {
-                        x: curr_range.x.to_owned(),
-                        m: curr_range.m.to_owned(),
-                        a: curr_range.a.to_owned(),
-                        s: (curr_range.s.0, self.value),
-                    })
-                }
-            }
+    // Split the range into parts matching and not matching the condition
+    fn split(&self, range: &DetailRange) -> (Option<DetailRange>, Option<DetailRange>) {
+        let (start, end) = range.get(self.prop);
+        let (matching, otherwise) = match self.comparison {
+            '>' => (
+                (start.max(self.value + 1), end),
+                (start, end.min(self.value + 1)),
+            ),
+            '<' => ((start, end.min(self.value)), (start.max(self.value), end)),
             _ => panic!("Wrong comparison rule"),
-        }
+        };
+        (
+            range.with(self.prop, matching),
+            range.with(self.prop, otherwise),
+        )
     }
+}
 
-    fn exclude_from(&self, range: &mut DetailRange) -> () {
-        match (self.prop, self.comparison) {
-            ('x', '>') => {
-                if range.x.1 > self.value + 1 {
-                    range.x = (range.x.0, self.value + 1)
-                }
-            }
-            ('x', '<') => {
-                if range.x.0 < self.value {
-                    range.x = (self.value, range.x.1)
-                }
-            }
-            ('m', '>') => {
-                if range.m.1 > self.value + 1 {
-                    range.m = (range.m.0, self.value + 1)
-                }
-            }
-            ('m', '<') => {
-                if range.m.0 < self.value {
-                    range.m = (self.value, range.m.1)
-                }
-            }
-            ('a', '>') => {
-                if range.a.1 > self.value + 1 {
-                    range.a = (range.a.0, self.value + 1)
-                }
-            }
-            ('a', '<') => {
-                if range.a.0 < self.value {
-                    range.a = (self.value, range.a.1)
-                }
-            }
-            ('s', '>') => {
-                if range.s.1 > self.value + 1 {
-                    range.s = (range.s.0, self.value + 1)
-                }
-            }
-            ('s', '<') => {
-                if range.s.0 < self.value {
-                    range.s = (self.value, range.s.1)
-                }
-            }
-            _ => panic!("Wrong comparison rule"),
-        }
+impl fmt::Display for RuleCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", self.prop, self.comparison, self.value)
     }
 }
 
 impl Detail {
-    fn from_str(input: &str) -> Detail {
+    // `{x=787,m=2655,a=1222,s=2876}`, missing properties are 0
+    fn from_str(input: &str) -> Result<Detail, ParseError> {
+        let wrong_detail = || ParseError::Detail(input.to_string());
         let mut detail = Detail {
             x: 0,
             m: 0,
             a: 0,
             s: 0,
         };
-        input
-            .trim_matches(|x| x == '{' || x == '}')
-            .split(|x| x == ',' || x == '=')
-            .collect::<Vec<&str>>()
-            .chunks(2)
-            .collect::<Vec<&[&str]>>()
+        let props = input
+            .strip_prefix('{')
+            .and_then(|x| x.strip_suffix('}'))
+            .ok_or_else(wrong_detail)?;
+        for prop in props.split(',') {
+            let (name, value) = prop.split_once('=').ok_or_else(wrong_detail)?;
+            let value = value.parse::<u32>().map_err(|_| wrong_detail())? as u64;
+            match name {
+                "x" => detail.x = value,
+                "m" => detail.m = value,
+                "a" => detail.a = value,
+                "s" => detail.s = value,
+                _ => return Err(wrong_detail()),
+            }
+        }
+
+        Ok(detail)
+    }
+}
+
+impl fmt::Display for Detail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{x={},m={},a={},s={}}}", self.x, self.m, self.a, self.s)
+    }
+}
+
+impl DetailRange {
+    fn full() -> DetailRange {
+        DetailRange {
+            x: (1, 4001),
+            m: (1, 4001),
+            a: (1, 4001),
+            s: (1, 4001),
+        }
+    }
+
+    fn get(&self, prop: char) -> (u64, u64) {
+        match prop {
+            'x' => self.x,
+            'm' => self.m,
+            'a' => self.a,
+            's' => self.s,
+            _ => panic!("Unknown property"),
+        }
+    }
+
+    // Copy of the range with one property replaced, `None` if it becomes empty
+    fn with(&self, prop: char, value: (u64, u64)) -> Option<DetailRange> {
+        if value.0 >= value.1 {
+            return None;
+        }
+        let mut result = *self;
+        match prop {
+            'x' => result.x = value,
+            'm' => result.m = value,
+            'a' => result.a = value,
+            's' => result.s = value,
+            _ => panic!("Unknown property"),
+        }
+        Some(result)
+    }
+
+    fn volume(&self) -> u64 {
+        [self.x, self.m, self.a, self.s]
             .iter()
-            .for_each(|&x| match x[0].chars().next().unwrap() {
-                'x' => detail.x = u64::from_str_radix(x[1], 10).unwrap(),
-                'm' => detail.m = u64::from_str_radix(x[1], 10).unwrap(),
-                'a' => detail.a = u64::from_str_radix(x[1], 10).unwrap(),
-                's' => detail.s = u64::from_str_radix(x[1], 10).unwrap(),
-                _ => panic!("Unknown property"),
+            .map(|(start, end)| end.saturating_sub(*start))
+            .product()
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Workflow(input) => write!(f, "Wrong workflow \"{input}\""),
+            ParseError::Rule(input) => write!(f, "Wrong rule \"{input}\""),
+            ParseError::Detail(input) => write!(f, "Wrong detail \"{input}\""),
+            ParseError::NoDetails => write!(f, "There is no empty line before the details"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompileError::UnknownWorkflow(name) => write!(f, "unknown workflow {name}"),
+            CompileError::Cycle(names) => {
+                write!(f, "cycle between workflows {}", names.join(" -> "))
+            }
+        }
+    }
+}
+
+impl DecisionTree {
+    pub(crate) fn compile(workflows: &[Workflow]) -> Result<DecisionTree, CompileError> {
+        let by_name: HashMap<&str, &Workflow> =
+            workflows.iter().map(|w| (w.name.as_str(), w)).collect();
+        let mut tree = DecisionTree {
+            nodes: vec![DecisionNode::Accept, DecisionNode::Reject],
+            root: REJECT_NODE,
+        };
+
+        // only the workflows reachable from `in` are compiled,
+        // the rest can't affect any detail
+        let mut entries: HashMap<String, usize> = HashMap::new();
+        tree.root = tree.compile_workflow("in", &by_name, &mut entries, &mut vec![])?;
+
+        Ok(tree)
+    }
+
+    // Returns the entry node of the workflow.
+    // `stack` holds workflows being compiled at the moment to detect cycles.
+    fn compile_workflow(
+        &mut self,
+        name: &str,
+        by_name: &HashMap<&str, &Workflow>,
+        entries: &mut HashMap<String, usize>,
+        stack: &mut Vec<String>,
+    ) -> Result<usize, CompileError> {
+        if let Some(entry) = entries.get(name) {
+            return Ok(*entry);
+        }
+        if let Some(pos) = stack.iter().position(|w| w == name) {
+            let mut cycle = stack[pos..].to_vec();
+            cycle.push(name.to_string());
+            return Err(CompileError::Cycle(cycle));
+        }
+        let workflow = by_name
+            .get(name)
+            .ok_or_else(|| CompileError::UnknownWorkflow(name.to_string()))?;
+
+        stack.push(name.to_string());
+        // build the chain of rules from the end,
+        // details matching no rule at all are rejected
+        let mut next = REJECT_NODE;
+        for (idx, rule) in workflow.rules.iter().enumerate().rev() {
+            let dest = match &rule.dest {
+                RuleDestination::Accept => ACCEPT_NODE,
+                RuleDestination::Reject => REJECT_NODE,
+                RuleDestination::Workflow(w) => {
+                    self.compile_workflow(w, by_name, entries, stack)?
+                }
+            };
+            let origin = (name.to_string(), idx);
+            self.nodes.push(match &rule.condition {
+                Some(condition) => DecisionNode::Check {
+                    origin,
+                    condition: condition.to_owned(),
+                    matching: dest,
+                    otherwise: next,
+                },
+                None => DecisionNode::Jump { origin, to: dest },
             });
+            next = self.nodes.len() - 1;
+        }
+        stack.pop();
 
-        detail
+        entries.insert(name.to_string(), next);
+        Ok(next)
     }
 
-    fn is_accepted(&self, workflows: &Vec<Workflow>, curr_workflow_name: String) -> bool {
-        let workflow = workflows
-            .iter()
-            .find(|&w| w.name == curr_workflow_name)
-            .unwrap();
-        for rule in workflow.rules.iter() {
-            if rule.condition.as_ref().is_some_and(|c| c.is_matching(self))
-                || rule.condition.is_none()
-            {
-                match &rule.dest {
-                    RuleDestination::Accept => return true,
-                    RuleDestination::Reject => return false,
-                    RuleDestination::Workflow(w) => {
-                        return self.is_accepted(workflows, w.to_owned())
+    // Workflows visited by the detail and whether it was accepted
+    fn trace(&self, detail: &Detail) -> (Vec<String>, bool) {
+        let mut workflows: Vec<String> = vec![];
+        let mut curr = self.root;
+        loop {
+            match &self.nodes[curr] {
+                DecisionNode::Accept => return (workflows, true),
+                DecisionNode::Reject => return (workflows, false),
+                DecisionNode::Check {
+                    origin,
+                    condition,
+                    matching,
+                    otherwise,
+                } => {
+                    if workflows.last() != Some(&origin.0) {
+                        workflows.push(origin.0.to_owned());
                     }
+                    curr = if condition.is_matching(detail) {
+                        *matching
+                    } else {
+                        *otherwise
+                    };
+                }
+                DecisionNode::Jump { origin, to } => {
+                    if workflows.last() != Some(&origin.0) {
+                        workflows.push(origin.0.to_owned());
+                    }
+                    curr = *to;
                 }
             }
         }
-        return false;
     }
-}
 
-fn process_input(input: &str) -> (Vec<Workflow>, Vec<Detail>) {
-    let parts: Vec<&str> = input.split("\n\n").collect();
-    let workflows: Vec<Workflow> = parts[0]
-        .trim()
-        .split('\n')
-        .map(|x| Workflow::from_str(x))
-        .collect();
-    let details: Vec<Detail> = parts[1]
-        .trim()
-        .split('\n')
-        .map(|x| Detail::from_str(x))
-        .collect();
-    (workflows, details)
-}
+    // Number of details from the box that are accepted
+    fn count_accepted(&self, range: &DetailRange) -> u64 {
+        let mut result = 0;
+        self.propagate(self.root, *range, &mut |node, r| {
+            if node == ACCEPT_NODE {
+                result += r.volume();
+            }
+        });
+        result
+    }
 
-fn find_accepted_details(workflows: &Vec<Workflow>) -> Vec<DetailRange> {
-    // start with the most broad range
-    let starting_range = DetailRange {
-        x: (1, 4001),
-        m: (1, 4001),
-        a: (1, 4001),
-        s: (1, 4001),
-    };
-    return do_find_accepted_details(workflows, starting_range, String::from("in"));
-}
+    // Rules that no detail can ever get to and match
+    fn find_unreachable_rules(&self, workflows: &[Workflow]) -> Vec<(String, usize)> {
+        let mut reached: HashSet<(String, usize)> = HashSet::new();
+        self.propagate(
+            self.root,
+            DetailRange::full(),
+            &mut |node, range| match &self.nodes[node] {
+                DecisionNode::Check {
+                    origin, condition, ..
+                } if condition.split(range).0.is_some() => {
+                    reached.insert(origin.to_owned());
+                }
+                DecisionNode::Jump { origin, .. } => {
+                    reached.insert(origin.to_owned());
+                }
+                _ => {}
+            },
+        );
+        workflows
+            .iter()
+            .flat_map(|w| (0..w.rules.len()).map(|idx| (w.name.to_owned(), idx)))
+            .filter(|origin| !reached.contains(origin))
+            .collect()
+    }
+
+    // Push the range through the tree calling `visit` for every node
+    // with the non-empty part of the range that gets there
+    fn propagate(
+        &self,
+        node: usize,
+        range: DetailRange,
+        visit: &mut impl FnMut(usize, &DetailRange),
+    ) {
+        visit(node, &range);
+        match &self.nodes[node] {
+            DecisionNode::Accept | DecisionNode::Reject => {}
+            DecisionNode::Check {
+                condition,
+                matching,
+                otherwise,
+                ..
+            } => {
+                let (matching_range, otherwise_range) = condition.split(&range);
+                if let Some(r) = matching_range {
+                    self.propagate(*matching, r, visit);
+                }
+                if let Some(r) = otherwise_range {
+                    self.propagate(*otherwise, r, visit);
+                }
+            }
+            DecisionNode::Jump { to, .. } => self.propagate(*to, range, visit),
+        }
+    }
 
-fn do_find_accepted_details(
-    workflows: &Vec<Workflow>,
-    range: DetailRange,
-    workflow_id: String,
-) -> Vec<DetailRange> {
-    // get the current workflow
-    let workflow = workflows.iter().find(|w| w.name == workflow_id).unwrap();
-    let mut result: Vec<DetailRange> = vec![];
-    let mut upd_range = range.clone();
-    // apply each condition to the range
-    for rule in workflow.rules.iter() {
-        match &rule.condition {
-            Some(cond) => {
-                // extend result with range matching the condition
-                let matching_range = cond.get_matching_range(&upd_range);
-                if matching_range.is_some() {
-                    result.extend(process_destination(
-                        workflows,
-                        matching_range.unwrap(),
-                        rule.dest.to_owned(),
-                    ));
+    fn to_dot(&self) -> String {
+        let mut result = String::from("digraph workflows {\n");
+        for (idx, node) in self.nodes.iter().enumerate() {
+            match node {
+                DecisionNode::Accept => result += &format!("  {idx} [label=\"A\" shape=box];\n"),
+                DecisionNode::Reject => result += &format!("  {idx} [label=\"R\" shape=box];\n"),
+                DecisionNode::Check {
+                    origin,
+                    condition,
+                    matching,
+                    otherwise,
+                } => {
+                    result += &format!("  {idx} [label=\"{}: {}\"];\n", origin.0, condition);
+                    result += &format!("  {idx} -> {matching} [label=\"yes\"];\n");
+                    result += &format!("  {idx} -> {otherwise} [label=\"no\"];\n");
+                }
+                DecisionNode::Jump { origin, to } => {
+                    result += &format!("  {idx} [label=\"{}\"];\n", origin.0);
+                    result += &format!("  {idx} -> {to};\n");
                 }
-                // update upd_range so that it doesn't match the condition
-                cond.exclude_from(&mut upd_range);
             }
-            None => result.extend(process_destination(
-                workflows,
-                upd_range.clone(),
-                rule.dest.to_owned(),
-            )),
         }
+        result += "}\n";
+        result
     }
+}
 
-    return result;
+// Workflows, an empty line and then the details
+pub(crate) fn process_input(input: &str) -> Result<(Vec<Workflow>, Vec<Detail>), ParseError> {
+    let (workflows, details) = input.split_once("\n\n").ok_or(ParseError::NoDetails)?;
+    let workflows: Vec<Workflow> = workflows
+        .trim()
+        .lines()
+        .map(Workflow::from_str)
+        .collect::<Result<_, _>>()?;
+    let details: Vec<Detail> = details
+        .trim()
+        .lines()
+        .map(Detail::from_str)
+        .collect::<Result<_, _>>()?;
+    Ok((workflows, details))
 }
 
-fn process_destination(
-    workflows: &Vec<Workflow>,
-    range: DetailRange,
-    destination: RuleDestination,
-) -> Vec<DetailRange> {
-    match destination {
-        RuleDestination::Workflow(w) => do_find_accepted_details(workflows, range, w.to_owned()),
-        RuleDestination::Reject => vec![],
-        RuleDestination::Accept => vec![range],
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> (Vec<Workflow>, Vec<Detail>) {
+        process_input(include_str!("input_test.txt")).unwrap()
+    }
+
+    #[test]
+    fn example_answers() {
+        let (workflows, details) = example();
+        let tree = DecisionTree::compile(&workflows).unwrap();
+        let accepted: u64 = details
+            .iter()
+            .filter(|d| tree.trace(d).1)
+            .map(|d| d.x + d.m + d.a + d.s)
+            .sum();
+        assert_eq!(accepted, 19114);
+        assert_eq!(tree.count_accepted(&DetailRange::full()), 167409079868000);
+    }
+
+    #[test]
+    fn trace_path() {
+        let (workflows, details) = example();
+        let tree = DecisionTree::compile(&workflows).unwrap();
+        assert_eq!(
+            tree.trace(&details[0]),
+            (
+                vec!["in".into(), "qqz".into(), "qs".into(), "lnx".into()],
+                true
+            )
+        );
+        assert_eq!(
+            tree.trace(&details[1]),
+            (
+                vec!["in".into(), "px".into(), "rfg".into(), "gd".into()],
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn cycles() {
+        let input = "in{x<10:A,ab}\nab{cd}\ncd{m>5:ab,R}\n\n{x=1}";
+        let (workflows, _) = process_input(input).unwrap();
+        match DecisionTree::compile(&workflows) {
+            Err(CompileError::Cycle(names)) => assert_eq!(names, ["ab", "cd", "ab"]),
+            other => panic!("Unexpected result {other:?}"),
+        }
+
+        // a cycle among workflows not reachable from `in` doesn't matter
+        let input = "in{x<10:A,R}\nab{cd}\ncd{m>5:ab,R}\n\n{x=1}";
+        let (workflows, _) = process_input(input).unwrap();
+        let tree = DecisionTree::compile(&workflows).unwrap();
+        assert_eq!(
+            tree.count_accepted(&DetailRange::full()),
+            9 * 4000 * 4000 * 4000
+        );
+        assert_eq!(
+            tree.find_unreachable_rules(&workflows),
+            [
+                ("ab".to_string(), 0),
+                ("cd".to_string(), 0),
+                ("cd".to_string(), 1)
+            ]
+        );
     }
 }
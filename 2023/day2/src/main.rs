@@ -1,89 +1,247 @@
-use std::fs::File;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs::{self, File};
 use std::io::{self, BufRead, BufReader};
 
-struct Game {
+pub(crate) struct Game {
     id: u32,
-    sets: Vec<GameSet>
+    sets: Vec<Bag>,
 }
 
-struct GameSet {
-    red: u8,
-    green: u8,
-    blue: u8,
+// Number of cubes of every color, used both for the bag and for the sets shown from it.
+// All the colors of the game are present, even with zero cubes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Bag {
+    cubes: BTreeMap<String, u32>,
 }
 
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    WrongFormat(String),
+    UnknownColor(String),
+    DuplicateColor(String),
+    CountOverflow(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFormat(input) => write!(f, "Wrong format of \"{input}\""),
+            ParseError::UnknownColor(color) => write!(f, "Unknown color \"{color}\""),
+            ParseError::DuplicateColor(color) => write!(f, "Color \"{color}\" is repeated"),
+            ParseError::CountOverflow(count) => write!(f, "Too many cubes: {count}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 impl Game {
-    fn is_valid(&self) -> bool {
-        return self.sets.iter().all(|s| s.is_valid());
+    fn is_possible(&self, bag: &Bag) -> bool {
+        self.sets.iter().all(|s| bag.contains(s))
+    }
+
+    // The smallest bag every set of the game could be taken from
+    fn minimal_bag(&self) -> Bag {
+        self.sets
+            .iter()
+            .fold(self.sets[0].emptied(), |acc, set| acc.union(set))
+    }
+}
+
+impl Bag {
+    // The one from the puzzle
+    pub(crate) fn default_bag() -> Bag {
+        Bag::from_str("12 red, 13 green, 14 blue", None).unwrap()
     }
 
-    fn get_power(&self) -> u32 {
-        let mut red: u8 = 0;
-        let mut green: u8 = 0;
-        let mut blue: u8 = 0;
-        for GameSet{red: r, green: g, blue: b} in self.sets.iter() {
-            if *r > red { red = *r; }
-            if *g > green { green = *g; }
-            if *b > blue { blue = *b; }
+    // `3 blue, 4 red`. If the colors are given, every one of them is present
+    // in the result and no other color is allowed. Every color can be mentioned only once.
+    pub(crate) fn from_str(input: &str, colors: Option<&Bag>) -> Result<Bag, ParseError> {
+        let mut result = match colors {
+            Some(bag) => bag.emptied(),
+            None => Bag {
+                cubes: BTreeMap::new(),
+            },
+        };
+        let mut seen: HashSet<&str> = HashSet::new();
+        for part in input.split(',').map(|x| x.trim()) {
+            let (num, color) = part
+                .split_once(' ')
+                .ok_or(ParseError::WrongFormat(part.to_string()))?;
+            let count = num.parse::<u32>().map_err(|err| match err.kind() {
+                std::num::IntErrorKind::PosOverflow => ParseError::CountOverflow(num.to_string()),
+                _ => ParseError::WrongFormat(part.to_string()),
+            })?;
+            if !seen.insert(color) {
+                return Err(ParseError::DuplicateColor(color.to_string()));
+            }
+            match result.cubes.get_mut(color) {
+                Some(curr) => *curr = count,
+                None if colors.is_none() => {
+                    result.cubes.insert(color.to_string(), count);
+                }
+                None => return Err(ParseError::UnknownColor(color.to_string())),
+            }
         }
-        return u32::from(red) * u32::from(green) * u32::from(blue);
+        Ok(result)
+    }
+
+    // Same colors without any cubes
+    fn emptied(&self) -> Bag {
+        Bag {
+            cubes: self
+                .cubes
+                .keys()
+                .map(|color| (color.to_owned(), 0))
+                .collect(),
+        }
+    }
+
+    fn contains(&self, set: &Bag) -> bool {
+        set.cubes
+            .iter()
+            .all(|(color, count)| self.cubes.get(color).is_some_and(|x| x >= count))
+    }
+
+    // Bag with the largest count of each color of both bags
+    fn union(&self, other: &Bag) -> Bag {
+        let mut result = self.clone();
+        for (color, count) in other.cubes.iter() {
+            let curr = result.cubes.entry(color.to_owned()).or_insert(0);
+            *curr = (*curr).max(*count);
+        }
+        result
+    }
+
+    fn power(&self) -> Option<u64> {
+        self.cubes
+            .values()
+            .try_fold(1u64, |acc, x| acc.checked_mul(*x as u64))
     }
 }
 
-impl GameSet {
-    fn is_valid(&self) -> bool {
-        // valid if it has not more than 12 red cubes, 13 green cubes, and 14 blue cubes
-        return self.red <= 12 && self.green <= 13 && self.blue <= 14;
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<String> = self
+            .cubes
+            .iter()
+            .map(|(color, count)| format!("{count} {color}"))
+            .collect();
+        write!(f, "{}", parts.join(", "))
     }
 }
 
-fn parse_game(input: &str) -> Game {
+// `Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green`
+pub(crate) fn parse_game(input: &str, colors: &Bag) -> Result<Game, ParseError> {
+    let wrong_format = || ParseError::WrongFormat(input.to_string());
     let (game_id, other) = input
-        .trim_start_matches("Game ")
-        .split_once(":")
-        .unwrap();
-    return Game {
-        id: u32::from_str_radix(game_id, 10).unwrap(),
-        sets: other.split(";").map(|set| parse_game_set(set)).collect()
+        .strip_prefix("Game ")
+        .and_then(|x| x.split_once(':'))
+        .ok_or_else(wrong_format)?;
+    Ok(Game {
+        id: game_id.parse().map_err(|_| wrong_format())?,
+        sets: other
+            .split(';')
+            .map(|set| Bag::from_str(set, Some(colors)))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+// The bag is either `--bag "12 red, 13 green, 14 blue"`
+// or `--bag-file <path>` with the same content
+fn read_bag() -> io::Result<Bag> {
+    let args: Vec<String> = std::env::args().collect();
+    let arg_value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let input = match (arg_value("--bag"), arg_value("--bag-file")) {
+        (Some(bag), _) => bag.to_owned(),
+        (None, Some(path)) => fs::read_to_string(path)?,
+        (None, None) => return Ok(Bag::default_bag()),
     };
+    Bag::from_str(input.trim(), None).map_err(to_io_error)
 }
 
-fn parse_game_set(input: &str) -> GameSet {
-    let mut red = 0u8;
-    let mut green = 0u8;
-    let mut blue = 0u8;
-    let parts = input.split(",").map(|x| x.trim());
-    for part in parts {
-        let (num, color) = part.split_once(" ").unwrap();
-        match color {
-            "red" => red = u8::from_str_radix(num, 10).unwrap(),
-            "green" => green = u8::from_str_radix(num, 10).unwrap(),
-            "blue" => blue = u8::from_str_radix(num, 10).unwrap(),
-            _ => ()
-        }
-    }
-    return GameSet { red, green, blue };
+fn to_io_error(err: ParseError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
 }
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day2/src/input.txt";
+    let verbose = std::env::args().any(|arg| arg == "--verbose");
+    let bag = read_bag()?;
 
     // Open the file
     let file = File::open(input_path)?;
     let reader = BufReader::new(file);
 
-    let mut valid_sum = 0;
-    let mut games_power = 0;
+    let mut valid_sum: u32 = 0;
+    let mut games_power: u64 = 0;
+    let mut all_games_bag = bag.emptied();
+    let overflow = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
     for line in reader.lines() {
-        let game_data = line?;
-        let game = parse_game(&game_data);
-        if game.is_valid() {
-            valid_sum += game.id;
+        let game = parse_game(&line?, &bag).map_err(to_io_error)?;
+        let minimal_bag = game.minimal_bag();
+        if verbose {
+            println!("Game {}: {}", game.id, minimal_bag);
         }
-        games_power += game.get_power();
+
+        if game.is_possible(&bag) {
+            valid_sum = valid_sum
+                .checked_add(game.id)
+                .ok_or_else(|| overflow("Sum overflow"))?;
+        }
+        games_power = minimal_bag
+            .power()
+            .and_then(|power| games_power.checked_add(power))
+            .ok_or_else(|| overflow("Power overflow"))?;
+        all_games_bag = all_games_bag.union(&minimal_bag);
     }
     println!("Part1: {}, Part2: {}", valid_sum, games_power);
+    println!("Minimal bag for all games: {}", all_games_bag);
+
+    Ok(())
+}
 
-    return Ok(());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let bag = Bag::default_bag();
+        let games: Vec<Game> = include_str!("input_test.txt")
+            .lines()
+            .map(|line| parse_game(line, &bag).unwrap())
+            .collect();
+        let valid_sum: u32 = games
+            .iter()
+            .filter(|game| game.is_possible(&bag))
+            .map(|game| game.id)
+            .sum();
+        let power: u64 = games
+            .iter()
+            .map(|game| game.minimal_bag().power().unwrap())
+            .sum();
+        assert_eq!((valid_sum, power), (8, 2286));
+    }
+
+    #[test]
+    fn wrong_sets() {
+        let bag = Bag::default_bag();
+        assert!(matches!(
+            parse_game("Game 1: 3 blue, 4 blue", &bag),
+            Err(ParseError::DuplicateColor(color)) if color == "blue"
+        ));
+        assert!(matches!(
+            parse_game("Game 1: 3 pink", &bag),
+            Err(ParseError::UnknownColor(_))
+        ));
+        // the same color in different sets is fine
+        assert!(parse_game("Game 1: 3 blue; 4 blue", &bag).is_ok());
+    }
 }
@@ -1,14 +1,12 @@
 use std::collections::{HashMap, VecDeque};
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
-use std::time::Instant;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day20/src/input.txt";
 
-    let now = Instant::now();
-
     // Part1
     // open the file and parse input
     let file = File::open(input_path)?;
@@ -16,7 +14,8 @@ fn main() -> io::Result<()> {
     let mut modules: HashMap<String, Module> = HashMap::new();
     for line in reader.lines() {
         let l = line?;
-        let module = Module::from_str(&l);
+        let module =
+            Module::from_str(&l).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
         modules.insert(module.id.to_owned(), module);
     }
 
@@ -35,6 +34,7 @@ fn main() -> io::Result<()> {
     // println!("Part1: {:?}", part1_result);
 
     // Part2
+    // prints the presses when `fc` sends a high pulse, until it's stopped
     let mut count = 0;
 
     loop {
@@ -44,14 +44,10 @@ fn main() -> io::Result<()> {
             println!("{count}");
         }
     }
-    println!("Part2: {}", count);
-
-    println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
 }
 
 #[derive(Debug)]
-struct Module {
+pub(crate) struct Module {
     id: String,
     destinations: Vec<String>,
     state: ModuleType,
@@ -64,37 +60,66 @@ enum ModuleType {
     Broadcaster,
 }
 
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    WrongFormat(String),
+    UnknownModuleType(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFormat(input) => write!(f, "Wrong format of \"{input}\""),
+            ParseError::UnknownModuleType(name) => write!(f, "Unknown module type of \"{name}\""),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 impl Module {
-    fn from_str(input: &str) -> Module {
-        let parts: Vec<&str> = input.split("->").map(|x| x.trim()).collect();
-        let destinations: Vec<String> = parts[1].split(',').map(|x| x.trim().to_string()).collect();
+    // `%a -> b, c`, `&inv -> a` or `broadcaster -> a, b, c`
+    pub(crate) fn from_str(input: &str) -> Result<Module, ParseError> {
+        let (name, destinations) = input
+            .split_once("->")
+            .ok_or(ParseError::WrongFormat(input.to_string()))?;
+        let name = name.trim();
+        let destinations: Vec<String> = destinations
+            .split(',')
+            .map(|x| x.trim().to_string())
+            .collect();
+        if destinations.iter().any(|x| x.is_empty()) {
+            return Err(ParseError::WrongFormat(input.to_string()));
+        }
 
-        if parts[0] == "broadcaster" {
-            return Module {
+        if name == "broadcaster" {
+            return Ok(Module {
                 id: String::from("broadcaster"),
                 destinations,
                 state: ModuleType::Broadcaster,
-            };
+            });
         }
 
-        let id: String = parts[0].chars().skip(1).collect();
-        if parts[0].starts_with('&') {
-            Module {
-                id,
-                destinations,
-                state: ModuleType::Conjunction(HashMap::new()),
-            }
+        let state = if name.starts_with('&') {
+            ModuleType::Conjunction(HashMap::new())
+        } else if name.starts_with('%') {
+            ModuleType::FlipFlop(false)
         } else {
-            Module {
-                id,
-                destinations,
-                state: ModuleType::FlipFlop(false),
-            }
+            return Err(ParseError::UnknownModuleType(name.to_string()));
+        };
+        let id = name[1..].to_string();
+        if id.is_empty() {
+            return Err(ParseError::WrongFormat(input.to_string()));
         }
+        Ok(Module {
+            id,
+            destinations,
+            state,
+        })
     }
 }
 
-fn init_conjunction_modules(modules: &mut HashMap<String, Module>) -> () {
+fn init_conjunction_modules(modules: &mut HashMap<String, Module>) {
     // when a conjunction is initialized
     // it must be low for all inputs
     let mut conjunction_sources: HashMap<String, Vec<String>> = HashMap::new();
@@ -102,16 +127,13 @@ fn init_conjunction_modules(modules: &mut HashMap<String, Module>) -> () {
         for dest_id in m.destinations.iter() {
             if modules.contains_key(dest_id) {
                 let dest_module = &modules[dest_id];
-                match dest_module.state {
-                    ModuleType::Conjunction(_) => {
-                        conjunction_sources
-                            .entry(dest_id.to_owned())
-                            .or_insert(vec![]);
-                        conjunction_sources
-                            .entry(dest_id.to_owned())
-                            .and_modify(|x| x.push(m.id.to_owned()));
-                    }
-                    _ => (),
+                if let ModuleType::Conjunction(_) = dest_module.state {
+                    conjunction_sources
+                        .entry(dest_id.to_owned())
+                        .or_insert(vec![]);
+                    conjunction_sources
+                        .entry(dest_id.to_owned())
+                        .and_modify(|x| x.push(m.id.to_owned()));
                 }
             }
         }
@@ -165,8 +187,8 @@ fn push_the_button(modules: &mut HashMap<String, Module>) -> (u64, u64, bool) {
                     curr_state.entry(from).and_modify(|x| *x = is_high_pulse);
 
                     // send new signals
-                    let next_pulse = !curr_state.values().all(|v| *v == true);
-                    if next_pulse == true && module.id == String::from("fc") {
+                    let next_pulse = !curr_state.values().all(|v| *v);
+                    if next_pulse && module.id == "fc" {
                         is_high_result = true
                     }
                     module.destinations.iter().for_each(|d| {
@@ -179,5 +201,5 @@ fn push_the_button(modules: &mut HashMap<String, Module>) -> (u64, u64, bool) {
 
     // let is_single_low_pulse = rx_low_pulses_count == 1;
     // println!("Low: {rx_low_pulses_count:?}, high: {rx_high_pulses_count}");
-    return (low_pulses_count, high_pulses_count, is_high_result);
+    (low_pulses_count, high_pulses_count, is_high_result)
 }
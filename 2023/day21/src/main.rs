@@ -1,8 +1,13 @@
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use grid::{Grid, Input};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
 use std::time::Instant;
 
+const PART2_STEPS: u64 = 26501365;
+// Largest number of periods sampled before giving up on the extrapolation,
+// the BFS block grows with the square of it
+const MAX_SAMPLES: u64 = 24;
+
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day21/src/input.txt";
@@ -10,53 +15,34 @@ fn main() -> io::Result<()> {
     let now = Instant::now();
 
     // Part1
-    // open the file and parse input
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let mut data: Vec<Vec<char>> = vec![];
-    for line in reader.lines() {
-        let l = line?;
-        data.push(l.chars().collect());
-    }
+    // map the input into memory and use it as a grid without copying
+    let input = Input::open(input_path)?;
+    let data =
+        Grid::parse(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
-    // Part1
-    let start_pos = find_start(&data);
+    // the walk starts from `S`
+    let start_pos = find_start(&data)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Start is not found"))?;
     let mut cache = HashMap::new();
     let positions = get_positions_after_steps(&data, vec![start_pos], 64, &mut cache);
     println!("Part1: {:?}", positions.len());
 
     // Part2
-    let mut infinite_cache: HashMap<(i32, i32), Vec<(i32, i32)>> = HashMap::new();
-    let infinite_start_pos = (start_pos.0 as i32, start_pos.1 as i32);
-    let mut increments: Vec<i32> = vec![];
-    get_increments_by_steps(
-        &data,
-        vec![infinite_start_pos],
-        500,
-        &mut infinite_cache,
-        &mut increments,
-    );
-    println!("{increments:?}");
-    let part2_result = find_sum(&increments, 26501365);
-    println!("Part2: {}", part2_result);
+    match count_reachable_plots(&data, start_pos, PART2_STEPS) {
+        Some(part2_result) => println!("Part2: {}", part2_result),
+        None => println!("Part2: the counts don't settle into a quadratic polynomial"),
+    }
 
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
 }
 
-fn find_start(data: &Vec<Vec<char>>) -> (usize, usize) {
-    for i in 0..data.len() {
-        for j in 0..data[0].len() {
-            if data[i][j] == 'S' {
-                return (i, j);
-            }
-        }
-    }
-    (0, 0)
+fn find_start(data: &Grid) -> Option<(usize, usize)> {
+    data.find(b'S')
 }
 
 fn get_positions_after_steps(
-    data: &Vec<Vec<char>>,
+    data: &Grid,
     curr_positions: Vec<(usize, usize)>,
     steps_left: i32,
     cache: &mut HashMap<(usize, usize), Vec<(usize, usize)>>,
@@ -67,169 +53,258 @@ fn get_positions_after_steps(
 
     let mut next_positions: HashSet<(usize, usize)> = HashSet::new();
     for curr_pos in curr_positions.iter() {
-        for neib in get_neibs(data, &curr_pos, cache) {
+        for neib in get_neibs(data, curr_pos, cache) {
             next_positions.insert(neib);
         }
     }
 
-    return get_positions_after_steps(
-        data,
-        next_positions.iter().map(|x| (x.0, x.1)).collect(),
-        steps_left - 1,
-        cache,
-    );
-}
-
-fn get_increments_by_steps(
-    data: &Vec<Vec<char>>,
-    curr_positions: Vec<(i32, i32)>,
-    steps_left: i32,
-    cache: &mut HashMap<(i32, i32), Vec<(i32, i32)>>,
-    result: &mut Vec<i32>,
-) -> () {
-    if steps_left == 0 {
-        return;
-    }
-
-    let mut next_positions: HashSet<(i32, i32)> = HashSet::new();
-    for curr_pos in curr_positions.iter() {
-        // transpose
-        let transposed = transpose(*curr_pos, data.len() as i32, data[0].len() as i32);
-        for neib in get_infinite_neibs(data, &transposed, cache) {
-            // transpose backwards
-            let retransposed_neib = (
-                neib.0 + (curr_pos.0 - transposed.0),
-                neib.1 + (curr_pos.1 - transposed.1),
-            );
-            next_positions.insert(retransposed_neib);
-        }
-    }
-
-    result.push((next_positions.len() - curr_positions.len()) as i32);
-    get_increments_by_steps(
+    get_positions_after_steps(
         data,
-        next_positions.iter().map(|x| (x.0, x.1)).collect(),
+        next_positions.into_iter().collect(),
         steps_left - 1,
         cache,
-        result,
-    );
+    )
 }
 
 fn get_neibs(
-    data: &Vec<Vec<char>>,
+    data: &Grid,
     pos: &(usize, usize),
     cache: &mut HashMap<(usize, usize), Vec<(usize, usize)>>,
 ) -> Vec<(usize, usize)> {
-    if cache.contains_key(pos) {
-        return cache.get(pos).unwrap().to_owned();
+    if let Some(neibs) = cache.get(pos) {
+        return neibs.to_owned();
     }
     let mut result: Vec<(usize, usize)> = vec![];
-    if pos.0 > 0 && data[pos.0 - 1][pos.1] != '#' {
+    if pos.0 > 0 && data[(pos.0 - 1, pos.1)] != b'#' {
         result.push((pos.0 - 1, pos.1));
     }
-    if pos.0 < data.len() - 1 && data[pos.0 + 1][pos.1] != '#' {
+    if pos.0 < data.height() - 1 && data[(pos.0 + 1, pos.1)] != b'#' {
         result.push((pos.0 + 1, pos.1));
     }
-    if pos.1 > 0 && data[pos.0][pos.1 - 1] != '#' {
+    if pos.1 > 0 && data[(pos.0, pos.1 - 1)] != b'#' {
         result.push((pos.0, pos.1 - 1));
     }
-    if pos.1 < data[0].len() - 1 && data[pos.0][pos.1 + 1] != '#' {
+    if pos.1 < data.width() - 1 && data[(pos.0, pos.1 + 1)] != b'#' {
         result.push((pos.0, pos.1 + 1));
     }
     cache.insert(*pos, result.to_owned());
     result
 }
 
-fn get_infinite_neibs(
-    data: &Vec<Vec<char>>,
-    pos: &(i32, i32),
-    cache: &mut HashMap<(i32, i32), Vec<(i32, i32)>>,
-) -> Vec<(i32, i32)> {
-    if cache.contains_key(pos) {
-        return cache.get(pos).unwrap().to_owned();
-    }
-    let mut result: Vec<(i32, i32)> = vec![];
-    if pos.0 > 0 && data[pos.0 as usize - 1][pos.1 as usize] != '#'
-        || pos.0 == 0 && data[data.len() - 1][pos.1 as usize] != '#'
-    {
-        result.push((pos.0 - 1, pos.1));
+// Number of garden plots reachable in exactly `steps` steps on the infinitely tiled map.
+//
+// Small step counts are answered directly from BFS distances. For big ones
+// the number of plots reachable after `remainder + n * period` steps is a
+// quadratic polynomial of `n` once the frontier has left the first few tiles,
+// so we sample it on a bounded block of tiles, wait until the third
+// differences vanish and extrapolate from there.
+// `None` if they don't vanish within `MAX_SAMPLES` periods.
+fn count_reachable_plots(data: &Grid, start: (usize, usize), steps: u64) -> Option<u64> {
+    let (height, width) = (data.height() as u64, data.width() as u64);
+
+    // The period has to be even: with an odd one the parity of every plot
+    // flips between samples and the sequence splits into two interleaved ones
+    let mut period = lcm(height, width);
+    if period % 2 == 1 {
+        period *= 2;
     }
-    if (pos.0 as usize) < data.len() - 1 && data[pos.0 as usize + 1][pos.1 as usize] != '#'
-        || pos.0 as usize == data.len() - 1 && data[0][pos.1 as usize] != '#'
-    {
-        result.push((pos.0 + 1, pos.1));
+    let remainder = steps % period;
+
+    let mut samples = 6;
+    while samples <= MAX_SAMPLES {
+        let max_steps = remainder + samples * period;
+        if steps <= max_steps {
+            return Some(reachable_counts(data, start, steps)[steps as usize]);
+        }
+
+        let counts = reachable_counts(data, start, max_steps);
+        let values: Vec<i128> = (0..=samples)
+            .map(|n| counts[(remainder + n * period) as usize] as i128)
+            .collect();
+        if let Some(first) = find_quadratic_tail(&values) {
+            let t = ((steps - remainder) / period - first as u64) as i128;
+            let (y0, y1, y2) = (values[first], values[first + 1], values[first + 2]);
+            let d1 = y1 - y0;
+            let d2 = y2 - 2 * y1 + y0;
+            return Some((y0 + t * d1 + t * (t - 1) / 2 * d2) as u64);
+        }
+
+        samples *= 2;
     }
-    if pos.1 > 0 && data[pos.0 as usize][pos.1 as usize - 1] != '#'
-        || pos.1 == 0 && data[pos.0 as usize][data[0].len() - 1] != '#'
-    {
-        result.push((pos.0, pos.1 - 1));
+    None
+}
+
+// Index of the first value after which the sequence is a quadratic polynomial.
+// At least two third differences have to be zero to trust the tail.
+fn find_quadratic_tail(values: &[i128]) -> Option<usize> {
+    let third_diffs: Vec<i128> = values
+        .windows(4)
+        .map(|w| w[3] - 3 * w[2] + 3 * w[1] - w[0])
+        .collect();
+    let stable_len = third_diffs.iter().rev().take_while(|d| **d == 0).count();
+    if stable_len < 2 {
+        return None;
     }
-    if (pos.1 as usize) < data[0].len() - 1 && data[pos.0 as usize][pos.1 as usize + 1] != '#'
-        || pos.1 as usize == data[0].len() - 1 && data[pos.0 as usize][0] != '#'
-    {
-        result.push((pos.0, pos.1 + 1));
+    Some(third_diffs.len() - stable_len)
+}
+
+// For every step count in 0..=max_steps calculate the number of plots
+// reachable in exactly that many steps on the infinitely tiled map.
+//
+// BFS runs on a square block of tiles around the starting one that is
+// large enough for no path of length max_steps to leave it.
+fn reachable_counts(data: &Grid, start: (usize, usize), max_steps: u64) -> Vec<u64> {
+    let (height, width) = (data.height(), data.width());
+    let radius = (max_steps as usize).div_ceil(height.min(width));
+    let (block_height, block_width) = ((2 * radius + 1) * height, (2 * radius + 1) * width);
+
+    let mut visited = vec![false; block_height * block_width];
+    let mut by_distance: Vec<u64> = vec![0; max_steps as usize + 1];
+    let block_start = (start.0 + radius * height, start.1 + radius * width);
+    visited[block_start.0 * block_width + block_start.1] = true;
+
+    let mut queue: VecDeque<((usize, usize), u64)> = VecDeque::new();
+    queue.push_back((block_start, 0));
+    while let Some(((i, j), dist)) = queue.pop_front() {
+        by_distance[dist as usize] += 1;
+        if dist == max_steps {
+            continue;
+        }
+
+        let mut neibs: Vec<(usize, usize)> = vec![];
+        if i > 0 {
+            neibs.push((i - 1, j));
+        }
+        if i < block_height - 1 {
+            neibs.push((i + 1, j));
+        }
+        if j > 0 {
+            neibs.push((i, j - 1));
+        }
+        if j < block_width - 1 {
+            neibs.push((i, j + 1));
+        }
+        for (ni, nj) in neibs {
+            if visited[ni * block_width + nj] || data[(ni % height, nj % width)] == b'#' {
+                continue;
+            }
+            visited[ni * block_width + nj] = true;
+            queue.push_back(((ni, nj), dist + 1));
+        }
+    }
+
+    // A plot is reachable in exactly `s` steps if it is reachable
+    // in `d <= s` steps and `s - d` is even (we can step back and forth).
+    // That needs a neighbor to step to, a walled in start can't move at all.
+    if max_steps > 0 && by_distance[1] == 0 {
+        return by_distance;
+    }
+    let mut result = by_distance;
+    for s in 2..result.len() {
+        result[s] += result[s - 2];
     }
-    cache.insert(*pos, result.to_owned());
     result
 }
 
-fn transpose(curr_pos: (i32, i32), height: i32, width: i32) -> (i32, i32) {
-    let mut result = curr_pos.to_owned();
-    while result.0 < 0 {
-        result.0 += height;
+fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
-    while result.0 >= height {
-        result.0 -= height;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+...........
+.....###.#.
+.###.##..#.
+..#.#...#..
+....#.#....
+.##..S####.
+.##..#...#.
+.......##..
+.##.#.####.
+.##..##.##.
+...........
+";
+
+    // Breadth first search on the tiled map itself, the plots reached with the same
+    // parity as `steps` can be walked back and forth to until the end
+    fn count_reachable_plots_brute_force(data: &Grid, start: (usize, usize), steps: u64) -> u64 {
+        let (height, width) = (data.height() as i64, data.width() as i64);
+        let start = (start.0 as i64, start.1 as i64);
+        let mut seen: HashSet<(i64, i64)> = HashSet::from([start]);
+        let mut frontier = vec![start];
+        let mut result = steps.is_multiple_of(2) as u64;
+        for step in 1..=steps {
+            let mut next_frontier: Vec<(i64, i64)> = vec![];
+            for (i, j) in frontier {
+                for (ni, nj) in [(i - 1, j), (i + 1, j), (i, j - 1), (i, j + 1)] {
+                    let tile = data[(
+                        ni.rem_euclid(height) as usize,
+                        nj.rem_euclid(width) as usize,
+                    )];
+                    if tile != b'#' && seen.insert((ni, nj)) {
+                        next_frontier.push((ni, nj));
+                    }
+                }
+            }
+            if step % 2 == steps % 2 {
+                result += next_frontier.len() as u64;
+            }
+            frontier = next_frontier;
+        }
+        result
     }
-    while result.1 < 0 {
-        result.1 += width;
+
+    fn assert_matches_brute_force(input: &str) {
+        let data = Grid::parse(input.as_bytes()).unwrap();
+        let start = find_start(&data).unwrap();
+        // the period is 22, so everything above 6 periods is extrapolated
+        for steps in [0, 1, 6, 10, 11, 23, 50, 100, 133, 300, 500] {
+            assert_eq!(
+                count_reachable_plots(&data, start, steps),
+                Some(count_reachable_plots_brute_force(&data, start, steps)),
+                "{steps} steps"
+            );
+        }
     }
-    while result.1 >= width {
-        result.1 -= width;
+
+    #[test]
+    fn extrapolation_matches_brute_force() {
+        assert_matches_brute_force(EXAMPLE);
     }
-    return result;
-}
 
-fn find_sum(increments: &Vec<i32>, last_step: i32) -> u64 {
-    // find lengh of a cycle by going from end and looking for an arithmetic progression
-    let mut sum = 1u64;
-    let cycle_len = find_cycle_len(increments);
-    println!("Cycle len: {cycle_len}");
-    // sum everything until the beginning of the cycle
-    let first_cycle_idx = increments.len() - cycle_len;
-    println!("Cycle start idx: {first_cycle_idx}");
-    sum += increments
-        .iter()
-        .take(first_cycle_idx)
-        .map(|x| *x as u64)
-        .sum::<u64>();
-
-    let first_hundred = increments.iter().take(100).map(|x| *x as u64).sum::<u64>() + 1;
-    println!("First hundred steps: {first_hundred}");
-    // for each element in the cycle find the sum of the arithmetic progression
-    for i in 0..cycle_len {
-        let first_idx = first_cycle_idx + i;
-        let difference = increments[first_idx] - increments[first_idx - cycle_len];
-        let number_of_elements =
-            ((last_step - 1 - first_idx as i32) / (cycle_len as i32) + 1) as u64;
-        sum += (number_of_elements as u64)
-            * (2u64 * increments[first_idx] as u64 + (number_of_elements - 1) * difference as u64)
-            / 2;
-    }
-
-    sum
-}
+    #[test]
+    fn off_centre_start() {
+        let input = EXAMPLE.replace('S', ".");
+        let mut input = input.into_bytes();
+        // the second plot of the second row
+        input[12 + 1] = b'S';
+        assert_matches_brute_force(std::str::from_utf8(&input).unwrap());
+    }
 
-fn find_cycle_len(sequence: &Vec<i32>) -> usize {
-    let last_idx = sequence.len() - 1;
-    for l in 1..sequence.len() / 3 {
-        if sequence[last_idx] - sequence[last_idx - l]
-            == sequence[last_idx - l] - sequence[last_idx - 2 * l]
-            && sequence[last_idx - 1] - sequence[last_idx - 1 - l]
-                == sequence[last_idx - 1 - l] - sequence[last_idx - 1 - 2 * l]
-        {
-            return l;
+    #[test]
+    fn walled_in_start_stays_put() {
+        let data = Grid::parse(b".#.\n#S#\n.#.\n").unwrap();
+        let start = find_start(&data).unwrap();
+        assert_eq!(count_reachable_plots(&data, start, 0), Some(1));
+        for steps in [1, 2, 3, 1000, PART2_STEPS] {
+            assert_eq!(count_reachable_plots(&data, start, steps), Some(0));
         }
     }
-    return 0;
+
+    #[test]
+    fn missing_start() {
+        let data = Grid::parse(b"...\n...\n").unwrap();
+        assert_eq!(find_start(&data), None);
+    }
 }
@@ -1,6 +1,8 @@
-use std::cmp::min;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::fs::File;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
 use std::io::{self, BufRead, BufReader};
 use std::time::Instant;
 
@@ -17,227 +19,334 @@ fn main() -> io::Result<()> {
     let mut data: Vec<Brick> = vec![];
     for (id, line) in reader.lines().enumerate() {
         let l = line?;
-        data.push(Brick::from_str(&l, id as u32));
+        let brick = Brick::from_str(&l, id)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        data.push(brick);
     }
 
     // Part1
-    let fallen = fall(&data);
-    let map = fallen
-        .iter()
-        .map(|x| (x.id, x.to_owned()))
-        .collect::<HashMap<u32, Brick>>();
-    let bricks_to_desintegrate = fallen
-        .iter()
-        .filter(|&b| b.is_safe_to_remove(&map))
-        .map(|b| b.to_owned())
-        .collect::<Vec<Brick>>();
-    let part1_result = bricks_to_desintegrate.iter().count();
+    let (settled, graph) =
+        settle(&data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    // Every brick is checked on its own, so with the `parallel` feature the checks
+    // are spread over the cores. The cascades of part 2 come from the dominator tree
+    // in linear time and stay sequential.
+    #[cfg(feature = "parallel")]
+    let ids = (0..settled.len()).into_par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let ids = 0..settled.len();
+    let part1_result = ids.filter(|id| graph.is_safe_to_remove(*id)).count();
     println!("Part1: {:?}", part1_result);
 
     // Part2
-    let mut cache: HashMap<u32, u32> = HashMap::new();
-    for b in fallen.iter() {
-        b.count_cascading_bricks_count(&map, &mut cache);
-    }
-    let part2_result: u32 = cache.values().sum();
+    let part2_result: usize = graph.cascade_counts().iter().sum();
     println!("Part2: {}", part2_result);
 
+    // Optionally save the settled stack in the input format
+    if let Some(output_path) = std::env::args().nth(1) {
+        fs::write(output_path, format_bricks(&settled))?;
+    }
+
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Brick {
-    start: XYZ,
-    end: XYZ,
-    id: u32,
-    supports: HashSet<u32>,
-    supporters: HashSet<u32>,
+pub(crate) struct Brick {
+    start: Xyz,
+    end: Xyz,
+    id: usize,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct XYZ {
+struct Xyz {
     x: usize,
     y: usize,
     z: usize,
 }
 
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    WrongFormat(String),
+    WrongCoordinate(String),
+    // z starts from 1, 0 is the ground
+    InGround(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFormat(input) => write!(f, "Wrong format of \"{input}\""),
+            ParseError::WrongCoordinate(input) => write!(f, "Wrong coordinate in \"{input}\""),
+            ParseError::InGround(input) => write!(f, "Brick \"{input}\" is in the ground"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// A brick that has to go up to settle, so it overlaps the bricks below it
+#[derive(Debug)]
+struct OverlapError(String);
+
+impl fmt::Display for OverlapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Brick \"{}\" overlaps the bricks below it", self.0)
+    }
+}
+
+impl std::error::Error for OverlapError {}
+
+// Who rests on whom after all the bricks have settled.
+// Both lists are indexed by the position of the brick in the settled list.
+#[derive(Debug, Clone, Default)]
+struct SupportGraph {
+    supports: Vec<Vec<usize>>,
+    supported_by: Vec<Vec<usize>>,
+}
+
 impl Brick {
-    fn from_str(input: &String, id: u32) -> Brick {
-        let parts = input.split('~').collect::<Vec<&str>>();
-        let start = XYZ::from_str(parts[0]);
-        let end = XYZ::from_str(parts[1]);
-        Brick {
-            start,
-            end,
+    // `1,0,1~1,2,1`
+    pub(crate) fn from_str(input: &str, id: usize) -> Result<Brick, ParseError> {
+        let (start, end) = input
+            .split_once('~')
+            .ok_or(ParseError::WrongFormat(input.to_string()))?;
+        let brick = Brick {
+            start: Xyz::from_str(start)?,
+            end: Xyz::from_str(end)?,
             id,
-            supports: HashSet::new(),
-            supporters: HashSet::new(),
+        };
+        if brick.lowest_z() == 0 {
+            return Err(ParseError::InGround(input.to_string()));
         }
+        Ok(brick)
+    }
+
+    fn to_input_string(&self) -> String {
+        format!(
+            "{},{},{}~{},{},{}",
+            self.start.x, self.start.y, self.start.z, self.end.x, self.end.y, self.end.z
+        )
     }
 
     fn lowest_z(&self) -> usize {
-        min(self.start.z, self.end.z)
+        self.start.z.min(self.end.z)
     }
 
-    fn is_vertical(&self) -> bool {
-        self.start.z != self.end.z
+    fn height(&self) -> usize {
+        self.start.z.abs_diff(self.end.z) + 1
     }
 
-    fn is_falling(&mut self, bricks: &mut Vec<Brick>) -> (bool, HashSet<u32>) {
-        if self.lowest_z() == 1 {
-            return (false, HashSet::new());
-        }
+    // All (x, y) cells covered by the brick when looking from above
+    fn footprint(&self) -> Vec<(usize, usize)> {
+        let (min_x, max_x) = (self.start.x.min(self.end.x), self.start.x.max(self.end.x));
+        let (min_y, max_y) = (self.start.y.min(self.end.y), self.start.y.max(self.end.y));
+        (min_x..=max_x)
+            .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+            .collect()
+    }
 
-        // check if there's any crossing if we move down
-        let mut next_pos = Brick {
-            start: self.start,
-            end: self.end,
-            id: self.id,
-            supports: HashSet::new(),
-            supporters: HashSet::new(),
+    // Bricks only fall, `None` if the brick would have to go up
+    fn move_to_z(&mut self, z: usize) -> Option<()> {
+        let shift = self.lowest_z().checked_sub(z)?;
+        self.start.z -= shift;
+        self.end.z -= shift;
+        Some(())
+    }
+}
+
+impl Xyz {
+    fn from_str(input: &str) -> Result<Xyz, ParseError> {
+        let parts = input
+            .split(',')
+            .map(|x| x.trim().parse())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| ParseError::WrongCoordinate(input.to_string()))?;
+        let [x, y, z] = parts[..] else {
+            return Err(ParseError::WrongFormat(input.to_string()));
         };
-        next_pos.start.z -= 1;
-        next_pos.end.z -= 1;
-        let mut supporters = HashSet::new();
-        for b in bricks.iter_mut().filter(|b| b.id != self.id) {
-            if next_pos.is_crossing(b) {
-                b.supports.insert(self.id);
-                supporters.insert(b.id);
-            }
-        }
+        Ok(Xyz { x, y, z })
+    }
+}
 
-        return (supporters.is_empty(), supporters);
+impl SupportGraph {
+    // Bricks lying directly on top of the given one
+    fn supports(&self, id: usize) -> &[usize] {
+        &self.supports[id]
     }
 
-    fn is_crossing(&self, other: &Brick) -> bool {
-        if self.is_vertical() && other.is_vertical() {
-            let self_range = (self.lowest_z(), self.start.z.max(self.end.z));
-            let other_range = (other.lowest_z(), other.start.z.max(other.end.z));
-            return self.start.x == other.start.x
-                && self.start.y == other.start.y
-                && ranges_are_intersecting(self_range, other_range);
-        }
+    // Bricks the given one is lying on, empty for bricks on the ground
+    fn supported_by(&self, id: usize) -> &[usize] {
+        &self.supported_by[id]
+    }
 
-        if !self.is_vertical() && !other.is_vertical() {
-            if self.lowest_z() != other.lowest_z() {
-                return false;
-            }
-            // check intersections on X and Y axes
-            let self_x_range = (self.start.x.min(self.end.x), self.start.x.max(self.end.x));
-            let other_x_range = (
-                other.start.x.min(other.end.x),
-                other.start.x.max(other.end.x),
-            );
-            let self_y_range = (self.start.y.min(self.end.y), self.start.y.max(self.end.y));
-            let other_y_range = (
-                other.start.y.min(other.end.y),
-                other.start.y.max(other.end.y),
-            );
-            return ranges_are_intersecting(self_x_range, other_x_range)
-                && ranges_are_intersecting(self_y_range, other_y_range);
-        }
+    fn is_safe_to_remove(&self, id: usize) -> bool {
+        // Check if any brick that we support would fall without us
+        self.supports(id)
+            .iter()
+            .all(|upper| self.supported_by(*upper).len() > 1)
+    }
 
-        if self.is_vertical() && !other.is_vertical() {
-            if self.lowest_z() > other.lowest_z() || self.start.z.max(self.end.z) < other.lowest_z()
-            {
-                return false;
-            }
-            if self.start.x < other.start.x.min(other.end.x)
-                || self.start.x > other.start.x.max(other.end.x)
-            {
-                return false; // no intersection on X axis
-            }
-            if self.start.y < other.start.y.min(other.end.y)
-                || self.start.y > other.start.y.max(other.end.y)
-            {
-                return false; // no intersection on X axis
-            }
-            return true;
-        } else {
-            return other.is_crossing(self);
+    // Immediate dominators of the bricks with the ground as a root.
+    // A brick falls when one of its dominators is removed and
+    // only then, so the dominator tree describes all the chain reactions.
+    //
+    // Returns parent of every brick in the tree, `None` means the ground.
+    fn dominator_tree(&self) -> Vec<Option<usize>> {
+        let bricks_count = self.supported_by.len();
+        let mut parents: Vec<Option<usize>> = vec![None; bricks_count];
+        let mut depths: Vec<usize> = vec![0; bricks_count];
+
+        // every supporter is processed before the bricks lying on it
+        for id in self.topological_order() {
+            let mut supporters = self.supported_by(id).iter().map(|s| Some(*s));
+            let first = supporters.next().flatten();
+            let idom = supporters.fold(first, |acc, s| {
+                lowest_common_ancestor(acc, s, &parents, &depths)
+            });
+            parents[id] = idom;
+            depths[id] = idom.map_or(1, |p| depths[p] + 1);
         }
+
+        parents
     }
 
-    fn is_safe_to_remove(&self, bricks: &HashMap<u32, Brick>) -> bool {
-        // Check if any brick that we support would fall without us
-        for sup_id in self.supports.iter() {
-            let other = bricks.get(sup_id).unwrap();
-            if other.supporters.iter().all(|s_id| *s_id == self.id) {
-                return false;
+    // For every brick - how many other bricks would fall if it was disintegrated
+    fn cascade_counts(&self) -> Vec<usize> {
+        let parents = self.dominator_tree();
+        let mut subtree_sizes: Vec<usize> = vec![1; parents.len()];
+        for id in self.topological_order().into_iter().rev() {
+            if let Some(parent) = parents[id] {
+                subtree_sizes[parent] += subtree_sizes[id];
             }
         }
-        return true;
+        subtree_sizes.iter().map(|size| size - 1).collect()
     }
 
-    fn count_cascading_bricks_count(
-        &self,
-        bricks: &HashMap<u32, Brick>,
-        cache: &mut HashMap<u32, u32>,
-    ) -> u32 {
-        if cache.contains_key(&self.id) {
-            return cache.get(&self.id).unwrap().to_owned();
-        }
-
-        let mut result = 0;
-        let mut bricks_temp = bricks.to_owned();
-        let mut falling_bricks: VecDeque<u32> = VecDeque::new();
-        falling_bricks.push_back(self.id);
-        while let Some(brick_id) = falling_bricks.pop_front() {
-            let falling_brick = bricks_temp.remove(&brick_id).unwrap();
-            // find which supports are going to fall
-            for sup_id in falling_brick.supports.iter() {
-                bricks_temp.entry(*sup_id).and_modify(|upper| {
-                    upper.supporters.remove(&brick_id);
-                    if upper.supporters.is_empty() {
-                        result += 1;
-                        falling_bricks.push_back(upper.id);
-                    }
-                });
+    fn topological_order(&self) -> Vec<usize> {
+        let mut in_degrees: Vec<usize> = self.supported_by.iter().map(|s| s.len()).collect();
+        let mut result: Vec<usize> = (0..in_degrees.len())
+            .filter(|id| in_degrees[*id] == 0)
+            .collect();
+        let mut idx = 0;
+        while idx < result.len() {
+            for upper in self.supports(result[idx]) {
+                in_degrees[*upper] -= 1;
+                if in_degrees[*upper] == 0 {
+                    result.push(*upper);
+                }
             }
+            idx += 1;
         }
-        cache
-            .entry(self.id)
-            .and_modify(|x| *x = result)
-            .or_insert(result);
-        return result;
+        result
     }
 }
 
-impl XYZ {
-    fn from_str(input: &str) -> XYZ {
-        let parts = input.split(',').collect::<Vec<&str>>();
-        XYZ {
-            x: usize::from_str_radix(parts[0], 10).unwrap(),
-            y: usize::from_str_radix(parts[1], 10).unwrap(),
-            z: usize::from_str_radix(parts[2], 10).unwrap(),
-        }
+fn lowest_common_ancestor(
+    a: Option<usize>,
+    b: Option<usize>,
+    parents: &[Option<usize>],
+    depths: &[usize],
+) -> Option<usize> {
+    let depth = |node: Option<usize>| node.map_or(0, |n| depths[n]);
+    let (mut a, mut b) = (a, b);
+    while depth(a) > depth(b) {
+        a = parents[a.unwrap()];
+    }
+    while depth(b) > depth(a) {
+        b = parents[b.unwrap()];
     }
+    while a != b {
+        a = parents[a.unwrap()];
+        b = parents[b.unwrap()];
+    }
+    a
 }
 
-fn fall(initial_bricks: &Vec<Brick>) -> Vec<Brick> {
+// Let all the bricks fall and build the graph of supports on the way.
+// Bricks are dropped from the lowest one and the highest occupied cell
+// of every (x, y) column is tracked in a height map.
+// The settled bricks are sorted by id and the graph uses their positions in that order.
+fn settle(initial_bricks: &[Brick]) -> Result<(Vec<Brick>, SupportGraph), OverlapError> {
     let mut result: Vec<Brick> = initial_bricks.to_vec();
+    result.sort_unstable_by_key(|b| b.id);
 
-    // sort by Z asc
-    result.sort_unstable_by(|a, b| a.lowest_z().cmp(&b.lowest_z()));
-
-    // one by one move bricks down
-    for i in 0..result.len() {
-        loop {
-            let (is_falling, supporters) = result[i].to_owned().is_falling(&mut result);
-            if is_falling {
-                result[i].start.z -= 1;
-                result[i].end.z -= 1;
-            } else {
-                result[i].supporters = supporters;
-                break;
-            }
+    let mut order: Vec<usize> = (0..result.len()).collect();
+    order.sort_unstable_by_key(|idx| result[*idx].lowest_z());
+
+    let mut graph = SupportGraph {
+        supports: vec![vec![]; result.len()],
+        supported_by: vec![vec![]; result.len()],
+    };
+    // (x, y) -> (top z, position of the top brick)
+    let mut height_map: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    for idx in order {
+        let footprint = result[idx].footprint();
+        let top = footprint
+            .iter()
+            .filter_map(|cell| height_map.get(cell).map(|(z, _)| *z))
+            .max()
+            .unwrap_or(0);
+
+        // find the bricks this one is going to lie on
+        let mut supporters: Vec<usize> = footprint
+            .iter()
+            .filter_map(|cell| height_map.get(cell))
+            .filter(|(z, _)| *z == top)
+            .map(|(_, other)| *other)
+            .collect();
+        supporters.sort_unstable();
+        supporters.dedup();
+        for supporter in supporters.iter() {
+            graph.supports[*supporter].push(idx);
+        }
+        graph.supported_by[idx] = supporters;
+
+        result[idx]
+            .move_to_z(top + 1)
+            .ok_or_else(|| OverlapError(result[idx].to_input_string()))?;
+        let new_top = top + result[idx].height();
+        for cell in footprint {
+            height_map.insert(cell, (new_top, idx));
         }
     }
 
-    return result;
+    Ok((result, graph))
+}
+
+fn format_bricks(bricks: &[Brick]) -> String {
+    bricks.iter().map(|b| b.to_input_string() + "\n").collect()
 }
 
-fn ranges_are_intersecting(a: (usize, usize), b: (usize, usize)) -> bool {
-    a.0 <= b.1 && b.0 <= a.1
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Vec<Brick> {
+        input
+            .lines()
+            .enumerate()
+            .map(|(id, line)| Brick::from_str(line, id).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn example() {
+        let bricks = parse(
+            "1,0,1~1,2,1\n0,0,2~2,0,2\n0,2,3~2,2,3\n0,0,4~0,2,4\n2,0,5~2,2,5\n0,1,6~2,1,6\n1,1,8~1,1,9",
+        );
+        let (settled, graph) = settle(&bricks).unwrap();
+        let safe = (0..settled.len())
+            .filter(|idx| graph.is_safe_to_remove(*idx))
+            .count();
+        assert_eq!(safe, 5);
+        assert_eq!(graph.cascade_counts().iter().sum::<usize>(), 7);
+    }
+
+    #[test]
+    fn overlapping_bricks() {
+        // the first brick is inside the second one, which settles first
+        // and leaves its top above the first one
+        let bricks = parse("0,0,2~0,0,2\n0,0,1~0,0,3");
+        assert!(settle(&bricks).is_err());
+    }
 }
@@ -1,206 +1,307 @@
-use core::panic;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::fmt;
+use std::io;
 use std::time::Instant;
 
-use petgraph::dot::Dot;
+use grid::{Grid, Input};
 use petgraph::stable_graph::{NodeIndex, StableUnGraph};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day23/src/input.txt";
+    let render = std::env::args().any(|arg| arg == "--render");
 
     let now = Instant::now();
 
     // Part1
-    // open the file and parse input
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let mut data: Vec<Vec<char>> = vec![];
-    for line in reader.lines() {
-        let l = line?;
-        data.push(l.chars().collect());
-    }
-
-    // Part1
-    let (start_j, _) = data[0]
-        .iter()
-        .enumerate()
-        .find(|&(_, ch)| *ch == '.')
-        .unwrap();
-    let (end_j, _) = data[data.len() - 1]
-        .iter()
-        .enumerate()
-        .find(|&(_, ch)| *ch == '.')
-        .unwrap();
-    println!(
-        "Start: ({},{}), end: ({},{})",
-        0,
-        start_j,
-        data.len() - 1,
-        end_j
-    );
-    let max_path_len = find_max_path(
-        (0, start_j),
-        (data.len() - 1, end_j),
-        &HashSet::new(),
-        &data,
-    );
-    let part1_result = max_path_len - 1; // do not count starting position
-    println!("Part1: {:?}", part1_result);
+    // map the input into memory and use it as a grid without copying
+    let input = Input::open(input_path)?;
+    let data =
+        Grid::parse(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
-    // Part2
     let graph = build_graph(&data);
 
-    // println!("{:?}", Dot::new(&graph));
+    // Part1
+    let junctions = JunctionGraph::from_graph(&graph, true)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    match junctions.find_max_path() {
+        Some((part1_result, path)) => {
+            println!("Part1: {:?}", part1_result);
+            if render {
+                print_path(&data, &junctions.path_cells(&path));
+            }
+        }
+        None => println!("Part1: no path from the start to the end"),
+    }
 
-    let part2_result = find_max_path_ignoring_slopes(
-        graph.node_indices().next().unwrap(),
-        graph.node_indices().last().unwrap(),
-        &HashSet::new(),
-        &graph,
-    )
-    .unwrap();
-    println!("Part2: {}", part2_result);
+    // Part2
+    let junctions = JunctionGraph::from_graph(&graph, false)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    match junctions.find_max_path() {
+        Some((part2_result, path)) => {
+            println!("Part2: {}", part2_result);
+            if render {
+                print_path(&data, &junctions.path_cells(&path));
+            }
+        }
+        None => println!("Part2: no path from the start to the end"),
+    }
 
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
 }
 
-fn find_max_path(
-    start: (usize, usize),
-    end: (usize, usize),
-    visited: &HashSet<(usize, usize)>,
-    data: &Vec<Vec<char>>,
-) -> u32 {
-    if start == end {
-        return 1;
+// The visited junctions of a path are kept as bits of a u64
+const MAX_JUNCTIONS: usize = 64;
+
+#[derive(Debug)]
+pub(crate) struct TooManyJunctions(usize);
+
+impl fmt::Display for TooManyJunctions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "The maze has {} junctions, at most {MAX_JUNCTIONS} are supported",
+            self.0
+        )
     }
+}
 
-    let mut new_visited = visited.to_owned();
-    new_visited.insert(start);
+impl std::error::Error for TooManyJunctions {}
 
-    // get not visited neighbors
-    let neibs = get_neighbors(start, &new_visited, data);
+// Compressed graph of the maze where nodes are start, end, dead ends and crossroads.
+// Junctions are numbered in the order of `build_graph` nodes,
+// so the start is the first one and the end is the last one.
+struct JunctionGraph {
+    positions: Vec<(usize, usize)>,
+    edges: Vec<Vec<Corridor>>,
+}
 
-    // calc longest path for each of them
-    match neibs
-        .iter()
-        .map(|x| find_max_path(*x, end, &new_visited, data))
-        .max()
-    {
-        Some(value) => 1 + value,
-        None => 0,
-    }
-    // choose max and return the result
+// Directed connection between two junctions
+struct Corridor {
+    to: usize,
+    len: u32,
+    // all the cells after the starting junction including the final one
+    cells: Vec<(usize, usize)>,
+}
+
+// Cells between two nodes of the maze graph, from the source of the edge to its target
+#[derive(Clone, Debug)]
+struct Segment {
+    // all the cells after the source including the target
+    cells: Vec<(usize, usize)>,
+    // whether the slopes let us walk it from the source to the target and back
+    forward: bool,
+    backward: bool,
 }
 
-fn get_neighbors(
-    node: (usize, usize),
-    visited: &HashSet<(usize, usize)>,
-    data: &Vec<Vec<char>>,
-) -> Vec<(usize, usize)> {
-    let mut candidates: Vec<(i32, i32)> = vec![];
-    match data[node.0][node.1] {
-        '.' => {
-            candidates.push((node.0 as i32 - 1, node.1 as i32));
-            candidates.push((node.0 as i32, node.1 as i32 + 1));
-            candidates.push((node.0 as i32 + 1, node.1 as i32));
-            candidates.push((node.0 as i32, node.1 as i32 - 1));
+impl Segment {
+    fn step(from: (usize, usize), to: (usize, usize), data: &Grid) -> Segment {
+        Segment {
+            cells: vec![to],
+            forward: slope_allows(data, from, to),
+            backward: slope_allows(data, to, from),
+        }
+    }
+
+    // The same cells walked from the target back to `source`
+    fn reversed(&self, source: (usize, usize)) -> Segment {
+        let mut cells: Vec<(usize, usize)> = self.cells.iter().rev().skip(1).copied().collect();
+        cells.push(source);
+        Segment {
+            cells,
+            forward: self.backward,
+            backward: self.forward,
         }
-        '^' => candidates.push((node.0 as i32 - 1, node.1 as i32)),
-        '>' => candidates.push((node.0 as i32, node.1 as i32 + 1)),
-        'v' => candidates.push((node.0 as i32 + 1, node.1 as i32)),
-        '<' => candidates.push((node.0 as i32, node.1 as i32 - 1)),
-        _ => panic!("Wrong tile"),
     }
 
-    // filter candidates by coordinate values, visited and cell type
-    return candidates
-        .iter()
-        .filter(|x| x.0 >= 0 && x.1 < data.len() as i32 && x.1 >= 0 && x.1 < data[0].len() as i32)
-        .map(|&x| (x.0 as usize, x.1 as usize))
-        .filter(|x| !visited.contains(x) && data[x.0][x.1] != '#')
-        .collect();
+    fn joined(&self, next: &Segment) -> Segment {
+        Segment {
+            cells: [self.cells.as_slice(), next.cells.as_slice()].concat(),
+            forward: self.forward && next.forward,
+            backward: self.backward && next.backward,
+        }
+    }
 }
 
-fn find_max_path_ignoring_slopes(
-    curr: NodeIndex,
-    end: NodeIndex,
-    visited: &HashSet<NodeIndex>,
-    graph: &StableUnGraph<(usize, usize), u32>,
-) -> Option<u32> {
-    if curr == end {
-        return Some(0);
+impl JunctionGraph {
+    // Both directions of every edge of the compressed graph,
+    // the ones going against a slope are left out when `respect_slopes` is set
+    fn from_graph(
+        graph: &StableUnGraph<(usize, usize), Segment>,
+        respect_slopes: bool,
+    ) -> Result<JunctionGraph, TooManyJunctions> {
+        let positions: Vec<(usize, usize)> = graph.node_indices().map(|n| graph[n]).collect();
+        if positions.len() > MAX_JUNCTIONS {
+            return Err(TooManyJunctions(positions.len()));
+        }
+        let indices: HashMap<NodeIndex, usize> = graph
+            .node_indices()
+            .enumerate()
+            .map(|(idx, node)| (node, idx))
+            .collect();
+
+        let mut edges: Vec<Vec<Corridor>> = (0..positions.len()).map(|_| vec![]).collect();
+        for edge in graph.edge_references() {
+            let (from, to) = (indices[&edge.source()], indices[&edge.target()]);
+            let segment = edge.weight();
+            if segment.forward || !respect_slopes {
+                edges[from].push(Corridor {
+                    to,
+                    len: segment.cells.len() as u32,
+                    cells: segment.cells.to_owned(),
+                });
+            }
+            if segment.backward || !respect_slopes {
+                edges[to].push(Corridor {
+                    to: from,
+                    len: segment.cells.len() as u32,
+                    cells: segment.reversed(positions[from]).cells,
+                });
+            }
+        }
+
+        Ok(JunctionGraph { positions, edges })
+    }
+
+    // Length of the longest path from start to end and the junctions it visits.
+    // The search is split by the first two moves, and with the `parallel` feature
+    // the branches run on all the cores. Of the longest paths the smallest one
+    // is returned, so the result doesn't depend on the order the branches finish in.
+    fn find_max_path(&self) -> Option<(u32, Vec<usize>)> {
+        let start = 0;
+        let end = self.positions.len() - 1;
+
+        let mut prefixes: Vec<(Vec<usize>, u32)> = vec![(vec![start], 0)];
+        for _ in 0..2 {
+            prefixes = prefixes
+                .iter()
+                .flat_map(|(path, len)| {
+                    let last = *path.last().unwrap();
+                    if last == end {
+                        return vec![(path.to_owned(), *len)];
+                    }
+                    self.edges[last]
+                        .iter()
+                        .filter(|c| !path.contains(&c.to))
+                        .map(|c| {
+                            let mut next_path = path.to_owned();
+                            next_path.push(c.to);
+                            (next_path, len + c.len)
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+
+        #[cfg(feature = "parallel")]
+        let prefixes = prefixes.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let prefixes = prefixes.into_iter();
+        prefixes
+            .filter_map(|(mut path, len)| {
+                let visited = path.iter().fold(0u64, |acc, junction| acc | 1 << junction);
+                let mut best = None;
+                self.search(
+                    *path.last().unwrap(),
+                    end,
+                    visited,
+                    len,
+                    &mut path,
+                    &mut best,
+                );
+                best
+            })
+            .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)))
     }
 
-    let mut new_visited = visited.to_owned();
-    new_visited.insert(curr);
-
-    // get not visited neighbors
-    let neibs = graph
-        .neighbors_undirected(curr)
-        .filter(|n| !visited.contains(n))
-        .collect::<Vec<NodeIndex>>();
-
-    // calc longest path for each of them
-    neibs
-        .iter()
-        .map(|x| {
-            let (edge, _) = graph.find_edge_undirected(curr, *x).unwrap();
-            let dist_to_neib = graph.edge_weight(edge).unwrap().to_owned();
-
-            match find_max_path_ignoring_slopes(*x, end, &new_visited, graph) {
-                Some(value) => Some(dist_to_neib + value),
-                None => None,
+    fn search(
+        &self,
+        curr: usize,
+        end: usize,
+        visited: u64,
+        len: u32,
+        path: &mut Vec<usize>,
+        best: &mut Option<(u32, Vec<usize>)>,
+    ) {
+        if curr == end {
+            if best.as_ref().is_none_or(|(best_len, _)| len > *best_len) {
+                *best = Some((len, path.to_owned()));
             }
-        })
-        .filter_map(|x| x)
-        .max()
+            return;
+        }
+
+        for corridor in self.edges[curr].iter() {
+            let bit = 1 << corridor.to;
+            if visited & bit != 0 {
+                continue;
+            }
+            path.push(corridor.to);
+            self.search(
+                corridor.to,
+                end,
+                visited | bit,
+                len + corridor.len,
+                path,
+                best,
+            );
+            path.pop();
+        }
+    }
+
+    // Expand a sequence of junctions into all the grid cells along the way
+    fn path_cells(&self, path: &[usize]) -> Vec<(usize, usize)> {
+        let mut result = vec![self.positions[path[0]]];
+        for pair in path.windows(2) {
+            let corridor = self.edges[pair[0]]
+                .iter()
+                .filter(|c| c.to == pair[1])
+                .max_by_key(|c| c.len)
+                .unwrap();
+            result.extend(corridor.cells.iter());
+        }
+        result
+    }
 }
 
-fn get_neighbors_ignoring_slopes(
-    node: (usize, usize),
-    visited: &HashSet<(usize, usize)>,
-    data: &Vec<Vec<char>>,
-) -> Vec<(usize, usize)> {
-    let candidates: Vec<(i32, i32)> = vec![
-        (node.0 as i32 - 1, node.1 as i32),
-        (node.0 as i32, node.1 as i32 + 1),
-        (node.0 as i32 + 1, node.1 as i32),
-        (node.0 as i32, node.1 as i32 - 1),
-    ];
-
-    // filter candidates by coordinate values, visited and cell type
-    return candidates
-        .iter()
-        .filter(|x| x.0 >= 0 && x.1 < data.len() as i32 && x.1 >= 0 && x.1 < data[0].len() as i32)
-        .map(|&x| (x.0 as usize, x.1 as usize))
-        .filter(|x| !visited.contains(x) && data[x.0][x.1] != '#')
-        .collect();
+// Check if the slope on the `from` cell lets us go to `to`
+fn slope_allows(data: &Grid, from: (usize, usize), to: (usize, usize)) -> bool {
+    match data[(from.0, from.1)] {
+        b'^' => to.0 + 1 == from.0,
+        b'>' => to.1 == from.1 + 1,
+        b'v' => to.0 == from.0 + 1,
+        b'<' => to.1 + 1 == from.1,
+        _ => true,
+    }
 }
 
-fn print_path(data: &Vec<Vec<char>>, path: &Vec<(usize, usize)>) {
-    for i in 0..data.len() {
-        for j in 0..data[0].len() {
+fn print_path(data: &Grid, path: &[(usize, usize)]) {
+    let path: HashSet<&(usize, usize)> = path.iter().collect();
+    for (i, row) in data.rows().enumerate() {
+        for (j, ch) in row.iter().enumerate() {
             if path.contains(&(i, j)) {
                 print!("0");
             } else {
-                print!("{}", data[i][j]);
+                print!("{}", *ch as char);
             }
         }
         println!();
     }
 }
 
-fn build_graph(data: &Vec<Vec<char>>) -> StableUnGraph<(usize, usize), u32> {
-    let mut g: StableUnGraph<(usize, usize), u32> = StableUnGraph::default();
-    // Add noodes
+// Graph of the walkable cells where every cell with two neighbors is merged
+// into the edge between them, so only the start, the end, dead ends
+// and crossroads are left
+fn build_graph(data: &Grid) -> StableUnGraph<(usize, usize), Segment> {
+    let mut g: StableUnGraph<(usize, usize), Segment> = StableUnGraph::default();
+    // Add nodes
     let mut nodes: HashMap<(usize, usize), NodeIndex> = HashMap::new();
-    for i in 0..data.len() {
-        for j in 0..data[0].len() {
-            if data[i][j] != '#' {
+    for (i, row) in data.rows().enumerate() {
+        for (j, ch) in row.iter().enumerate() {
+            if *ch != b'#' {
                 let n = g.add_node((i, j));
                 nodes.insert((i, j), n);
             }
@@ -208,17 +309,17 @@ fn build_graph(data: &Vec<Vec<char>>) -> StableUnGraph<(usize, usize), u32> {
     }
 
     // Add edges
-    for i in 0..data.len() {
-        for j in 0..data[0].len() {
-            if data[i][j] != '#' {
-                let node = nodes.get(&(i, j)).unwrap();
-                if i > 0 && data[i - 1][j] != '#' {
-                    let other = nodes.get(&(i - 1, j)).unwrap();
-                    g.add_edge(*node, *other, 1);
+    for (i, row) in data.rows().enumerate() {
+        for (j, ch) in row.iter().enumerate() {
+            if *ch != b'#' {
+                let node = nodes[&(i, j)];
+                if i > 0 && data[(i - 1, j)] != b'#' {
+                    let segment = Segment::step((i, j), (i - 1, j), data);
+                    g.add_edge(node, nodes[&(i - 1, j)], segment);
                 }
-                if j > 0 && data[i][j - 1] != '#' {
-                    let other = nodes.get(&(i, j - 1)).unwrap();
-                    g.add_edge(*node, *other, 1);
+                if j > 0 && data[(i, j - 1)] != b'#' {
+                    let segment = Segment::step((i, j), (i, j - 1), data);
+                    g.add_edge(node, nodes[&(i, j - 1)], segment);
                 }
             }
         }
@@ -239,17 +340,15 @@ fn build_graph(data: &Vec<Vec<char>>) -> StableUnGraph<(usize, usize), u32> {
         let next_nodes: Vec<NodeIndex> = g.neighbors(curr).filter(|n| *n != prev).collect();
         if next_nodes.len() == 1 {
             // If only two neibs (prev and next) - remove current and attach next to prev
-            let (edge_to_prev, _) = g.find_edge_undirected(curr, prev).unwrap();
-            let prev_weight = g.edge_weight(edge_to_prev).unwrap().to_owned();
-            let next = next_nodes.iter().next().unwrap();
-            let (edge_to_next, _) = g.find_edge_undirected(curr, *next).unwrap();
-            let next_weight = g.edge_weight(edge_to_next).unwrap().to_owned();
+            let next = next_nodes[0];
+            let to_curr = oriented_segment(&g, prev, curr);
+            let from_curr = oriented_segment(&g, curr, next);
             g.remove_node(curr);
-            g.add_edge(prev, *next, prev_weight + next_weight);
+            g.add_edge(prev, next, to_curr.joined(&from_curr));
 
-            if !visited.contains(next) {
-                visited.insert(*next);
-                queue.push_back((prev, *next));
+            if !visited.contains(&next) {
+                visited.insert(next);
+                queue.push_back((prev, next));
             }
         } else {
             for next in next_nodes.iter().filter(|n| !visited.contains(n)) {
@@ -259,5 +358,97 @@ fn build_graph(data: &Vec<Vec<char>>) -> StableUnGraph<(usize, usize), u32> {
         }
     }
 
-    return g;
+    g
+}
+
+// Segment of the edge between two nodes walked from `from` to `to`
+fn oriented_segment(
+    g: &StableUnGraph<(usize, usize), Segment>,
+    from: NodeIndex,
+    to: NodeIndex,
+) -> Segment {
+    let edge = g.find_edge(from, to).unwrap();
+    let segment = &g[edge];
+    if g.edge_endpoints(edge).unwrap().0 == from {
+        segment.to_owned()
+    } else {
+        segment.reversed(g[to])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+#.#####################
+#.......#########...###
+#######.#########.#.###
+###.....#.>.>.###.#.###
+###v#####.#v#.###.#.###
+###.>...#.#.#.....#...#
+###v###.#.#.#########.#
+###...#.#.#.......#...#
+#####.#.#.#######.#.###
+#.....#.#.#.......#...#
+#.#####.#.#.#########v#
+#.#...#...#...###...>.#
+#.#.#v#######v###.###v#
+#...#.>.#...>.>.#.###.#
+#####v#.#.###v#.#.###.#
+#.....#...#...#.#.#...#
+#.#########.###.#.#.###
+#...###...#...#...#.###
+###.###.#.###v#####v###
+#...#...#.#.>.>.#.>.###
+#.###.###.#.###.#.#v###
+#.....###...###...#...#
+#####################.#
+";
+
+    fn longest_paths(input: &str) -> (Option<u32>, Option<u32>) {
+        let data = Grid::parse(input.as_bytes()).unwrap();
+        let graph = build_graph(&data);
+        let [part1, part2] = [true, false].map(|respect_slopes| {
+            JunctionGraph::from_graph(&graph, respect_slopes)
+                .unwrap()
+                .find_max_path()
+                .map(|(len, _)| len)
+        });
+        (part1, part2)
+    }
+
+    // A straight corridor with a dead end on every other row,
+    // every dead end and its crossroad add two junctions to the start and the end
+    fn dead_ends(count: usize) -> String {
+        let mut input = String::from("#.##\n");
+        for _ in 0..count {
+            input.push_str("#.##\n#..#\n");
+        }
+        input.push_str("#.##\n");
+        input
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(longest_paths(EXAMPLE), (Some(94), Some(154)));
+    }
+
+    #[test]
+    fn junction_limit() {
+        assert_eq!(longest_paths(&dead_ends(31)), (Some(63), Some(63)));
+
+        let input = dead_ends(32);
+        let data = Grid::parse(input.as_bytes()).unwrap();
+        assert!(matches!(
+            JunctionGraph::from_graph(&build_graph(&data), true),
+            Err(TooManyJunctions(66))
+        ));
+    }
+
+    #[test]
+    fn no_path() {
+        // the slopes point away from each other
+        assert_eq!(longest_paths("#.#\n#v#\n#^#\n#.#\n"), (None, Some(3)));
+    }
 }
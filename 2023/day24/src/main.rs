@@ -1,10 +1,10 @@
-use core::panic;
-use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::time::Instant;
 
-use vecmath::{vec3_add, vec3_cross, vec3_dot, vec3_sub};
+// Rock velocities searched on the x and y axes
+const MAX_VELOCITY: i128 = 1000;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
@@ -19,38 +19,40 @@ fn main() -> io::Result<()> {
     let mut data: Vec<Hailstone> = vec![];
     for line in reader.lines() {
         let l = line?;
-        data.push(Hailstone::from_str(&l));
+        let hailstone = Hailstone::from_str(&l)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        data.push(hailstone);
     }
-    // println!("{data:?}");
 
     // Part1
     // let test_area = (7f64, 27f64);
     let test_area = (200000000000000f64, 400000000000000f64);
     let crossings = find_crossings(&data, test_area);
-    // println!("{crossings:?}");
     let part1_result = crossings.len();
     println!("Part1: {:?}", part1_result);
 
     // Part2
-    let line = find_line_crossing_all(&data.iter().map(|x| x.to_int()).collect());
+    let data_int: Vec<HailstoneInt> = data.iter().map(|x| x.to_int()).collect();
+    let Some(line) = find_line_crossing_all(&data_int, MAX_VELOCITY) else {
+        println!("Part2: no rock velocity up to {MAX_VELOCITY} hits every hailstone");
+        return Ok(());
+    };
     println!("Line: {line:?}");
     let part2_result = line.x + line.y + line.z;
     println!("Part2: {}", part2_result);
 
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-struct Hailstone {
-    x: f64,
-    y: f64,
-    z: f64,
-    vx: f64,
-    vy: f64,
-    vz: f64,
-    k: f64,
-    b: f64,
+pub(crate) struct Hailstone {
+    x: i64,
+    y: i64,
+    z: i64,
+    vx: i64,
+    vy: i64,
+    vz: i64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -63,6 +65,23 @@ struct HailstoneInt {
     vz: i128,
 }
 
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    WrongFormat(String),
+    WrongNumber(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFormat(input) => write!(f, "Wrong format of \"{input}\""),
+            ParseError::WrongNumber(input) => write!(f, "Wrong number \"{input}\""),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 impl HailstoneInt {
     fn adjust(&self, vx_adj: i128, vy_adj: i128, vz_adj: i128) -> HailstoneInt {
         HailstoneInt {
@@ -74,161 +93,56 @@ impl HailstoneInt {
             vz: self.vz - vz_adj,
         }
     }
-
-    fn is_parallel(&self, other: &HailstoneInt) -> bool {
-        let vec_cross = vec3_cross([self.vx, self.vy, self.vz], [other.vx, other.vy, other.vz]);
-        return vec_cross[0] == 0 && vec_cross[1] == 0 && vec_cross[2] == 0;
-    }
-
-    fn is_crossing_3d(&self, other: &HailstoneInt) -> bool {
-        let vec_cross = vec3_cross([self.vx, self.vy, self.vz], [other.vx, other.vy, other.vz]);
-        if vec_cross[0] == 0 && vec_cross[1] == 0 && vec_cross[2] == 0 {
-            return false;
-        }
-        let diff = vec3_sub([other.x, other.y, other.z], [self.x, self.y, self.z]);
-        vec3_dot(vec_cross, diff) == 0
-    }
-
-    fn cross_point_3d(&self, other: &HailstoneInt) -> Option<(i128, i128, i128)> {
-        if !self.is_crossing_3d(other) {
-            return None;
-        }
-
-        let s = (self.vy * (other.x - self.x) - self.vx * (other.y - self.y))
-            / (self.vx * other.vy - self.vy * other.vx);
-        let result = vec3_add(
-            [other.x, other.y, other.z],
-            [s * other.vx, s * other.vy, s * other.vz],
-        );
-        Some((result[0], result[1], result[2]))
-    }
 }
 
 impl Hailstone {
-    fn identity() -> Hailstone {
-        Hailstone {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-            vx: 0.0,
-            vy: 0.0,
-            vz: 0.0,
-            k: 0.0,
-            b: 0.0,
-        }
-    }
-
-    fn from_str(input: &str) -> Hailstone {
-        let parts: Vec<Vec<&str>> = input
-            .split('@')
-            .map(|x| x.trim().split(',').map(|y| y.trim()).collect())
-            .collect();
-        let x = i128::from_str_radix(parts[0][0], 10).unwrap() as f64;
-        let y = i128::from_str_radix(parts[0][1], 10).unwrap() as f64;
-        let z = i128::from_str_radix(parts[0][2], 10).unwrap() as f64;
-        let vx = i128::from_str_radix(parts[1][0], 10).unwrap() as f64;
-        let vy = i128::from_str_radix(parts[1][1], 10).unwrap() as f64;
-        let vz = i128::from_str_radix(parts[1][2], 10).unwrap() as f64;
-        let k = vy / vx;
-        let b = y - k * x;
-
-        Hailstone {
+    // `19, 13, 30 @ -2, 1, -2`, the numbers are i64 so that the exact
+    // arithmetic of part 2 fits into i128
+    pub(crate) fn from_str(input: &str) -> Result<Hailstone, ParseError> {
+        let wrong_format = || ParseError::WrongFormat(input.to_string());
+        let (position, velocity) = input.split_once('@').ok_or_else(wrong_format)?;
+        let triple = |part: &str| -> Result<[i64; 3], ParseError> {
+            let numbers = part
+                .split(',')
+                .map(|x| {
+                    x.trim()
+                        .parse()
+                        .map_err(|_| ParseError::WrongNumber(x.trim().to_string()))
+                })
+                .collect::<Result<Vec<i64>, _>>()?;
+            numbers.try_into().map_err(|_| wrong_format())
+        };
+        let [x, y, z] = triple(position)?;
+        let [vx, vy, vz] = triple(velocity)?;
+
+        Ok(Hailstone {
             x,
             y,
             z,
             vx,
             vy,
             vz,
-            k,
-            b,
-        }
+        })
     }
 
-    fn from_pair(p1: (f64, f64, f64), p2: (f64, f64, f64)) -> Hailstone {
-        Hailstone {
-            x: p1.0,
-            y: p1.1,
-            z: p1.2,
-            vx: p2.0 - p1.0,
-            vy: p2.1 - p1.1,
-            vz: p2.2 - p1.2,
-            k: 0.0,
-            b: 0.0,
-        }
-    }
-
-    fn to_int(&self) -> HailstoneInt {
+    fn to_int(self) -> HailstoneInt {
         HailstoneInt {
-            x: self.x as i128,
-            y: self.y as i128,
-            z: self.z as i128,
-            vx: self.vx as i128,
-            vy: self.vy as i128,
-            vz: self.vz as i128,
-        }
-    }
-
-    fn adjust(&self, vx_adj: i32, vy_adj: i32, vz_adj: i32) -> Hailstone {
-        Hailstone {
-            x: self.x,
-            y: self.y,
-            z: self.z,
-            vx: self.vx - vx_adj as f64,
-            vy: self.vy - vy_adj as f64,
-            vz: self.vz - vz_adj as f64,
-            k: self.k,
-            b: self.b,
-        }
-    }
-
-    fn is_parallel(&self, other: &Hailstone) -> bool {
-        let vec_cross = vec3_cross([self.vx, self.vy, self.vz], [other.vx, other.vy, other.vz]);
-        return vec_cross[0] < f64::EPSILON
-            && vec_cross[1] < f64::EPSILON
-            && vec_cross[2] < f64::EPSILON;
-    }
-
-    fn is_crossing_3d(&self, other: &Hailstone) -> bool {
-        let vec_cross = vec3_cross([self.vx, self.vy, self.vz], [other.vx, other.vy, other.vz]);
-        if vec_cross[0] < f64::EPSILON && vec_cross[1] < f64::EPSILON && vec_cross[2] < f64::EPSILON
-        {
-            return false;
+            x: self.x.into(),
+            y: self.y.into(),
+            z: self.z.into(),
+            vx: self.vx.into(),
+            vy: self.vy.into(),
+            vz: self.vz.into(),
         }
-        let diff = vec3_sub([other.x, other.y, other.z], [self.x, self.y, self.z]);
-        vec3_dot(vec_cross, diff) < f64::EPSILON
-    }
-
-    fn cross_point_3d(&self, other: &Hailstone) -> Option<(f64, f64, f64)> {
-        if !self.is_crossing_3d(other) {
-            return None;
-        }
-
-        let s = (self.vy * (other.x - self.x) - self.vx * (other.y - self.y))
-            / (self.vx * other.vy - self.vy * other.vx);
-        let result = vec3_add(
-            [other.x, other.y, other.z],
-            [s * other.vx, s * other.vy, s * other.vz],
-        );
-        Some((result[0], result[1], result[2]))
-    }
-
-    fn is_crossing_3d_all(&self, data: &Vec<Hailstone>) -> bool {
-        for i in 0..data.len() {
-            if !self.is_crossing_3d(&data[i]) {
-                return false;
-            }
-        }
-        return true;
     }
 }
 
-fn find_crossings(data: &Vec<Hailstone>, test_area: (f64, f64)) -> Vec<(f64, f64)> {
+fn find_crossings(data: &[Hailstone], test_area: (f64, f64)) -> Vec<(f64, f64)> {
     let mut result: Vec<(f64, f64)> = vec![];
     for i in 0..data.len() {
         for j in (i + 1)..data.len() {
-            match get_crossing(&data[i], &data[j], test_area) {
-                Some(value) => result.push(value),
-                None => (),
+            if let Some(value) = get_crossing(&data[i], &data[j], test_area) {
+                result.push(value);
             }
         }
     }
@@ -241,13 +155,27 @@ fn get_crossing(
     second: &Hailstone,
     test_area: (f64, f64),
 ) -> Option<(f64, f64)> {
-    if first.k == second.k {
+    // first + first.v * t = second + second.v * s by Cramer's rule,
+    // unlike the slopes it also works for paths along the y axis
+    let [x1, y1, vx1, vy1] = [first.x, first.y, first.vx, first.vy].map(|x| x as f64);
+    let [x2, y2, vx2, vy2] = [second.x, second.y, second.vx, second.vy].map(|x| x as f64);
+    let det = vx2 * vy1 - vx1 * vy2;
+    if det == 0f64 {
         // lines are parallel
         return None;
     }
-    let candidate_x = (second.b - first.b) / (first.k - second.k);
-    let candidate_y = (first.k * second.b - first.b * second.k) / (first.k - second.k);
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let t = (vx2 * dy - vy2 * dx) / det;
+    let s = (vx1 * dy - vy1 * dx) / det;
+
+    // check that the point is in positive direction
+    // from the start of each trace
+    if t <= 0f64 || s <= 0f64 {
+        return None;
+    }
 
+    let candidate_x = x1 + vx1 * t;
+    let candidate_y = y1 + vy1 * t;
     if candidate_x < test_area.0
         || candidate_x > test_area.1
         || candidate_y < test_area.0
@@ -256,166 +184,300 @@ fn get_crossing(
         return None;
     }
 
-    // check that the point is in positive direction
-    // from the start of each trace
-    if (candidate_x - first.x) / first.vx <= 0f64 {
-        return None;
+    Some((candidate_x, candidate_y))
+}
+
+// In the frame of the rock every hailstone goes through the starting point of the rock,
+// so the hailstones with the rock velocity subtracted all cross in one point.
+// The velocity is searched on x and y, where two adjusted hailstones already give
+// the point and the times they are hit, and the other ones only have to agree.
+// The z axis follows from two hailstones hit at different times.
+fn find_line_crossing_all(data: &[HailstoneInt], max_velocity: i128) -> Option<HailstoneInt> {
+    for vx in -max_velocity..=max_velocity {
+        for vy in -max_velocity..=max_velocity {
+            if let Some(rock) = find_rock_with_velocity(data, vx, vy) {
+                return Some(rock);
+            }
+        }
     }
-    if (candidate_y - first.y) / first.vy <= 0f64 {
+    None
+}
+
+fn find_rock_with_velocity(data: &[HailstoneInt], vx: i128, vy: i128) -> Option<HailstoneInt> {
+    let first = data.first()?.adjust(vx, vy, 0);
+    // the second hailstone can't move in the same direction as the first one,
+    // or the lines don't fix the point
+    let second = data
+        .iter()
+        .map(|h| h.adjust(vx, vy, 0))
+        .skip(1)
+        .find(|h| first.vx * h.vy != first.vy * h.vx)?;
+
+    // first + first.v * t = second + second.v * s by Cramer's rule
+    let (dx, dy) = (second.x - first.x, second.y - first.y);
+    let det = second.vx * first.vy - first.vx * second.vy;
+    let t = second.vx * dy - second.vy * dx;
+    let s = first.vx * dy - first.vy * dx;
+    if t % det != 0 || s % det != 0 || t / det < 0 || s / det < 0 {
         return None;
     }
-    if (candidate_x - second.x) / second.vx <= 0f64 {
+    let t = t / det;
+    let (x, y) = (first.x + first.vx * t, first.y + first.vy * t);
+
+    // times when every hailstone is hit, hailstones that stay still relatively
+    // to the rock don't give one on x and y, and aren't supported
+    let mut times: Vec<i128> = vec![];
+    for h in data.iter().map(|h| h.adjust(vx, vy, 0)) {
+        let time = if h.vx != 0 {
+            (x - h.x) / h.vx
+        } else if h.vy != 0 {
+            (y - h.y) / h.vy
+        } else {
+            return None;
+        };
+        if time < 0 || h.x + h.vx * time != x || h.y + h.vy * time != y {
+            return None;
+        }
+        times.push(time);
+    }
+
+    // z + vz * t = first.z + first.vz * t, and the same for any hailstone hit at another time
+    let (second, s) = data
+        .iter()
+        .zip(times.iter())
+        .find(|(_, time)| **time != t)?;
+    let first = &data[0];
+    let diff = second.z + second.vz * s - first.z - first.vz * t;
+    if diff % (s - t) != 0 {
         return None;
     }
-    if (candidate_y - second.y) / second.vy <= 0f64 {
+    let vz = diff / (s - t);
+    let z = first.z + (first.vz - vz) * t;
+    if data
+        .iter()
+        .zip(times.iter())
+        .any(|(h, time)| h.z + (h.vz - vz) * time != z)
+    {
         return None;
     }
 
-    return Some((candidate_x, candidate_y));
+    Some(HailstoneInt {
+        x,
+        y,
+        z,
+        vx,
+        vy,
+        vz,
+    })
 }
 
-fn find_line_crossing_all(data: &Vec<HailstoneInt>) -> HailstoneInt {
-    //let adjusted = find_adjusted_hailstone(data);
-    let adjusted = HailstoneInt {
-        x: 159153037374407i128,
-        y: 228139153674672i128,
-        z: 170451316297300i128,
-        vx: 245,
-        vy: 75,
-        vz: 221,
-    };
-    let time = (adjusted.x - data[0].x) / (data[0].vx - adjusted.vx);
-    println!("Time: {time}");
-    HailstoneInt {
-        x: data[0].x + (data[0].vx - adjusted.vx) * time,
-        y: data[0].y + (data[0].vy - adjusted.vy) * time,
-        z: data[0].z + (data[0].vz - adjusted.vz) * time,
-        vx: adjusted.vx,
-        vy: adjusted.vy,
-        vz: adjusted.vz,
-    }
-}
-
-fn find_adjusted_hailstone(data: &Vec<HailstoneInt>) -> HailstoneInt {
-    for vx in -1000..1000 {
-        for vy in -1000..1000 {
-            for vz in -1000..1000 {
-                let adjusted_hailstones: Vec<HailstoneInt> =
-                    data.iter().map(|h| h.adjust(vx, vy, vz)).collect();
-                if let Some(point) = all_intersecting_in_one_point_int(&adjusted_hailstones) {
-                    println!("Found velocity: {vx}, {vy}, {vz}");
-                    println!("Cross point: {point:?}");
-                    return HailstoneInt {
-                        x: point.0,
-                        y: point.1,
-                        z: point.2,
-                        vx,
-                        vy,
-                        vz,
-                    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use vecmath::{vec3_add, vec3_cross, vec3_dot, vec3_sub};
+
+    const TEST_AREA: (i128, i128) = (200000000000000, 400000000000000);
+    // Rock velocities of the generated cases, the search is quadratic in it
+    const TEST_VELOCITY: i128 = 50;
+
+    // Part 1 without rounding: the crossing point is multiplied by the determinant
+    fn count_crossings_exact(data: &[HailstoneInt], test_area: (i128, i128)) -> usize {
+        let mut result = 0;
+        for (i, first) in data.iter().enumerate() {
+            for second in data[i + 1..].iter() {
+                let (dx, dy) = (second.x - first.x, second.y - first.y);
+                let mut det = second.vx * first.vy - first.vx * second.vy;
+                if det == 0 {
+                    continue;
+                }
+                let mut t = second.vx * dy - second.vy * dx;
+                let mut s = first.vx * dy - first.vy * dx;
+                if det < 0 {
+                    (det, t, s) = (-det, -t, -s);
+                }
+                let area = test_area.0 * det..=test_area.1 * det;
+                let (x, y) = (first.x * det + first.vx * t, first.y * det + first.vy * t);
+                if t > 0 && s > 0 && area.contains(&x) && area.contains(&y) {
+                    result += 1;
                 }
             }
         }
+        result
     }
-    HailstoneInt {
-        x: 0,
-        y: 0,
-        z: 0,
-        vx: 0,
-        vy: 0,
-        vz: 0,
-    }
-}
 
-fn find_line_crossing_all_backup(data: &Vec<Hailstone>) -> Hailstone {
-    let mut crosses: Vec<(f64, f64, f64)> = vec![];
-    for i in 0..data.len() {
-        for j in (i + 1)..data.len() {
-            if let Some(cross) = data[i].cross_point_3d(&data[j]) {
-                crosses.push(cross);
+    impl HailstoneInt {
+        fn from_pair(p1: (i128, i128, i128), p2: (i128, i128, i128)) -> HailstoneInt {
+            HailstoneInt {
+                x: p1.0,
+                y: p1.1,
+                z: p1.2,
+                vx: p2.0 - p1.0,
+                vy: p2.1 - p1.1,
+                vz: p2.2 - p1.2,
             }
         }
-    }
-    println!("{} crosses found", crosses.len());
-    println!("{crosses:?}");
 
-    let mut lines: Vec<Hailstone> = vec![];
-    for i in 0..crosses.len() {
-        for j in (i + 1)..crosses.len() {
-            lines.push(Hailstone::from_pair(crosses[i], crosses[j]));
+        fn is_crossing_3d(&self, other: &HailstoneInt) -> bool {
+            let vec_cross = vec3_cross([self.vx, self.vy, self.vz], [other.vx, other.vy, other.vz]);
+            if vec_cross[0] == 0 && vec_cross[1] == 0 && vec_cross[2] == 0 {
+                return false;
+            }
+            let diff = vec3_sub([other.x, other.y, other.z], [self.x, self.y, self.z]);
+            vec3_dot(vec_cross, diff) == 0
         }
-    }
-    for line in lines {
-        if line.is_crossing_3d_all(data) {
-            println!("We found the line: {line:?}");
-            return line;
+
+        // `None` also when the paths are parallel on x and y
+        fn cross_point_3d(&self, other: &HailstoneInt) -> Option<(i128, i128, i128)> {
+            if !self.is_crossing_3d(other) {
+                return None;
+            }
+
+            let s = (self.vy * (other.x - self.x) - self.vx * (other.y - self.y))
+                .checked_div(self.vx * other.vy - self.vy * other.vx)?;
+            let result = vec3_add(
+                [other.x, other.y, other.z],
+                [s * other.vx, s * other.vy, s * other.vz],
+            );
+            Some((result[0], result[1], result[2]))
         }
-    }
-    panic!("No line is found!");
-}
 
-fn get_candidates(
-    data: &Vec<Hailstone>,
-    excluded: HashSet<usize>,
-) -> ((usize, usize, usize), (f64, f64, f64), (f64, f64, f64)) {
-    // find three lines so that one of them crosses two others,
-    // but others aren't crossing each other
-    for i in 0..data.len() {
-        if excluded.contains(&i) {
-            continue;
+        fn is_crossing_3d_all(&self, data: &[HailstoneInt]) -> bool {
+            data.iter().all(|h| self.is_crossing_3d(h))
         }
-        for j in 0..data.len() {
-            if excluded.contains(&j) {
-                continue;
+    }
+
+    // The rock goes through the points where the paths of the hailstones cross,
+    // so it's found when at least two of them are on its line
+    fn find_line_crossing_all_backup(data: &[HailstoneInt]) -> Option<HailstoneInt> {
+        let mut crosses: Vec<(i128, i128, i128)> = vec![];
+        for i in 0..data.len() {
+            for j in (i + 1)..data.len() {
+                if let Some(cross) = data[i].cross_point_3d(&data[j]) {
+                    crosses.push(cross);
+                }
             }
-            if let Some(cross1) = data[i].cross_point_3d(&data[j]) {
-                // i and j are crossing - look for the line
-                // that crosses exactly one of them
-                for k in 0..data.len() {
-                    if k == i || k == j || excluded.contains(&k) {
-                        continue;
-                    }
-                    if data[i].is_crossing_3d(&data[k])
-                        && !data[j].is_crossing_3d(&data[k])
-                        && !data[j].is_parallel(&data[k])
-                    {
-                        let cross2 = data[i].cross_point_3d(&data[k]).unwrap();
-                        return ((i, j, k), cross1, cross2);
-                    }
-                    if data[j].is_crossing_3d(&data[k])
-                        && !data[i].is_crossing_3d(&data[k])
-                        && !data[i].is_parallel(&data[k])
-                    {
-                        let cross2 = data[j].cross_point_3d(&data[k]).unwrap();
-                        return ((j, i, k), cross1, cross2);
-                    }
+        }
+
+        for i in 0..crosses.len() {
+            for j in (i + 1)..crosses.len() {
+                let line = HailstoneInt::from_pair(crosses[i], crosses[j]);
+                if line.is_crossing_3d_all(data) {
+                    return Some(line);
                 }
             }
         }
+        None
     }
 
-    panic!("No such lines");
-}
+    fn hailstone() -> impl Strategy<Value = Hailstone> {
+        let position = 100000000000000i64..500000000000000;
+        let velocity = -300i64..300;
+        (
+            [position.clone(), position.clone(), position],
+            [velocity.clone(), velocity.clone(), velocity],
+        )
+            .prop_map(|([x, y, z], [vx, vy, vz])| Hailstone {
+                x,
+                y,
+                z,
+                vx,
+                vy,
+                vz,
+            })
+    }
 
-fn all_intersecting_in_one_point_int(data: &Vec<HailstoneInt>) -> Option<(i128, i128, i128)> {
-    for i in 0..data.len() {
-        for j in (i + 1)..data.len() {
-            if !data[i].is_crossing_3d(&data[j]) {
-                return None;
-            }
+    fn rock() -> impl Strategy<Value = HailstoneInt> {
+        let position = 200000000000000i128..400000000000000;
+        let velocity = -TEST_VELOCITY..=TEST_VELOCITY;
+        (
+            [position.clone(), position.clone(), position],
+            [velocity.clone(), velocity.clone(), velocity],
+        )
+            .prop_map(|([x, y, z], [vx, vy, vz])| HailstoneInt {
+                x,
+                y,
+                z,
+                vx,
+                vy,
+                vz,
+            })
+    }
+
+    // A rock and hailstones aimed at it in pairs, the two of a pair meet the rock
+    // at the same time, so their paths cross on its line
+    fn hailstones_hit_by_rock() -> impl Strategy<Value = (HailstoneInt, Vec<HailstoneInt>)> {
+        rock()
+            .prop_flat_map(|rock| {
+                let rock_velocity = [rock.vx, rock.vy, rock.vz];
+                let velocity = -300i128..300;
+                let aimed = (
+                    [velocity.clone(), velocity.clone(), velocity],
+                    1000000000i128..1000000000000,
+                )
+                    .prop_filter(
+                        "the hailstone moves along the rock",
+                        move |(v, _)| {
+                            vec3_cross(*v, rock_velocity) != [0; 3]
+                                && (0..3).all(|k| v[k] != rock_velocity[k])
+                        },
+                    );
+                let pairs = prop::collection::vec((aimed.clone(), aimed), 2..5).prop_filter(
+                    "the hailstones of a pair are parallel",
+                    |pairs| {
+                        pairs
+                            .iter()
+                            .all(|((v1, _), (v2, _))| vec3_cross(*v1, *v2) != [0; 3])
+                    },
+                );
+                (Just(rock), pairs)
+            })
+            .prop_map(|(rock, pairs)| {
+                let mut result = vec![];
+                for (k, ((v1, time), (v2, _))) in pairs.into_iter().enumerate() {
+                    // distinct times for distinct pairs
+                    let time = time + k as i128;
+                    for v in [v1, v2] {
+                        result.push(HailstoneInt {
+                            x: rock.x + (rock.vx - v[0]) * time,
+                            y: rock.y + (rock.vy - v[1]) * time,
+                            z: rock.z + (rock.vz - v[2]) * time,
+                            vx: v[0],
+                            vy: v[1],
+                            vz: v[2],
+                        });
+                    }
+                }
+                (rock, result)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn crossings_match_exact_count(data in prop::collection::vec(hailstone(), 0..20)) {
+            let test_area = (TEST_AREA.0 as f64, TEST_AREA.1 as f64);
+            let data_int: Vec<HailstoneInt> = data.iter().map(|x| x.to_int()).collect();
+            prop_assert_eq!(
+                find_crossings(&data, test_area).len(),
+                count_crossings_exact(&data_int, TEST_AREA)
+            );
         }
+
     }
-    println!("Found all crossing");
-    return data[0].cross_point_3d(&data[1]);
-}
 
-fn all_insecting_in_one_point(data: &Vec<Hailstone>) -> Option<(f64, f64, f64)> {
-    for i in 0..data.len() {
-        for j in (i + 1)..data.len() {
-            if !data[i].is_crossing_3d(&data[j]) {
-                return None;
-            }
+    proptest! {
+        #[test]
+        fn line_matches_backup((rock, data) in hailstones_hit_by_rock()) {
+            let found = find_line_crossing_all(&data, TEST_VELOCITY);
+            prop_assert_eq!(found, Some(rock));
+            let line = find_line_crossing_all_backup(&data);
+            prop_assert!(line.is_some());
+            // the same line, the backup one starts at a crossing and goes to the next one
+            let line = line.unwrap();
+            let direction = [rock.vx, rock.vy, rock.vz];
+            let offset = [line.x - rock.x, line.y - rock.y, line.z - rock.z];
+            prop_assert_eq!(vec3_cross([line.vx, line.vy, line.vz], direction), [0; 3]);
+            prop_assert_eq!(vec3_cross(offset, direction), [0; 3]);
         }
     }
-    return data[0].cross_point_3d(&data[1]);
 }
@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::fmt;
+use std::fs;
+use std::io;
 use std::time::Instant;
 
 use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableUnGraph};
@@ -14,33 +15,9 @@ fn main() -> io::Result<()> {
 
     // Part1
     // open the file and parse input
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let mut graph: StableUnGraph<String, ()> = StableUnGraph::default();
-    let mut nodes: HashMap<String, NodeIndex> = HashMap::new();
-    let mut edges: HashMap<(NodeIndex, NodeIndex), EdgeIndex> = HashMap::new();
-    for line in reader.lines() {
-        let l = line?;
-        let curr_nodes: Vec<NodeIndex> = l
-            .split_whitespace()
-            .map(|x| {
-                let trimmed = x.trim_matches(':');
-                match nodes.get(trimmed) {
-                    Some(idx) => *idx,
-                    None => {
-                        let idx = graph.add_node(trimmed.to_string());
-                        nodes.insert(trimmed.to_string(), idx);
-                        idx
-                    }
-                }
-            })
-            .collect();
-        for i in 1..curr_nodes.len() {
-            let edge_index = graph.add_edge(curr_nodes[0], curr_nodes[i], ());
-            edges.insert((curr_nodes[0], curr_nodes[i]), edge_index);
-            edges.insert((curr_nodes[i], curr_nodes[0]), edge_index);
-        }
-    }
+    let input = fs::read_to_string(input_path)?;
+    let graph =
+        parse_input(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
     // Part1
     let top_3_edges = get_top_3_edges(&graph);
@@ -67,7 +44,51 @@ fn main() -> io::Result<()> {
     println!("Part2: {}", part2_result);
 
     println!("Elapsed: {:?}", now.elapsed());
-    return Ok(());
+    Ok(())
+}
+
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    WrongFormat(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFormat(input) => write!(f, "Wrong format of \"{input}\""),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// `jqt: rhn xhk nvd` per line, every wire is listed once
+pub(crate) fn parse_input(input: &str) -> Result<StableUnGraph<String, ()>, ParseError> {
+    let mut graph: StableUnGraph<String, ()> = StableUnGraph::default();
+    let mut nodes: HashMap<String, NodeIndex> = HashMap::new();
+    let mut node = |name: &str| match nodes.get(name) {
+        Some(idx) => *idx,
+        None => {
+            let idx = graph.add_node(name.to_string());
+            nodes.insert(name.to_string(), idx);
+            idx
+        }
+    };
+    let mut wires: Vec<(NodeIndex, NodeIndex)> = vec![];
+    for line in input.lines() {
+        let (name, others) = line
+            .split_once(':')
+            .filter(|(name, others)| !name.trim().is_empty() && !others.trim().is_empty())
+            .ok_or(ParseError::WrongFormat(line.to_string()))?;
+        let from = node(name.trim());
+        for other in others.split_whitespace() {
+            wires.push((from, node(other)));
+        }
+    }
+    for (from, to) in wires {
+        graph.add_edge(from, to, ());
+    }
+    Ok(graph)
 }
 
 fn get_top_3_edges(graph: &StableUnGraph<String, ()>) -> Vec<EdgeIndex> {
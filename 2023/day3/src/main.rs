@@ -1,131 +1,248 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
-
-fn is_near_symbol(data: &Vec<Vec<char>>, i: isize, j: isize) -> bool {
-    let near_cells = [
-        (-1, -1), (-1, 0), (-1, 1),
-        (0, -1), (0, 0), (0, 1),
-        (1, -1), (1, 0), (1, 1)
-    ];
-    for (i_mod, j_mod) in near_cells {
-        if i as isize + i_mod >= 0
-            && i + i_mod < data.len() as isize
-            && j + j_mod >= 0
-            && j + j_mod < data[0].len() as isize
-            && !data[(i + i_mod) as usize][(j + j_mod) as usize].is_digit(10)
-            && data[(i + i_mod) as usize][(j + j_mod) as usize] != '.' {
-            return true;
+use grid::{Grid, Input};
+use std::fmt;
+use std::io;
+
+// A number in the schematic spanning columns `start..=end` of its row
+struct NumberSpan {
+    value: u32,
+    start: usize,
+    end: usize,
+    // indices of the adjacent symbols
+    symbols: Vec<usize>,
+}
+
+struct Symbol {
+    ch: char,
+    row: usize,
+    col: usize,
+    // indices of the adjacent numbers
+    numbers: Vec<usize>,
+}
+
+// All the numbers and symbols of the engine schematic linked with each other
+pub(crate) struct Schematic {
+    numbers: Vec<NumberSpan>,
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    // the number starting at (row, col) doesn't fit into u32
+    NumberOverflow { row: usize, col: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::NumberOverflow { row, col } => {
+                write!(f, "Number at ({row}, {col}) is too large")
+            }
         }
     }
-
-    return false;
 }
 
-// Find all numbers that are adjacent to the current cell
-fn get_nearby_numbers(data: &Vec<Vec<char>>, i: isize, j: isize) -> Vec<u32> {
-    let near_cells = [
-        (-1, -1), (-1, 0), (-1, 1),
-        (0, -1), (0, 0), (0, 1),
-        (1, -1), (1, 0), (1, 1)
-    ];
-
-    let mut number_starts = vec![];
-    for (i_mod, j_mod) in near_cells {
-        if i + i_mod >= 0
-            && i + i_mod < data.len() as isize
-            && j + j_mod >= 0
-            && j + j_mod < data[0].len() as isize
-            && data[(i + i_mod) as usize][(j + j_mod) as usize].is_digit(10) {
-                // move left until we meet the first non-digit character or the beginning of string
-                let mut start_j = j + j_mod;
-                while start_j >= 0 && data[(i + i_mod) as usize][start_j as usize].is_digit(10) {
-                    start_j -= 1;
+impl std::error::Error for ParseError {}
+
+impl Schematic {
+    // Rows are read one by one and only the previous row is looked at again:
+    // everything adjacent to a new number or symbol is either in its row or in the one above.
+    // Numbers and symbols of the last two rows are indexed by their columns,
+    // so only the columns around a number or a symbol are checked.
+    pub(crate) fn index(data: &Grid) -> Result<Schematic, ParseError> {
+        let mut schematic = Schematic {
+            numbers: vec![],
+            symbols: vec![],
+        };
+        let width = data.width();
+        // the number or the symbol in every column of the previous row
+        let mut prev_numbers: Vec<Option<usize>> = vec![None; width];
+        let mut prev_symbols: Vec<Option<usize>> = vec![None; width];
+        for (row, line) in data.rows().enumerate() {
+            let first_number = schematic.numbers.len();
+            let first_symbol = schematic.symbols.len();
+            schematic.scan_row(row, line)?;
+
+            let mut curr_numbers: Vec<Option<usize>> = vec![None; width];
+            for n in first_number..schematic.numbers.len() {
+                let number = &schematic.numbers[n];
+                curr_numbers[number.start..=number.end].fill(Some(n));
+            }
+            let mut curr_symbols: Vec<Option<usize>> = vec![None; width];
+            for s in first_symbol..schematic.symbols.len() {
+                curr_symbols[schematic.symbols[s].col] = Some(s);
+            }
+
+            // link the new numbers with the symbols around them in both rows
+            for n in first_number..schematic.numbers.len() {
+                let number = &schematic.numbers[n];
+                let cols = number.start.saturating_sub(1)..=(number.end + 1).min(width - 1);
+                for symbols in [&prev_symbols, &curr_symbols] {
+                    for s in symbols[cols.clone()].iter().flatten() {
+                        schematic.link(n, *s);
+                    }
                 }
-                if !number_starts.iter().any(|ns| *ns == (i + i_mod, start_j + 1)) {
-                    number_starts.push((i + i_mod, start_j + 1))
+            }
+            // and the new symbols with the numbers of the previous row,
+            // a number can take several of the columns
+            for s in first_symbol..schematic.symbols.len() {
+                let col = schematic.symbols[s].col;
+                let cols = col.saturating_sub(1)..=(col + 1).min(width - 1);
+                let mut last_linked: Option<usize> = None;
+                for n in prev_numbers[cols].iter().flatten() {
+                    if last_linked != Some(*n) {
+                        schematic.link(*n, s);
+                        last_linked = Some(*n);
+                    }
                 }
             }
+
+            prev_numbers = curr_numbers;
+            prev_symbols = curr_symbols;
+        }
+        Ok(schematic)
     }
 
-    // convert number starts to the numbers
-    return number_starts.iter().map(|ns| {
-        let mut j = ns.1;
-        let mut str = String::from("");
-        while j < data[0].len() as isize && data[ns.0 as usize][j as usize].is_digit(10) {
-            str.push(data[ns.0 as usize][j as usize]);
-            j += 1;
+    fn scan_row(&mut self, row: usize, line: &[u8]) -> Result<(), ParseError> {
+        let mut curr_number: Option<NumberSpan> = None;
+        for (col, ch) in line.iter().map(|x| *x as char).enumerate() {
+            if let Some(digit) = ch.to_digit(10) {
+                let number = curr_number.get_or_insert(NumberSpan {
+                    value: 0,
+                    start: col,
+                    end: col,
+                    symbols: vec![],
+                });
+                number.value = number
+                    .value
+                    .checked_mul(10)
+                    .and_then(|x| x.checked_add(digit))
+                    .ok_or(ParseError::NumberOverflow {
+                        row,
+                        col: number.start,
+                    })?;
+                number.end = col;
+                continue;
+            }
+
+            self.numbers.extend(curr_number.take());
+            if ch != '.' {
+                self.symbols.push(Symbol {
+                    ch,
+                    row,
+                    col,
+                    numbers: vec![],
+                });
+            }
         }
-        return u32::from_str_radix(&str, 10).unwrap();
-    }).collect();
-}
+        // the number in the end of line
+        self.numbers.extend(curr_number);
+        Ok(())
+    }
 
+    fn link(&mut self, number: usize, symbol: usize) {
+        self.numbers[number].symbols.push(symbol);
+        self.symbols[symbol].numbers.push(number);
+    }
+
+    // Numbers adjacent to at least one symbol
+    fn part_numbers(&self) -> impl Iterator<Item = &NumberSpan> {
+        self.numbers.iter().filter(|n| !n.symbols.is_empty())
+    }
+
+    // Symbols of the given type with exactly `adjacent` numbers around them,
+    // gears are `find_symbols('*', 2)`
+    fn find_symbols(&self, ch: char, adjacent: usize) -> impl Iterator<Item = &Symbol> {
+        self.symbols
+            .iter()
+            .filter(move |s| s.ch == ch && s.numbers.len() == adjacent)
+    }
+
+    // Product of the numbers around the symbol
+    fn ratio(&self, symbol: &Symbol) -> u64 {
+        symbol
+            .numbers
+            .iter()
+            .map(|n| self.numbers[*n].value as u64)
+            .product()
+    }
+}
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day3/src/input.txt";
 
-    // Open the file
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
+    // `<symbol> <count>` lists the symbols with that many numbers around them
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let query: Option<(char, usize)> = match &args[..] {
+        [symbol, count] => symbol.chars().next().zip(count.parse().ok()),
+        _ => None,
+    };
 
-    // Collect the data into two-dimensional array
-    let mut data: Vec<Vec<char>> = vec![];
-    for line in reader.lines() {
-        data.push(line?.chars().collect());
-    }
+    // Map the input into memory and use it as a grid without copying
+    let input = Input::open(input_path)?;
+    let data =
+        Grid::parse(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let schematic =
+        Schematic::index(&data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
 
     // part 1
-    // Line per line character per character collect part numbers
-    let mut total_num_parts = 0;
-    let mut i = 0;
-    while i < data.len() {
-        let mut j = 0;
-        let mut curr_number = String::from("");
-        let mut is_part_number = false;
-        while j < data[i].len() {
-            if data[i][j].is_digit(10) {
-                curr_number.push(data[i][j]);
-                // check if there's a symbol nearby
-                if !is_part_number && is_near_symbol(&data, i as isize, j as isize) {
-                    is_part_number = true;
-                }
-            } else {
-                // finish the current number and increase the total
-                // if it was a part number
-                if is_part_number && !curr_number.is_empty() {
-                    total_num_parts += i32::from_str_radix(&curr_number, 10).unwrap();
-                }
-                curr_number.clear();
-                is_part_number = false;
-            }
-            j += 1;
-        }
-        // the case when the part number is in the end of line
-        if is_part_number && !curr_number.is_empty() {
-            total_num_parts += i32::from_str_radix(&curr_number, 10).unwrap();
-        }
-        i += 1;
-    }
+    let total_num_parts: u32 = schematic.part_numbers().map(|n| n.value).sum();
 
     // part 2
-    // check every star character - how many part numbers are nearby?
-    let mut total_gear_ratios = 0;
-    let mut i = 0;
-    while i < data.len() {
-        let mut j = 0;
-        while j < data[i].len() {
-            if data[i][j] == '*' {
-                let near_numbers = get_nearby_numbers(&data, i as isize, j as isize);
-                if near_numbers.len() == 2 {
-                    total_gear_ratios += near_numbers[0] * near_numbers[1];
-                }
-            }
-            j += 1;
+    let total_gear_ratios: u64 = schematic
+        .find_symbols('*', 2)
+        .map(|s| schematic.ratio(s))
+        .sum();
+
+    println!("Part1: {}, part2: {}", total_num_parts, total_gear_ratios);
+
+    if let Some((ch, adjacent)) = query {
+        for symbol in schematic.find_symbols(ch, adjacent) {
+            let numbers: Vec<u32> = symbol
+                .numbers
+                .iter()
+                .map(|n| schematic.numbers[*n].value)
+                .collect();
+            println!("{} at ({}, {}): {:?}", ch, symbol.row, symbol.col, numbers);
         }
-        i += 1;
     }
+    Ok(())
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!("Part1: {}, part2: {}", total_num_parts, total_gear_ratios);
-    Ok(())
+    fn index(input: &str) -> Schematic {
+        Schematic::index(&Grid::parse(input.as_bytes()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn example() {
+        let schematic = index(include_str!("input_test.txt"));
+        let part_numbers: u32 = schematic.part_numbers().map(|n| n.value).sum();
+        let ratios: u64 = schematic
+            .find_symbols('*', 2)
+            .map(|s| schematic.ratio(s))
+            .sum();
+        assert_eq!((part_numbers, ratios), (4361, 467835));
+    }
+
+    #[test]
+    fn neighbours() {
+        let schematic = index("123.45\n.*...#\n6.789.");
+        let numbers_of = |ch: char| -> Vec<u32> {
+            let symbol = schematic.symbols.iter().find(|s| s.ch == ch).unwrap();
+            let mut numbers: Vec<u32> = symbol
+                .numbers
+                .iter()
+                .map(|n| schematic.numbers[*n].value)
+                .collect();
+            numbers.sort_unstable();
+            numbers
+        };
+        // the number above takes all three columns, but it's linked once
+        assert_eq!(numbers_of('*'), [6, 123, 789]);
+        assert_eq!(numbers_of('#'), [45, 789]);
+    }
 }
@@ -1,77 +1,232 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
 use std::fs::File;
-use std::collections::{HashSet, HashMap};
 use std::io::{self, BufRead, BufReader};
 
-fn str_to_set(input: &str) -> HashSet<i32> {
-    return input.split_whitespace()
-        .map(|s| i32::from_str_radix(s, 10).unwrap())
-        .collect();
+pub(crate) struct Card {
+    id: u32,
+    // how many of the owned numbers are winning
+    matches: usize,
 }
 
-fn calc_points(winning: &HashSet<i32>, owned: &HashSet<i32>) -> i32 {
-    // find equal numbers in both parts and calc the result
-    let intersected = winning.intersection(owned).count() as u32;
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    WrongFormat(String),
+    CardNumber(String),
+    NoSeparator(String),
+    WrongNumber(String),
+}
+
+#[derive(Debug)]
+enum EvaluationError {
+    // (expected card number, actual one)
+    UnexpectedCard(u32, u32),
+    // card number after which the counters don't fit into u64
+    Overflow(u32),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFormat(input) => write!(f, "Wrong card \"{input}\""),
+            ParseError::CardNumber(input) => write!(f, "Wrong card number in \"{input}\""),
+            ParseError::NoSeparator(input) => write!(f, "No separator in \"{input}\""),
+            ParseError::WrongNumber(input) => write!(f, "Wrong number in \"{input}\""),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
 
-    return if intersected > 0 { 2i32.pow(intersected - 1) } else { 0 };
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvaluationError::UnexpectedCard(expected, actual) => {
+                write!(f, "Expected card {expected} but got card {actual}")
+            }
+            EvaluationError::Overflow(id) => write!(f, "Too many points or cards at card {id}"),
+        }
+    }
 }
 
-fn find_cards_to_copy(card_id: u32, winning: &HashSet<i32>, owned: &HashSet<i32>) -> HashSet<u32> {
-    // find intersection
-    let intersected = winning.intersection(owned).count() as u32;
+impl std::error::Error for EvaluationError {}
+
+impl Card {
+    // `Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53`
+    pub(crate) fn from_str(input: &str) -> Result<Card, ParseError> {
+        let (header, numbers) = input
+            .split_once(':')
+            .ok_or_else(|| ParseError::WrongFormat(input.to_string()))?;
+        let id = header
+            .strip_prefix("Card")
+            .and_then(|x| x.trim().parse().ok())
+            .ok_or_else(|| ParseError::CardNumber(input.to_string()))?;
+        let (winning, owned) = numbers
+            .split_once('|')
+            .ok_or_else(|| ParseError::NoSeparator(input.to_string()))?;
+        let wrong_number = || ParseError::WrongNumber(input.to_string());
+        let winning = str_to_set(winning).ok_or_else(wrong_number)?;
+        let owned = str_to_set(owned).ok_or_else(wrong_number)?;
+        Ok(Card {
+            id,
+            matches: winning.intersection(&owned).count(),
+        })
+    }
 
-    let mut i = 0;
-    let mut result: HashSet<u32> = HashSet::new();
-    while i < intersected {
-        result.insert(card_id + i + 1);
-        i += 1;
+    // `None` if the points don't fit into u64
+    fn points(&self) -> Option<u64> {
+        if self.matches > 0 {
+            1u64.checked_shl(u32::try_from(self.matches - 1).ok()?)
+        } else {
+            Some(0)
+        }
     }
+}
+
+fn str_to_set(input: &str) -> Option<HashSet<u32>> {
+    input.split_whitespace().map(|s| s.parse().ok()).collect()
+}
 
-    return result;
+// Evaluates the cards one by one as they are read.
+// Copies won by a card only go to the next `matches` cards,
+// so only the counters for the cards ahead are kept.
+struct ScratchcardEvaluator {
+    // copies won for the next cards, the front one is for the next card
+    pending_copies: VecDeque<u64>,
+    // (card number, matches, instances including the original) for every card
+    cards: Vec<(u32, usize, u64)>,
+    points: u64,
+    total_cards: u64,
+}
+
+impl ScratchcardEvaluator {
+    fn new() -> ScratchcardEvaluator {
+        ScratchcardEvaluator {
+            pending_copies: VecDeque::new(),
+            cards: vec![],
+            points: 0,
+            total_cards: 0,
+        }
+    }
+
+    fn process(&mut self, card: Card) -> Result<(), EvaluationError> {
+        let expected_id = self.cards.len() as u32 + 1;
+        if card.id != expected_id {
+            return Err(EvaluationError::UnexpectedCard(expected_id, card.id));
+        }
+        let overflow = || EvaluationError::Overflow(card.id);
+
+        let instances = self
+            .pending_copies
+            .pop_front()
+            .unwrap_or(0)
+            .checked_add(1)
+            .ok_or_else(overflow)?;
+        if self.pending_copies.len() < card.matches {
+            self.pending_copies.resize(card.matches, 0);
+        }
+        // copies can be won for cards after the last one, so the counters saturate
+        // and the overflow is only reported if the card turns up
+        for copies in self.pending_copies.iter_mut().take(card.matches) {
+            *copies = copies.saturating_add(instances);
+        }
+
+        self.points = card
+            .points()
+            .and_then(|points| self.points.checked_add(points))
+            .ok_or_else(overflow)?;
+        self.total_cards = self
+            .total_cards
+            .checked_add(instances)
+            .ok_or_else(overflow)?;
+        self.cards.push((card.id, card.matches, instances));
+        Ok(())
+    }
+
+    // Copies won for the cards after the last one are lost
+    fn total_copies(&self) -> u64 {
+        self.total_cards - self.cards.len() as u64
+    }
 }
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day4/src/input.txt";
+    let verbose = std::env::args().any(|arg| arg == "--verbose");
 
-    // Open the file
-    let mut file = File::open(input_path)?;
-    let mut reader = BufReader::new(file);
-
-    // part1
-    let mut part1_result = 0;
+    // Open the file and evaluate the cards as they come
+    let file = File::open(input_path)?;
+    let reader = BufReader::new(file);
+    let mut evaluator = ScratchcardEvaluator::new();
     for line in reader.lines() {
-        let data = line?;
-        let parts: Vec<&str> = data.trim_start_matches("Card ").split(&[':', '|'][..]).map(|x| x.trim()).collect();
-        let winning = str_to_set(parts[1]) ;
-        let owned = str_to_set(parts[2]);
-        let points = calc_points(&winning, &owned);
-        part1_result += points;
+        let card = Card::from_str(&line?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        evaluator
+            .process(card)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    }
+
+    if verbose {
+        for (id, matches, instances) in evaluator.cards.iter() {
+            println!("Card {id}: {matches} matches, {instances} instances");
+        }
+        println!("Copies won: {}", evaluator.total_copies());
     }
-    println!("{}", part1_result);
+
+    // part1
+    println!("Part1: {}", evaluator.points);
 
     // part2
-    let mut card_ids: HashMap<u32, u32> = HashMap::new();
-    file = File::open(input_path)?;
-    reader = BufReader::new(file);
-    for line in reader.lines() {
-        let data = line?;
-        let parts: Vec<&str> = data.trim_start_matches("Card ").split(&[':', '|'][..]).map(|x| x.trim()).collect();
-        let card_id = u32::from_str_radix(parts[0], 10).unwrap();
-        let winning = str_to_set(parts[1]);
-        let owned = str_to_set(parts[2]);
-        // add the original card to the card_ids
-        card_ids.entry(card_id).and_modify(|val| *val += 1).or_insert(1);
-        // find what cards will be copied
-        let cards_to_copy = find_cards_to_copy(card_id, &winning, &owned);
-        // multiply by the number of instances of the current card
-        let curr_number = card_ids[&card_id];
-        // insert new copies into card_ids
-        for id in cards_to_copy {
-            card_ids.entry(id).and_modify(|val| *val += curr_number).or_insert(curr_number);
+    println!("Part2: {}", evaluator.total_cards);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(input: &str) -> Result<ScratchcardEvaluator, EvaluationError> {
+        let mut evaluator = ScratchcardEvaluator::new();
+        for line in input.lines() {
+            evaluator.process(Card::from_str(line).unwrap())?;
         }
+        Ok(evaluator)
     }
-    let cards_total = card_ids.iter().fold(0, |acc, (_k, v)| acc + v);
-    println!("{}", cards_total);
 
-    return Ok(());
+    #[test]
+    fn example() {
+        let evaluator = evaluate(include_str!("input_test.txt")).unwrap();
+        assert_eq!(evaluator.points, 13);
+        assert_eq!(evaluator.total_cards, 30);
+    }
+
+    #[test]
+    fn many_matches() {
+        let numbers: Vec<String> = (1..=64).map(|x| x.to_string()).collect();
+        let numbers = numbers.join(" ");
+        let card = Card::from_str(&format!("Card 1: {numbers} | {numbers}")).unwrap();
+        assert_eq!(card.points(), Some(1 << 63));
+
+        let numbers = format!("{numbers} 65");
+        let line = format!("Card 1: {numbers} | {numbers}");
+        assert_eq!(Card::from_str(&line).unwrap().points(), None);
+        assert!(matches!(evaluate(&line), Err(EvaluationError::Overflow(1))));
+    }
+
+    #[test]
+    fn wrong_cards() {
+        assert!(matches!(
+            Card::from_str("Card x: 1 | 2"),
+            Err(ParseError::CardNumber(_))
+        ));
+        assert!(matches!(
+            Card::from_str("Card 1: 1 2"),
+            Err(ParseError::NoSeparator(_))
+        ));
+        assert!(matches!(
+            evaluate("Card 2: 1 | 2"),
+            Err(EvaluationError::UnexpectedCard(1, 2))
+        ));
+    }
 }
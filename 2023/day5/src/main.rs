@@ -1,137 +1,297 @@
-use std::collections::VecDeque;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
 use std::ops::Range;
 
+// Piecewise-linear map from one category to another.
+// Pieces are sorted by their source ranges and don't overlap,
+// values outside of all pieces have no image.
+#[derive(Debug, Clone)]
 struct Mapping {
-    ranges: Vec<MappingRange>,
+    source: String,
+    target: String,
+    pieces: Vec<Piece>,
 }
 
-struct MappingRange {
+#[derive(Debug, Clone)]
+struct Piece {
     src: Range<u64>,
-    dest: Range<u64>,
+    offset: i64,
+}
+
+// All the mappings of the almanac by their source category
+pub(crate) struct Almanac {
+    mappings: HashMap<String, Mapping>,
+}
+
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    Seeds(String),
+    MapHeader(String),
+    // not three numbers, or the ranges go beyond u64
+    MapRange(String),
+    // two maps from the same category
+    DuplicateMap(String),
+    // two ranges of a map share source values
+    Overlap(String),
+    // odd number of seed values, or the range goes beyond u64
+    SeedRanges(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Seeds(input) => write!(f, "Wrong seeds \"{input}\""),
+            ParseError::MapHeader(input) => write!(f, "Wrong map header \"{input}\""),
+            ParseError::MapRange(input) => write!(f, "Wrong range \"{input}\""),
+            ParseError::DuplicateMap(source) => write!(f, "There are several maps from {source}"),
+            ParseError::Overlap(header) => write!(f, "Overlapping ranges in \"{header}\""),
+            ParseError::SeedRanges(input) => write!(f, "Wrong seed ranges \"{input}\""),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Piece {
+    fn dest(&self) -> Range<u64> {
+        shift(&self.src, self.offset)
+    }
 }
 
 impl Mapping {
-    fn from(input: &str) -> Mapping {
-        let lines: Vec<&str> = input.split("\n").collect();
-        let mut ranges: Vec<MappingRange> = vec![];
+    fn from(input: &str) -> Result<Mapping, ParseError> {
+        let lines: Vec<&str> = input.split('\n').collect();
+        // header looks like `seed-to-soil map:`
+        let (source, target) = lines[0]
+            .trim_end_matches(" map:")
+            .split_once("-to-")
+            .ok_or_else(|| ParseError::MapHeader(lines[0].to_string()))?;
+        let mut pieces: Vec<Piece> = vec![];
         for line in lines[1..].iter() {
+            let wrong_range = || ParseError::MapRange(line.to_string());
             let parts: Vec<u64> = line
                 .split_whitespace()
-                .map(|x| u64::from_str_radix(x, 10).unwrap())
-                .collect();
-            ranges.push(MappingRange {
-                src: parts[1]..parts[1] + parts[2],
-                dest: parts[0]..parts[0] + parts[2],
+                .map(|x| x.parse().ok())
+                .collect::<Option<_>>()
+                .ok_or_else(wrong_range)?;
+            let [dest, src, len] = parts[..] else {
+                return Err(wrong_range());
+            };
+            if src.checked_add(len).is_none() || dest.checked_add(len).is_none() {
+                return Err(wrong_range());
+            }
+            pieces.push(Piece {
+                src: src..src + len,
+                offset: i64::try_from(dest as i128 - src as i128).map_err(|_| wrong_range())?,
             });
         }
+        pieces.sort_unstable_by_key(|p| p.src.start);
+        if pieces.windows(2).any(|w| w[0].src.end > w[1].src.start) {
+            return Err(ParseError::Overlap(lines[0].to_string()));
+        }
+
+        // values not covered by any range are mapped to themselves
+        let mut filled: Vec<Piece> = vec![];
+        let mut next_start = 0;
+        for piece in pieces {
+            if next_start < piece.src.start {
+                filled.push(Piece {
+                    src: next_start..piece.src.start,
+                    offset: 0,
+                });
+            }
+            next_start = piece.src.end;
+            filled.push(piece);
+        }
+        filled.push(Piece {
+            src: next_start..u64::MAX,
+            offset: 0,
+        });
+
+        Ok(Mapping {
+            source: source.to_string(),
+            target: target.to_string(),
+            pieces: normalize(filled),
+        })
+    }
+
+    fn identity(category: &str) -> Mapping {
+        Mapping {
+            source: category.to_string(),
+            target: category.to_string(),
+            pieces: vec![Piece {
+                src: 0..u64::MAX,
+                offset: 0,
+            }],
+        }
+    }
+
+    fn apply(&self, value: u64) -> Option<u64> {
+        let idx = self.pieces.partition_point(|p| p.src.end <= value);
+        self.pieces
+            .get(idx)
+            .filter(|p| p.src.contains(&value))
+            .map(|p| (value as i128 + p.offset as i128) as u64)
+    }
+
+    fn apply_for_ranges(&self, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+        let mut results: Vec<Range<u64>> = vec![];
+        for rng in ranges.iter() {
+            for piece in self.pieces.iter() {
+                let intersection = intersect(rng, &piece.src);
+                if !intersection.is_empty() {
+                    results.push(shift(&intersection, piece.offset));
+                }
+            }
+        }
+        results
+    }
+
+    // Single mapping doing the same as applying `self` and then `next`
+    fn compose(&self, next: &Mapping) -> Mapping {
+        assert_eq!(self.target, next.source, "Mappings can't be composed");
+        let mut pieces: Vec<Piece> = vec![];
+        for piece in self.pieces.iter() {
+            let dest = piece.dest();
+            for next_piece in next.pieces.iter() {
+                let intersection = intersect(&dest, &next_piece.src);
+                if !intersection.is_empty() {
+                    pieces.push(Piece {
+                        src: shift(&intersection, -piece.offset),
+                        offset: piece.offset + next_piece.offset,
+                    });
+                }
+            }
+        }
+        pieces.sort_unstable_by_key(|p| p.src.start);
 
-        return Mapping { ranges };
+        Mapping {
+            source: self.source.to_owned(),
+            target: next.target.to_owned(),
+            pieces: normalize(pieces),
+        }
     }
 
-    fn apply(&self, values: &Vec<u64>) -> Vec<u64> {
-        // for each value find where it's going to be mapped
-        return values
+    // Mapping from the target back to the source,
+    // `None` if two source values are mapped to the same target one
+    fn invert(&self) -> Option<Mapping> {
+        let mut pieces: Vec<Piece> = self
+            .pieces
             .iter()
-            .map(|val| {
-                return match self
-                    .ranges
-                    .iter()
-                    .find(|r| (**r).src.start <= *val && *val < (**r).src.end)
-                {
-                    Some(range) => (*range).dest.start + (*val - (*range).src.start),
-                    None => *val,
-                };
+            .map(|p| Piece {
+                src: p.dest(),
+                offset: -p.offset,
             })
             .collect();
+        pieces.sort_unstable_by_key(|p| p.src.start);
+        if pieces.windows(2).any(|w| w[0].src.end > w[1].src.start) {
+            return None;
+        }
+
+        Some(Mapping {
+            source: self.target.to_owned(),
+            target: self.source.to_owned(),
+            pieces: normalize(pieces),
+        })
     }
+}
 
-    fn apply_for_ranges(&self, ranges: &Vec<Range<u64>>) -> Vec<Range<u64>> {
-        if ranges.is_empty() {
-            return vec![];
+impl Almanac {
+    fn from(input: &str) -> Result<Almanac, ParseError> {
+        let mut mappings: HashMap<String, Mapping> = HashMap::new();
+        for block in input.split("\n\n") {
+            let mapping = Mapping::from(block.trim())?;
+            if mappings.contains_key(&mapping.source) {
+                return Err(ParseError::DuplicateMap(mapping.source));
+            }
+            mappings.insert(mapping.source.to_owned(), mapping);
         }
-        let mut results: Vec<Range<u64>> = vec![];
-        let mut queue: VecDeque<Range<u64>> = VecDeque::from_iter(ranges.to_owned());
-
-        let mut curr_range = queue.pop_front();
-        while curr_range.is_some() {
-            let rng = curr_range.unwrap();
-            let mut is_processed = false;
-            for map in self.ranges.iter() {
-                // curr range is included entirely into the mapping range
-                if map.src.start <= rng.start && rng.end <= map.src.end {
-                    // push mapped range into results
-                    let mapped_start = rng.start - map.src.start + map.dest.start;
-                    results.push(mapped_start..mapped_start + rng.end - rng.start);
-                    is_processed = true;
-                    break;
-                }
-                // curr range is intersecting with the end of mapping range
-                else if rng.end > map.src.end
-                    && rng.start < map.src.end
-                    && rng.start > map.src.start
-                {
-                    // println!(
-                    //     "Curr range: {:?}; mapping from: {:?}; mapping to: {:?}",
-                    //     rng, map.src, map.dest
-                    // );
-                    // push mapped intersection into results
-                    let mapped_start = rng.start - map.src.start + map.dest.start;
-                    let mapped_len = map.src.end - rng.start;
-                    results.push(mapped_start..mapped_start + mapped_len);
-                    is_processed = true;
-                    // push the rest of the current range to the queue
-                    let rest = map.src.end..rng.end;
-                    queue.push_back(rest);
-                    break;
-                }
-                // curr range is intersecting with the beginning of mapping range
-                else if rng.start < map.src.start
-                    && rng.end > map.src.start
-                    && rng.end < map.src.end
-                {
-                    // push mapped intersection into results
-                    let mapped_start = map.dest.start;
-                    let mapped_len = rng.end - map.src.start;
-                    results.push(mapped_start..mapped_start + mapped_len);
-                    is_processed = true;
-                    // push the rest of the current range to the queue
-                    let rest = rng.start..map.src.start;
-                    queue.push_back(rest);
-                    break;
-                }
-                // curr range covers entire mapping range
-                else if rng.start <= map.src.start && rng.end >= map.src.end {
-                    // push mapped intersection into results
-                    results.push(map.dest.start..map.dest.end);
-                    is_processed = true;
-                    // push two parts that left from the current range to the queue
-                    let starting_range = rng.start..map.src.start;
-                    if !starting_range.is_empty() {
-                        queue.push_back(starting_range);
-                    }
-                    let ending_range = map.src.end..rng.end;
-                    if !ending_range.is_empty() {
-                        queue.push_back(ending_range);
-                    }
-                    break;
-                }
-                // there was no intersection - continue to the next mapping range
+        Ok(Almanac { mappings })
+    }
+
+    // Compose the chain of mappings between two categories.
+    // If `to` comes before `from` in the almanac the chain is inverted.
+    fn mapping(&self, from: &str, to: &str) -> Option<Mapping> {
+        match self.chain(from, to) {
+            Some(chain) => Some(compose_chain(from, &chain)),
+            None => compose_chain(to, &self.chain(to, from)?).invert(),
+        }
+    }
+
+    fn chain(&self, from: &str, to: &str) -> Option<Vec<&Mapping>> {
+        // every category is a source of at most one mapping, so just follow them
+        let mut result: Vec<&Mapping> = vec![];
+        let mut curr = from;
+        while curr != to {
+            let mapping = self.mappings.get(curr)?;
+            if result.len() > self.mappings.len() {
+                return None;
             }
-            if !is_processed {
-                // curr range is outside of all mappings - push it directly into the results
-                results.push(rng);
+            result.push(mapping);
+            curr = &mapping.target;
+        }
+        Some(result)
+    }
+}
+
+fn compose_chain(from: &str, chain: &[&Mapping]) -> Mapping {
+    chain
+        .iter()
+        .fold(Mapping::identity(from), |acc, m| acc.compose(m))
+}
+
+fn shift(range: &Range<u64>, offset: i64) -> Range<u64> {
+    let shift_value = |value: u64| (value as i128 + offset as i128) as u64;
+    shift_value(range.start)..shift_value(range.end)
+}
+
+fn intersect(a: &Range<u64>, b: &Range<u64>) -> Range<u64> {
+    a.start.max(b.start)..a.end.min(b.end)
+}
+
+// Glue together adjacent pieces with the same offset
+fn normalize(pieces: Vec<Piece>) -> Vec<Piece> {
+    let mut result: Vec<Piece> = vec![];
+    for piece in pieces.into_iter().filter(|p| !p.src.is_empty()) {
+        match result.last_mut() {
+            Some(last) if last.src.end == piece.src.start && last.offset == piece.offset => {
+                last.src.end = piece.src.end
             }
-            curr_range = queue.pop_front();
+            _ => result.push(piece),
         }
+    }
+    result
+}
 
-        return results;
+// Pairs of values are the start and the length of a range
+fn values_to_ranges(values: &[u64]) -> Result<Vec<Range<u64>>, ParseError> {
+    let wrong_ranges = || {
+        let values: Vec<String> = values.iter().map(|x| x.to_string()).collect();
+        ParseError::SeedRanges(values.join(" "))
+    };
+    let pairs = values.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(wrong_ranges());
     }
+    pairs
+        .map(|s| s[0].checked_add(s[1]).map(|end| s[0]..end))
+        .collect::<Option<_>>()
+        .ok_or_else(wrong_ranges)
 }
 
-fn values_to_ranges(values: &Vec<u64>) -> Vec<Range<u64>> {
-    return values.chunks(2).map(|s| s[0]..s[0] + s[1]).collect();
+// `seeds: 79 14 55 13` and the maps after it
+pub(crate) fn parse_input(input: &str) -> Result<(Vec<u64>, Almanac), ParseError> {
+    let (seeds_str, rest) = input.split_once('\n').unwrap_or((input, ""));
+    let seeds: Vec<u64> = seeds_str
+        .trim_start_matches("seeds: ")
+        .split_whitespace()
+        .map(|x| x.parse().ok())
+        .collect::<Option<Vec<u64>>>()
+        .filter(|seeds| !seeds.is_empty())
+        .ok_or_else(|| ParseError::Seeds(seeds_str.to_string()))?;
+    Ok((seeds, Almanac::from(rest.trim())?))
 }
 
 fn main() -> io::Result<()> {
@@ -139,46 +299,131 @@ fn main() -> io::Result<()> {
     let input_path = "day5/src/input.txt";
 
     // Part1
-    // open the file
-    let file = File::open(input_path)?;
-    let mut reader = BufReader::new(file);
-
-    // read input seeds
-    let mut seeds_str = String::from("");
-    let _ = reader.read_line(&mut seeds_str);
-    let mut values: Vec<u64> = seeds_str
-        .trim_start_matches("seeds: ")
-        .split_whitespace()
-        .map(|x| u64::from_str_radix(x, 10).unwrap())
-        .collect();
-    let seeds = values.to_owned();
-
-    // read mappings info
-    let mut rest = String::from("");
-    let _ = reader.read_to_string(&mut rest);
-    let mappings: Vec<Mapping> = rest
-        .split("\n\n")
-        .map(|x| Mapping::from(x.trim()))
-        .collect();
-
-    // for each mapping take input seeds and map into the next layer
-    for mapping in mappings.iter() {
-        values = mapping.apply(&values);
-    }
+    // read the seeds and the mappings info
+    let input = fs::read_to_string(input_path)?;
+    let (seeds, almanac) =
+        parse_input(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let invalid_data = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let seed_to_location = almanac
+        .mapping("seed", "location")
+        .ok_or_else(|| invalid_data("Seeds can't be mapped to locations"))?;
 
     // find min location
-    let part1_result = values.iter().min().unwrap();
+    let part1_result = seeds
+        .iter()
+        .filter_map(|x| seed_to_location.apply(*x))
+        .min()
+        .ok_or_else(|| invalid_data("No seed has a location"))?;
     println!("Part1: {}", part1_result);
 
     // Part2
-    // for each mapping transform existing ranges into the new ones
-    let mut value_ranges = values_to_ranges(&seeds);
-    for mapping in mappings.iter() {
-        value_ranges = mapping.apply_for_ranges(&value_ranges);
-    }
+    // transform seed ranges into the location ones
+    let seed_ranges =
+        values_to_ranges(&seeds).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let value_ranges = seed_to_location.apply_for_ranges(&seed_ranges);
     // find min location
-    let part2_result = value_ranges.iter().map(|x| x.start).min().unwrap();
+    let part2_result = value_ranges
+        .iter()
+        .map(|x| x.start)
+        .min()
+        .ok_or_else(|| invalid_data("No seed range has a location"))?;
     println!("Part2: {}", part2_result);
 
-    return Ok(());
+    // Optional query: `<from category> <to category> <value>`
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [from, to, value] = &args[..] {
+        match almanac.mapping(from, to) {
+            Some(mapping) => {
+                let value: u64 = value.parse().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidInput, format!("Wrong value {value}"))
+                })?;
+                println!("{from} {value} -> {to} {:?}", mapping.apply(value));
+            }
+            None => println!("Can't map {from} to {to}"),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let (seeds, almanac) = parse_input(include_str!("input_test.txt")).unwrap();
+        let mapping = almanac.mapping("seed", "location").unwrap();
+        let locations: Vec<Option<u64>> = seeds.iter().map(|x| mapping.apply(*x)).collect();
+        assert_eq!(locations, [Some(82), Some(43), Some(86), Some(35)]);
+
+        let ranges = mapping.apply_for_ranges(&values_to_ranges(&seeds).unwrap());
+        assert_eq!(ranges.iter().map(|x| x.start).min(), Some(46));
+    }
+
+    #[test]
+    fn inverted_mappings() {
+        let (seeds, almanac) = parse_input(include_str!("input_test.txt")).unwrap();
+        let seed_to_location = almanac.mapping("seed", "location").unwrap();
+        let location_to_seed = seed_to_location.invert().unwrap();
+        assert_eq!(location_to_seed.source, "location");
+        assert_eq!(location_to_seed.target, "seed");
+        for value in seeds
+            .into_iter()
+            .chain([0, 1, 49, 50, 97, 98, 99, 100, 1000])
+        {
+            let location = seed_to_location.apply(value).unwrap();
+            assert_eq!(location_to_seed.apply(location), Some(value));
+        }
+
+        // two seeds going to the same soil
+        let mapping = Mapping::from("seed-to-soil map:\n50 98 2\n50 10 2").unwrap();
+        assert!(mapping.invert().is_none());
+    }
+
+    #[test]
+    fn category_queries() {
+        let (_, almanac) = parse_input(include_str!("input_test.txt")).unwrap();
+        // seed 79 is soil 81 and light 74, seed 14 is fertilizer 53 and humidity 43
+        assert_eq!(
+            almanac.mapping("soil", "light").unwrap().apply(81),
+            Some(74)
+        );
+        assert_eq!(
+            almanac.mapping("light", "soil").unwrap().apply(74),
+            Some(81)
+        );
+        let fertilizer_to_humidity = almanac.mapping("fertilizer", "humidity").unwrap();
+        assert_eq!(fertilizer_to_humidity.apply(53), Some(43));
+        assert_eq!(almanac.mapping("water", "water").unwrap().apply(7), Some(7));
+        assert!(almanac.mapping("seed", "gold").is_none());
+    }
+
+    #[test]
+    fn wrong_seed_ranges() {
+        assert!(matches!(
+            values_to_ranges(&[79, 14, 55]),
+            Err(ParseError::SeedRanges(_))
+        ));
+        assert!(matches!(
+            values_to_ranges(&[u64::MAX, 1]),
+            Err(ParseError::SeedRanges(_))
+        ));
+    }
+
+    #[test]
+    fn wrong_input() {
+        assert!(matches!(
+            parse_input("seeds: \n\nseed-to-soil map:\n50 98 2"),
+            Err(ParseError::Seeds(_))
+        ));
+        assert!(matches!(
+            parse_input("seeds: 1 2\n\nseed-to-soil map:\n50 98 2\n\nseed-to-soil map:\n1 2 3"),
+            Err(ParseError::DuplicateMap(source)) if source == "seed"
+        ));
+        assert!(matches!(
+            parse_input("seeds: 1 2\n\nseed-to-soil map:\n50 98 5\n10 100 3"),
+            Err(ParseError::Overlap(header)) if header == "seed-to-soil map:"
+        ));
+    }
 }
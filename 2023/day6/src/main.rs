@@ -1,72 +1,200 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day6/src/input.txt";
 
     // Part1
-    // open the file and parse input
-    let file = File::open(input_path)?;
-    let mut reader = BufReader::new(file);
-    let mut buf = String::from("");
-    let _ = reader.read_line(&mut buf);
-    let times: Vec<u64> = buf
-        .trim_start_matches("Time:")
-        .trim()
-        .split_whitespace()
-        .map(|x| u64::from_str_radix(x, 10).unwrap())
-        .collect();
-    let part2_time =
-        u64::from_str_radix(&buf.trim_start_matches("Time:").trim().replace(" ", ""), 10).unwrap();
-
-    buf.clear();
-    let _ = reader.read_line(&mut buf);
-    let distances: Vec<u64> = buf
-        .trim_start_matches("Distance:")
-        .trim()
-        .split_whitespace()
-        .map(|x| u64::from_str_radix(x, 10).unwrap())
-        .collect();
-    let part2_distance = u64::from_str_radix(
-        &buf.trim_start_matches("Distance:").trim().replace(" ", ""),
-        10,
-    )
-    .unwrap();
-
-    let part1_result = times
-        .iter()
-        .zip(distances.iter())
-        .map(|x| calc_win_conditions(*x.0, *x.1))
-        .reduce(|acc, x| acc * x);
-    println!("Part1: {:?}", part1_result);
+    // read the file and parse input
+    let input = fs::read_to_string(input_path)?;
+    let (times, distances, (part2_time, part2_distance)) =
+        parse_races(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    let part1_result = count_all_wins(&times, &distances).ok_or(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Product overflow",
+    ))?;
+    println!("Part1: {}", part1_result);
 
-    let part2_result = calc_win_conditions(part2_time, part2_distance);
-    println!("Part2: {:?}", part2_result);
+    let part2_result = count_wins(part2_time, part2_distance);
+    println!("Part2: {}", part2_result);
 
-    return Ok(());
+    Ok(())
 }
 
-fn calc_win_conditions(race_time: u64, record: u64) -> u64 {
-    let mut hold_time = record / race_time;
-    // find starting win condition
-    while calc_distance(hold_time, race_time) <= record && hold_time < race_time {
-        hold_time += 1;
+// The boat goes `hold_time * (race_time - hold_time)`, so the winning hold times
+// are between the roots of h^2 - race_time * h + record = 0.
+// The integer square root gives the roots up to one, and the bounds are fixed
+// by checking the distances exactly.
+fn winning_interval(race_time: u64, record: u64) -> Option<RangeInclusive<u64>> {
+    let (time, record) = (race_time as u128, record as u128);
+    let discriminant = (time * time).checked_sub(4 * record)?;
+    let is_win = |hold_time: u128| hold_time <= time && calc_distance(hold_time, time) > record;
+
+    // the first hold time that wins, the last one is symmetric to it
+    let mut start = (time - discriminant.isqrt()) / 2;
+    while start > 0 && is_win(start - 1) {
+        start -= 1;
+    }
+    while start <= time / 2 && !is_win(start) {
+        start += 1;
     }
-    let start = hold_time;
+    if !is_win(start) {
+        return None;
+    }
+    let end = time - start;
+    Some(start as u64..=end as u64)
+}
+
+fn count_wins(race_time: u64, record: u64) -> u64 {
+    winning_interval(race_time, record).map_or(0, |range| range.end() - range.start() + 1)
+}
 
-    hold_time += 1;
-    // find ending win condition
-    while calc_distance(hold_time, race_time) > record && hold_time < race_time {
-        hold_time += 1;
+// Product of the numbers of ways to win every race, `None` if it doesn't fit into u64
+fn count_all_wins(times: &[u64], records: &[u64]) -> Option<u64> {
+    times
+        .iter()
+        .zip(records.iter())
+        .try_fold(1u64, |acc, (time, record)| {
+            acc.checked_mul(count_wins(*time, *record))
+        })
+}
+
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    MissingLine(&'static str),
+    // a number that isn't one or doesn't fit into u64
+    WrongNumber(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingLine(name) => write!(f, "There is no {name} line"),
+            ParseError::WrongNumber(input) => write!(f, "Wrong number \"{input}\""),
+        }
     }
-    let end = hold_time;
+}
 
-    // println!("Start: {}, end: {}", start, end);
+impl std::error::Error for ParseError {}
 
-    return if start <= end { end - start } else { 0 };
+// Times and distances of the races, and the single race of part 2
+// with the spaces between the numbers removed
+type Races = (Vec<u64>, Vec<u64>, (u64, u64));
+
+pub(crate) fn parse_races(input: &str) -> Result<Races, ParseError> {
+    let mut lines = input.lines();
+    let time_line = lines
+        .next()
+        .ok_or(ParseError::MissingLine("Time"))?
+        .trim_start_matches("Time:");
+    let distance_line = lines
+        .next()
+        .ok_or(ParseError::MissingLine("Distance"))?
+        .trim_start_matches("Distance:");
+    let parse = |x: &str| {
+        x.parse()
+            .map_err(|_| ParseError::WrongNumber(x.to_string()))
+    };
+    let numbers = |line: &str| -> Result<Vec<u64>, ParseError> {
+        line.split_whitespace().map(parse).collect()
+    };
+    let kerned = |line: &str| -> Result<u64, ParseError> { parse(&line.trim().replace(' ', "")) };
+    Ok((
+        numbers(time_line)?,
+        numbers(distance_line)?,
+        (kerned(time_line)?, kerned(distance_line)?),
+    ))
 }
 
-fn calc_distance(hold_time: u64, race_time: u64) -> u64 {
-    return hold_time * (race_time - hold_time);
+fn calc_distance(hold_time: u128, race_time: u128) -> u128 {
+    hold_time * (race_time - hold_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn winning_interval_brute_force(race_time: u64, record: u64) -> Option<RangeInclusive<u64>> {
+        let wins: Vec<u64> = (0..=race_time)
+            .filter(|h| calc_distance(*h as u128, race_time as u128) > record as u128)
+            .collect();
+        Some(*wins.first()?..=*wins.last()?)
+    }
+
+    // records around the best distance of the race
+    fn small_race() -> impl Strategy<Value = (u64, u64)> {
+        (0u64..200).prop_flat_map(|time| (Just(time), 0..=time * time / 4 + 1))
+    }
+
+    // races too long to try every hold time, often with only a few ways to win
+    fn large_race() -> impl Strategy<Value = (u64, u64)> {
+        (any::<u64>(), 0u32..64, any::<bool>(), any::<u64>()).prop_map(
+            |(time, shift, near_best, random)| {
+                let time = time >> shift;
+                let best = (time / 2) as u128 * (time - time / 2) as u128;
+                let record = if near_best {
+                    best.saturating_sub((random % 1000) as u128)
+                } else {
+                    random as u128 % (best + 1)
+                };
+                (time, record.min(u64::MAX as u128) as u64)
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn matches_brute_force((time, record) in small_race()) {
+            prop_assert_eq!(
+                winning_interval(time, record),
+                winning_interval_brute_force(time, record)
+            );
+        }
+
+        #[test]
+        fn races_match_brute_force(races in prop::collection::vec(small_race(), 0..5)) {
+            let (times, records): (Vec<u64>, Vec<u64>) = races.iter().copied().unzip();
+            let expected = races
+                .iter()
+                .map(|(time, record)| {
+                    winning_interval_brute_force(*time, *record)
+                        .map_or(0, |range| range.end() - range.start() + 1)
+                })
+                .product();
+            prop_assert_eq!(count_all_wins(&times, &records), Some(expected));
+        }
+
+        // the interval has to be tight: the bounds win and their neighbors don't
+        #[test]
+        fn interval_is_tight((time, record) in large_race()) {
+            let is_win = |h: u64| calc_distance(h as u128, time as u128) > record as u128;
+            match winning_interval(time, record) {
+                Some(range) => {
+                    prop_assert!(is_win(*range.start()) && is_win(*range.end()));
+                    prop_assert!(*range.start() == 0 || !is_win(range.start() - 1));
+                    prop_assert!(*range.end() == time || !is_win(range.end() + 1));
+                }
+                None => prop_assert!(!is_win(time / 2)),
+            }
+        }
+    }
+
+    #[test]
+    fn example() {
+        let (times, distances, (time, distance)) =
+            parse_races("Time:      7  15   30\nDistance:  9  40  200").unwrap();
+        assert_eq!(count_all_wins(&times, &distances), Some(288));
+        assert_eq!(count_wins(time, distance), 71503);
+    }
+
+    #[test]
+    fn product_overflow() {
+        let times = [u32::MAX as u64 * 4; 3];
+        assert_eq!(count_all_wins(&times, &[0; 3]), None);
+    }
 }
@@ -1,146 +1,217 @@
-use core::panic;
-use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 
-struct Hand {
-    cards: Vec<u8>,
-    combination_rank: u8,
+pub(crate) struct Hand {
+    cards: Vec<char>,
     bid: u32,
 }
 
+// Hand types from the weakest to the strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum HandType {
+    HighCard,
+    OnePair,
+    TwoPairs,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
+}
+
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    WrongFormat(String),
+    UnknownCard(char),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongFormat(input) => write!(f, "Wrong hand \"{input}\""),
+            ParseError::UnknownCard(card) => write!(f, "Unknown card {card}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Rules of a Camel Cards game
+struct RuleSet {
+    // all the cards from the weakest to the strongest
+    card_order: Vec<char>,
+    // cards that pretend to be whatever makes the hand stronger
+    wildcards: Vec<char>,
+    // hand types from the weakest to the strongest
+    type_ranking: Vec<HandType>,
+}
+
 impl Hand {
-    fn from_part1(input: &str) -> Hand {
+    // `32T3K 765`: five cards and the bid
+    pub(crate) fn from_str(input: &str) -> Result<Hand, ParseError> {
+        let wrong_format = || ParseError::WrongFormat(input.to_string());
         let parts: Vec<&str> = input.split_whitespace().collect();
-        let cards: Vec<u8> = parts[0]
-            .chars()
-            .map(|ch| match ch {
-                'A' => 14,
-                'K' => 13,
-                'Q' => 12,
-                'J' => 11,
-                'T' => 10,
-                '9' => 9,
-                '8' => 8,
-                '7' => 7,
-                '6' => 6,
-                '5' => 5,
-                '4' => 4,
-                '3' => 3,
-                '2' => 2,
-                _ => panic!("Unexpected card value"),
-            })
-            .collect();
+        let [cards, bid] = parts[..] else {
+            return Err(wrong_format());
+        };
+        Ok(Hand {
+            cards: parse_cards(cards)?,
+            bid: bid.parse().map_err(|_| wrong_format())?,
+        })
+    }
+}
 
-        let mut card_counts: HashMap<u8, u8> = HashMap::new();
-        for c in cards.iter() {
-            card_counts.entry(*c).and_modify(|x| *x += 1).or_insert(1);
+// `32T3K`: exactly five known cards
+pub(crate) fn parse_cards(input: &str) -> Result<Vec<char>, ParseError> {
+    let cards: Vec<char> = input.chars().collect();
+    if cards.len() != 5 {
+        return Err(ParseError::WrongFormat(input.to_string()));
+    }
+    if let Some(card) = cards.iter().find(|card| !"23456789TJQKA".contains(**card)) {
+        return Err(ParseError::UnknownCard(*card));
+    }
+    Ok(cards)
+}
+
+impl HandType {
+    const ALL: [HandType; 7] = [
+        HandType::HighCard,
+        HandType::OnePair,
+        HandType::TwoPairs,
+        HandType::ThreeOfAKind,
+        HandType::FullHouse,
+        HandType::FourOfAKind,
+        HandType::FiveOfAKind,
+    ];
+
+    // Hand type by the sizes of groups of equal cards, the largest first
+    fn from_groups(groups: &[u8]) -> HandType {
+        match groups {
+            [5, ..] => HandType::FiveOfAKind,
+            [4, ..] => HandType::FourOfAKind,
+            [3, 2, ..] => HandType::FullHouse,
+            [3, ..] => HandType::ThreeOfAKind,
+            [2, 2, ..] => HandType::TwoPairs,
+            [2, ..] => HandType::OnePair,
+            _ => HandType::HighCard,
         }
-        let combination_rank = if card_counts.len() == 5 {
-            1 // high card
-        } else if card_counts.len() == 4 {
-            2 // one pair
-        } else if card_counts.len() == 3 && card_counts.iter().any(|x| *x.1 == 3) {
-            4 // three of a kind
-        } else if card_counts.len() == 3 {
-            3 // two pairs
-        } else if card_counts.len() == 2 && card_counts.iter().any(|x| *x.1 == 4) {
-            6 // four of a kind
-        } else if card_counts.len() == 2 {
-            5 // full house
-        } else {
-            7 // five of a kind
-        };
+    }
 
-        return Hand {
-            cards,
-            combination_rank,
-            bid: u32::from_str_radix(parts[1], 10).unwrap(),
-        };
+    fn name(&self) -> &'static str {
+        match self {
+            HandType::HighCard => "high card",
+            HandType::OnePair => "one pair",
+            HandType::TwoPairs => "two pairs",
+            HandType::ThreeOfAKind => "three of a kind",
+            HandType::FullHouse => "full house",
+            HandType::FourOfAKind => "four of a kind",
+            HandType::FiveOfAKind => "five of a kind",
+        }
     }
+}
 
-    fn from_part2(input: &str) -> Hand {
-        let parts: Vec<&str> = input.split_whitespace().collect();
-        let cards: Vec<u8> = parts[0]
-            .chars()
-            .map(|ch| match ch {
-                'A' => 13,
-                'K' => 12,
-                'Q' => 11,
-                'T' => 10,
-                '9' => 9,
-                '8' => 8,
-                '7' => 7,
-                '6' => 6,
-                '5' => 5,
-                '4' => 4,
-                '3' => 3,
-                '2' => 2,
-                'J' => 1,
-                _ => panic!("Unexpected card value"),
-            })
-            .collect();
+impl RuleSet {
+    fn part1() -> RuleSet {
+        RuleSet {
+            card_order: "23456789TJQKA".chars().collect(),
+            wildcards: vec![],
+            type_ranking: HandType::ALL.to_vec(),
+        }
+    }
 
-        let mut card_counts: HashMap<u8, u8> = HashMap::new();
-        for c in cards.iter() {
-            card_counts.entry(*c).and_modify(|x| *x += 1).or_insert(1);
+    // `J` is a joker now: the weakest card that can replace any other one
+    fn part2() -> RuleSet {
+        RuleSet {
+            card_order: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            type_ranking: HandType::ALL.to_vec(),
         }
-        // print!("Cards: {:?} => ", card_counts);
-        // apply jokers
-        if card_counts.contains_key(&1) {
-            let jokers_count = card_counts.remove_entry(&1).unwrap().1.to_owned();
-            let max_pair = match card_counts.iter().max_by_key(|x| *x.1) {
-                Some(pair) => pair,
-                None => (&2, &5),
+    }
+
+    // `None` for a card that isn't played under these rules
+    fn card_value(&self, card: char) -> Option<usize> {
+        self.card_order.iter().position(|c| *c == card)
+    }
+
+    // Type of the hand and the card all the wildcards turn into
+    fn classify_with_wildcards(&self, cards: &[char]) -> (HandType, Option<char>) {
+        let mut card_counts: HashMap<char, u8> = HashMap::new();
+        let mut wildcards_count = 0;
+        for c in cards.iter() {
+            if self.wildcards.contains(c) {
+                wildcards_count += 1;
+            } else {
+                *card_counts.entry(*c).or_insert(0) += 1;
             }
-            .to_owned();
-            card_counts
-                .entry(*max_pair.0)
-                .and_modify(|x| *x += jokers_count)
-                .or_insert(jokers_count);
         }
-        // println!("{:?}", card_counts);
-
-        let combination_rank = if card_counts.len() == 5 {
-            1 // high card
-        } else if card_counts.len() == 4 {
-            2 // one pair
-        } else if card_counts.len() == 3 && card_counts.iter().any(|x| *x.1 == 3) {
-            4 // three of a kind
-        } else if card_counts.len() == 3 {
-            3 // two pairs
-        } else if card_counts.len() == 2 && card_counts.iter().any(|x| *x.1 == 4) {
-            6 // four of a kind
-        } else if card_counts.len() == 2 {
-            5 // full house
+
+        // wildcards always join the largest group (the strongest card on ties)
+        let best_card = card_counts
+            .iter()
+            .max_by_key(|(card, count)| (**count, self.card_value(**card)))
+            .map(|(card, _)| *card);
+        let mut groups: Vec<u8> = card_counts.values().copied().collect();
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        match groups.first_mut() {
+            Some(largest) => *largest += wildcards_count,
+            None => groups.push(wildcards_count),
+        }
+
+        let substitute = if wildcards_count > 0 {
+            best_card.or(cards.first().copied())
         } else {
-            7 // five of a kind
+            None
         };
+        (HandType::from_groups(&groups), substitute)
+    }
 
-        return Hand {
-            cards,
-            combination_rank,
-            bid: u32::from_str_radix(parts[1], 10).unwrap(),
-        };
+    fn classify(&self, cards: &[char]) -> HandType {
+        self.classify_with_wildcards(cards).0
     }
 
-    fn cmp(&self, other: &Hand) -> Ordering {
-        // Compare ranks of combinations
-        let combination_cmp = self.combination_rank.cmp(&other.combination_rank);
-        return match combination_cmp {
-            // if combinations of equal rank - compare cards one by one
-            Ordering::Equal => self
-                .cards
-                .iter()
-                .zip(other.cards.iter())
-                .find_map(|x| match x.0.cmp(x.1) {
-                    Ordering::Equal => None,
-                    other => Some(other),
-                })
-                .unwrap(),
-            other => other,
-        };
+    fn explain(&self, cards: &[char]) -> String {
+        let hand: String = cards.iter().collect();
+        match self.classify_with_wildcards(cards) {
+            (hand_type, Some(substitute)) => format!(
+                "{hand}: {} (wildcards play as {substitute})",
+                hand_type.name()
+            ),
+            (hand_type, None) => format!("{hand}: {}", hand_type.name()),
+        }
+    }
+
+    // Hands are compared by the rank of their type first
+    // and if it's equal - card by card.
+    // `None` if the hand has a card unknown to the rules.
+    fn sort_key(&self, cards: &[char]) -> Option<(usize, Vec<usize>)> {
+        let hand_type = self.classify(cards);
+        let type_rank = self.type_ranking.iter().position(|t| *t == hand_type)?;
+        let values = cards
+            .iter()
+            .map(|c| self.card_value(*c))
+            .collect::<Option<_>>()?;
+        Some((type_rank, values))
+    }
+
+    // Hands from the weakest to the strongest
+    fn rank<'a>(&self, hands: &'a [Hand]) -> Option<Vec<&'a Hand>> {
+        let mut keyed: Vec<((usize, Vec<usize>), &Hand)> = hands
+            .iter()
+            .map(|h| self.sort_key(&h.cards).map(|key| (key, h)))
+            .collect::<Option<_>>()?;
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        Some(keyed.into_iter().map(|(_, h)| h).collect())
+    }
+
+    // `None` if some hand can't be ranked or the sum doesn't fit into u64
+    fn total_winnings(&self, hands: &[Hand]) -> Option<u64> {
+        self.rank(hands)?
+            .iter()
+            .enumerate()
+            .try_fold(0u64, |acc, (i, h)| {
+                (h.bid as u64).checked_mul(i as u64 + 1)?.checked_add(acc)
+            })
     }
 }
 
@@ -148,42 +219,84 @@ fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day7/src/input.txt";
 
-    // Part1
+    // Hands passed as arguments are just explained under both rule sets
+    let hands_to_explain: Vec<Vec<char>> = std::env::args()
+        .skip(1)
+        .map(|arg| parse_cards(&arg))
+        .collect::<Result<_, _>>()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    if !hands_to_explain.is_empty() {
+        for cards in hands_to_explain.iter() {
+            println!("Part1 rules: {}", RuleSet::part1().explain(cards));
+            println!("Part2 rules: {}", RuleSet::part2().explain(cards));
+        }
+        return Ok(());
+    }
+
     // open the file and parse input
-    let mut file = File::open(input_path)?;
-    let mut reader = BufReader::new(file);
-    let mut inputs: Vec<Hand> = reader
-        .lines()
-        .map(|l| Hand::from_part1(&l.unwrap()))
-        .collect();
-
-    // sort hands by their relative score
-    inputs.sort_unstable_by(|a, b| a.cmp(b));
-
-    // calc result
-    let part1_total_winnings = inputs
-        .iter()
-        .enumerate()
-        .fold(0u32, |acc, (i, h)| acc + h.bid * (i + 1) as u32);
-    println!("Part1: {:?}", part1_total_winnings);
+    let file = File::open(input_path)?;
+    let reader = BufReader::new(file);
+    let mut inputs: Vec<Hand> = vec![];
+    for line in reader.lines() {
+        let hand = Hand::from_str(&line?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        inputs.push(hand);
+    }
+
+    // Part1
+    match RuleSet::part1().total_winnings(&inputs) {
+        Some(value) => println!("Part1: {:?}", value),
+        None => println!("Part1: overflow"),
+    }
 
     // Part2
-    file = File::open(input_path)?;
-    reader = BufReader::new(file);
-    inputs = reader
-        .lines()
-        .map(|l| Hand::from_part2(&l.unwrap()))
-        .collect();
-
-    // sort hands by their relative score
-    inputs.sort_unstable_by(|a, b| a.cmp(b));
-
-    // calc result
-    let part2_total_winnings = inputs
-        .iter()
-        .enumerate()
-        .fold(0u32, |acc, (i, h)| acc + h.bid * (i + 1) as u32);
-    println!("Part2: {:?}", part2_total_winnings);
-
-    return Ok(());
+    match RuleSet::part2().total_winnings(&inputs) {
+        Some(value) => println!("Part2: {:?}", value),
+        None => println!("Part2: overflow"),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Vec<Hand> {
+        input
+            .lines()
+            .map(|line| Hand::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn example() {
+        let hands = parse(include_str!("input_test.txt"));
+        assert_eq!(RuleSet::part1().total_winnings(&hands), Some(6440));
+        assert_eq!(RuleSet::part2().total_winnings(&hands), Some(5905));
+    }
+
+    #[test]
+    fn large_bids() {
+        let hands = parse("32T3K 4294967295\nKK677 4294967295");
+        assert_eq!(
+            RuleSet::part1().total_winnings(&hands),
+            Some(4294967295 * 3)
+        );
+    }
+
+    #[test]
+    fn explain() {
+        let cards = parse_cards("KTJJT").unwrap();
+        assert_eq!(RuleSet::part1().explain(&cards), "KTJJT: two pairs");
+        assert_eq!(
+            RuleSet::part2().explain(&cards),
+            "KTJJT: four of a kind (wildcards play as T)"
+        );
+        assert!(matches!(
+            parse_cards("XYZ12"),
+            Err(ParseError::UnknownCard('X'))
+        ));
+        assert!(matches!(parse_cards("KT"), Err(ParseError::WrongFormat(_))));
+    }
 }
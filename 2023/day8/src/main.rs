@@ -1,10 +1,10 @@
-use core::panic;
-use std::collections::{HashMap, VecDeque};
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
 
 #[derive(Copy, Clone)]
-enum Instruction {
+pub(crate) enum Instruction {
     Left,
     Right,
 }
@@ -12,108 +12,383 @@ enum Instruction {
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day8/src/input.txt";
+    let verbose = std::env::args().any(|arg| arg == "--verbose");
 
     // Part1
     // open the file and parse input
-    let file = File::open(input_path)?;
-    let mut reader = BufReader::new(file);
-    let mut buf = String::from("");
-    let _ = reader.read_line(&mut buf);
-    let input_instructions: Vec<Instruction> = buf
+    let input = fs::read_to_string(input_path)?;
+    let (instructions, network) =
+        parse_input(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    // go from start to finish
+    match network.id("AAA") {
+        Some(start) => {
+            let part1_result = solve(&network, &instructions, &[start], |name| name == "ZZZ");
+            print_result("Part1", part1_result);
+        }
+        None => println!("Part1: there is no AAA node"),
+    }
+
+    // Part2
+    let starts: Vec<usize> = network.ids_matching(|name| name.ends_with('A'));
+    if verbose && instructions.is_empty() {
+        println!("There are no instructions, the ghosts don't move");
+    } else if verbose {
+        for start in starts.iter() {
+            let cycle =
+                GhostCycle::find(&network, &instructions, *start, |name| name.ends_with('Z'));
+            println!(
+                "{}: ends at {:?} before the cycle, the cycle starts at {} and takes {} steps, ends at {:?} in it",
+                network.names[*start], cycle.prefix_hits, cycle.start, cycle.len, cycle.cycle_hits
+            );
+        }
+    }
+    let part2_result = solve(&network, &instructions, &starts, |name| name.ends_with('Z'));
+    print_result("Part2", part2_result);
+
+    Ok(())
+}
+
+fn print_result(part: &str, result: Result<Option<u128>, Overflow>) {
+    match result {
+        Ok(Some(steps)) => println!("{part}: {steps}"),
+        Ok(None) => println!("{part}: the ghosts never meet on the end nodes"),
+        Err(Overflow) => println!("{part}: overflow"),
+    }
+}
+
+// The steps of the ghosts don't fit into u128
+#[derive(Debug, PartialEq)]
+struct Overflow;
+
+#[derive(Debug)]
+pub(crate) enum ParseError {
+    WrongInstruction(char),
+    WrongFormat(String),
+    DuplicateNode(String),
+    UndefinedNode(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongInstruction(ch) => write!(f, "Wrong instruction '{ch}'"),
+            ParseError::WrongFormat(input) => write!(f, "Wrong node \"{input}\""),
+            ParseError::DuplicateNode(name) => write!(f, "Node {name} is defined twice"),
+            ParseError::UndefinedNode(name) => write!(f, "Node {name} is not defined"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// The instructions, an empty line and then the nodes
+pub(crate) fn parse_input(input: &str) -> Result<(Vec<Instruction>, Network), ParseError> {
+    let mut lines = input.lines();
+    let instructions = lines
+        .next()
+        .unwrap_or("")
         .trim()
         .chars()
         .map(|ch| match ch {
-            'L' => Instruction::Left,
-            'R' => Instruction::Right,
-            _ => panic!("Wrong instruction"),
+            'L' => Ok(Instruction::Left),
+            'R' => Ok(Instruction::Right),
+            _ => Err(ParseError::WrongInstruction(ch)),
         })
-        .collect();
-    let _ = reader.read_line(&mut buf); // skip one line
-    let mut network: HashMap<String, (String, String)> = HashMap::new();
-    for line in reader.lines() {
-        let data = line?;
-        let parts: Vec<&str> = data.split('=').map(|s| s.trim()).collect();
-        let rest: Vec<&str> = parts[1]
-            .trim_matches(|c| c == '(' || c == ')')
-            .split(',')
-            .map(|s| s.trim())
-            .collect();
-        network.insert(
-            parts[0].to_owned(),
-            (rest[0].to_owned(), rest[1].to_owned()),
-        );
+        .collect::<Result<Vec<_>, _>>()?;
+    lines.next(); // skip one line
+    let lines: Vec<String> = lines.map(|line| line.to_owned()).collect();
+    Ok((instructions, Network::from_lines(&lines)?))
+}
+
+// Nodes with names interned to their indices
+pub(crate) struct Network {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
+
+impl Network {
+    // `AAA = (BBB, CCC)` per line
+    fn from_lines(lines: &[String]) -> Result<Network, ParseError> {
+        let mut network = Network {
+            names: vec![],
+            ids: HashMap::new(),
+            left: vec![],
+            right: vec![],
+        };
+        let mut is_defined: Vec<bool> = vec![];
+        for line in lines.iter() {
+            let wrong_format = || ParseError::WrongFormat(line.to_string());
+            let (name, rest) = line.split_once('=').ok_or_else(wrong_format)?;
+            let (left, right) = rest
+                .trim()
+                .trim_matches(|c| c == '(' || c == ')')
+                .split_once(',')
+                .ok_or_else(wrong_format)?;
+            let [id, left, right] = [name, left, right].map(|x| network.intern(x.trim()));
+            is_defined.resize(network.names.len(), false);
+            if is_defined[id] {
+                return Err(ParseError::DuplicateNode(network.names[id].to_owned()));
+            }
+            network.left[id] = left;
+            network.right[id] = right;
+            is_defined[id] = true;
+        }
+        match is_defined.iter().position(|x| !x) {
+            Some(id) => Err(ParseError::UndefinedNode(network.names[id].to_owned())),
+            None => Ok(network),
+        }
     }
 
-    // go from start to finish
-    let part1_result = get_steps_to_finish("AAA", &|x| x == "ZZZ", &input_instructions, &network);
-    println!("Part1: {part1_result}");
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        self.left.push(id);
+        self.right.push(id);
+        id
+    }
 
-    // Part2
-    let individual_steps: Vec<u64> = network
-        .keys()
-        .filter_map(|s| {
-            if s.ends_with('A') {
-                Some(get_steps_to_finish(
-                    &s.as_str(),
-                    &|x| x.ends_with('Z'),
-                    &input_instructions,
-                    &network,
-                ))
-            } else {
-                None
+    fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    fn ids_matching(&self, predicate: impl Fn(&str) -> bool) -> Vec<usize> {
+        (0..self.names.len())
+            .filter(|id| predicate(&self.names[*id]))
+            .collect()
+    }
+
+    fn next(&self, id: usize, instruction: Instruction) -> usize {
+        match instruction {
+            Instruction::Left => self.left[id],
+            Instruction::Right => self.right[id],
+        }
+    }
+}
+
+// Where a ghost is on the end nodes.
+// The state of a ghost is its node and the position in the instructions,
+// so after at most (nodes * instructions) steps it starts going in a cycle.
+struct GhostCycle {
+    // steps when it's on the end node before the cycle starts
+    prefix_hits: Vec<u128>,
+    // the first step of the cycle
+    start: u128,
+    len: u128,
+    // steps when it's on the end node during the first round of the cycle
+    cycle_hits: Vec<u128>,
+}
+
+impl GhostCycle {
+    fn find(
+        network: &Network,
+        instructions: &[Instruction],
+        start: usize,
+        is_end: impl Fn(&str) -> bool,
+    ) -> GhostCycle {
+        let is_end: Vec<bool> = network.names.iter().map(|name| is_end(name)).collect();
+        // the first step each state has been visited at
+        let mut visited: Vec<Option<u128>> = vec![None; network.names.len() * instructions.len()];
+        let mut hits: Vec<u128> = vec![];
+        let mut curr = start;
+        let mut step = 0u128;
+        loop {
+            let instruction_idx = (step % instructions.len() as u128) as usize;
+            let state = curr * instructions.len() + instruction_idx;
+            if let Some(cycle_start) = visited[state] {
+                let (prefix_hits, cycle_hits) = hits.iter().partition(|h| **h < cycle_start);
+                return GhostCycle {
+                    prefix_hits,
+                    start: cycle_start,
+                    len: step - cycle_start,
+                    cycle_hits,
+                };
             }
-        })
+            visited[state] = Some(step);
+            if is_end[curr] {
+                hits.push(step);
+            }
+            curr = network.next(curr, instructions[instruction_idx]);
+            step += 1;
+        }
+    }
+
+    fn is_on_end(&self, step: u128) -> bool {
+        if step < self.start {
+            self.prefix_hits.contains(&step)
+        } else {
+            let offset = (step - self.start) % self.len;
+            self.cycle_hits.contains(&(self.start + offset))
+        }
+    }
+}
+
+// The first step when all the ghosts are on the end nodes at once.
+// Before every ghost gets into its cycle the step has to be one of the prefix hits
+// of the ghost starting its cycle the last. After that every ghost is periodic
+// and the steps are combined with the Chinese remainder theorem.
+fn solve(
+    network: &Network,
+    instructions: &[Instruction],
+    starts: &[usize],
+    is_end: impl Fn(&str) -> bool,
+) -> Result<Option<u128>, Overflow> {
+    if starts.is_empty() || instructions.is_empty() {
+        return Ok(None);
+    }
+    let cycles: Vec<GhostCycle> = starts
+        .iter()
+        .map(|start| GhostCycle::find(network, instructions, *start, &is_end))
         .collect();
 
-    let part2_result = individual_steps.iter().fold(1, |acc, x| lcm(acc, *x));
-    println!("Part2: {part2_result}");
+    let latest = cycles.iter().max_by_key(|c| c.start).unwrap();
+    let early_step = latest
+        .prefix_hits
+        .iter()
+        .find(|step| cycles.iter().all(|c| c.is_on_end(**step)));
+    if let Some(step) = early_step {
+        return Ok(Some(*step));
+    }
 
-    return Ok(());
-}
+    // (remainder, modulus) of the steps all the ghosts seen so far are on the end nodes
+    let mut congruences: Vec<(u128, u128)> = vec![(0, 1)];
+    for cycle in cycles.iter() {
+        let mut next: Vec<(u128, u128)> = vec![];
+        for (r, m) in congruences.iter() {
+            for hit in cycle.cycle_hits.iter() {
+                if let Some(congruence) = combine((*r, *m), (*hit % cycle.len, cycle.len))? {
+                    next.push(congruence);
+                }
+            }
+        }
+        congruences = next;
+    }
 
-fn get_steps_to_finish(
-    start: &str,
-    is_end: &dyn Fn(&str) -> bool,
-    input_instructions: &Vec<Instruction>,
-    network: &HashMap<String, (String, String)>,
-) -> u64 {
-    let mut curr = start;
-    let mut result = 0u64;
-    let mut instructions: VecDeque<Instruction> = VecDeque::new();
-    instructions.extend(input_instructions.iter().copied());
-    while !is_end(curr) {
-        result += 1;
-        let op = instructions.pop_front().unwrap();
-
-        let choises = match network.get(curr) {
-            Some(value) => value,
-            None => break,
+    // the smallest step after all the cycles have started
+    let mut result: Option<u128> = None;
+    for (r, m) in congruences.iter() {
+        let step = if *r >= latest.start {
+            *r
+        } else {
+            (latest.start - r)
+                .div_ceil(*m)
+                .checked_mul(*m)
+                .and_then(|x| x.checked_add(*r))
+                .ok_or(Overflow)?
         };
-        curr = match op {
-            Instruction::Left => &choises.0,
-            Instruction::Right => &choises.1,
-        };
-
-        instructions.push_back(op);
+        result = Some(result.map_or(step, |min| min.min(step)));
     }
-    return result;
+    Ok(result)
 }
 
-fn gcd(a: u64, b: u64) -> u64 {
-    let mut a = a;
-    let mut b = b;
-    while b != 0 {
-        let temp = b;
-        b = a % b;
-        a = temp;
+// Solution of x = a1 (mod m1) and x = a2 (mod m2) as (remainder, lcm(m1, m2)),
+// `None` if there is none
+fn combine(
+    (a1, m1): (u128, u128),
+    (a2, m2): (u128, u128),
+) -> Result<Option<(u128, u128)>, Overflow> {
+    let signed = |x: u128| i128::try_from(x).map_err(|_| Overflow);
+    let (g, p, _) = extended_gcd(signed(m1)?, signed(m2)?);
+    let diff = signed(a2)? - signed(a1)?;
+    if diff % g != 0 {
+        return Ok(None);
     }
-    return a;
+    let m2_reduced = signed(m2)? / g;
+    // m1 * k = diff (mod m2), where p is the inverse of m1 / g modulo m2 / g
+    let k = (diff / g % m2_reduced)
+        .checked_mul(p)
+        .ok_or(Overflow)?
+        .rem_euclid(m2_reduced) as u128;
+    let modulus = m1.checked_mul(m2_reduced as u128).ok_or(Overflow)?;
+    let remainder = m1
+        .checked_mul(k)
+        .and_then(|x| x.checked_add(a1))
+        .ok_or(Overflow)?;
+    Ok(Some((remainder % modulus, modulus)))
 }
 
-fn lcm(a: u64, b: u64) -> u64 {
-    if a == 0 || b == 0 {
-        0
+// (gcd, x, y) such that a * x + b * y = gcd
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
     } else {
-        (a / gcd(a, b)) * b
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - a / b * y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let (instructions, network) = parse_input(include_str!("input_test.txt")).unwrap();
+        let starts = network.ids_matching(|name| name.ends_with('A'));
+        let steps = solve(&network, &instructions, &starts, |name| name.ends_with('Z'));
+        assert_eq!(steps, Ok(Some(6)));
+    }
+
+    fn solve_input(input: &str) -> Result<Option<u128>, Overflow> {
+        let (instructions, network) = parse_input(input).unwrap();
+        let starts = network.ids_matching(|name| name.ends_with('A'));
+        solve(&network, &instructions, &starts, |name| name.ends_with('Z'))
+    }
+
+    #[test]
+    fn meet_before_the_cycles() {
+        // both ghosts are on the end nodes only once, before they loop forever
+        let input = "L\n\n\
+            1AA = (1ZZ, 1ZZ)\n1ZZ = (1BB, 1BB)\n1BB = (1BB, 1BB)\n\
+            2AA = (2ZZ, 2ZZ)\n2ZZ = (2BB, 2BB)\n2BB = (2CC, 2CC)\n2CC = (2BB, 2BB)";
+        assert_eq!(solve_input(input), Ok(Some(1)));
+    }
+
+    #[test]
+    fn meet_in_the_cycles() {
+        // cycles of 4 and 6 steps, the ghosts are on the end nodes at 2 (mod 4) and 4 (mod 6)
+        let input = "L\n\n\
+            1AA = (1BB, 1BB)\n1BB = (1ZZ, 1ZZ)\n1ZZ = (1CC, 1CC)\n1CC = (1AA, 1AA)\n\
+            2AA = (2BB, 2BB)\n2BB = (2CC, 2CC)\n2CC = (2DD, 2DD)\n\
+            2DD = (2ZZ, 2ZZ)\n2ZZ = (2EE, 2EE)\n2EE = (2AA, 2AA)";
+        assert_eq!(solve_input(input), Ok(Some(10)));
+        // 1 (mod 4) and 2 (mod 6) are never at the same step
+        let input = "L\n\n\
+            1AA = (1ZZ, 1ZZ)\n1ZZ = (1BB, 1BB)\n1BB = (1CC, 1CC)\n1CC = (1AA, 1AA)\n\
+            2AA = (2BB, 2BB)\n2BB = (2ZZ, 2ZZ)\n2ZZ = (2CC, 2CC)\n\
+            2CC = (2DD, 2DD)\n2DD = (2EE, 2EE)\n2EE = (2AA, 2AA)";
+        assert_eq!(solve_input(input), Ok(None));
+    }
+
+    #[test]
+    fn combine_overflow() {
+        assert_eq!(combine((2, 4), (4, 6)), Ok(Some((10, 12))));
+        assert_eq!(combine((0, 1 << 100), (1, (1 << 100) + 1)), Err(Overflow));
+    }
+
+    #[test]
+    fn wrong_networks() {
+        assert!(matches!(
+            parse_input("L\n\nAAA = (BBB, AAA)\nAAA = (AAA, AAA)\nBBB = (AAA, AAA)"),
+            Err(ParseError::DuplicateNode(name)) if name == "AAA"
+        ));
+        assert!(matches!(
+            parse_input("L\n\nAAA = (BBB, AAA)"),
+            Err(ParseError::UndefinedNode(name)) if name == "BBB"
+        ));
+        assert!(matches!(
+            parse_input("LX\n\nAAA = (AAA, AAA)"),
+            Err(ParseError::WrongInstruction('X'))
+        ));
+        assert!(matches!(
+            parse_input("L\n\nAAA (AAA, AAA)"),
+            Err(ParseError::WrongFormat(_))
+        ));
     }
 }
@@ -1,72 +1,284 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::num::ParseIntError;
 
 fn main() -> io::Result<()> {
     // Specify the path to the input file
     let input_path = "day9/src/input.txt";
+    // number of values to extrapolate in both directions for every sequence
+    let to_print: Option<usize> = std::env::args().nth(1).and_then(|arg| arg.parse().ok());
 
     // Part1
     // open the file and parse input
-    let file = File::open(input_path)?;
-    let reader = BufReader::new(file);
-    let data: Vec<Vec<i32>> = reader
-        .lines()
-        .map(|l| {
-            let input = l.unwrap();
-            let seq: Vec<i32> = input
-                .split_whitespace()
-                .map(|x| i32::from_str_radix(x, 10).unwrap())
-                .collect();
-            return seq;
-        })
-        .collect();
-
-    let extrapolations: Vec<(i32, i32)> = data.iter().map(|seq| extrapolate(seq)).collect();
-    let sums = extrapolations
+    let input = fs::read_to_string(input_path)?;
+    let data =
+        parse_input(&input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    let tables: Vec<DifferenceTable<i64>> = data
         .iter()
-        .fold((0, 0), |(acc_first, acc_last), (x_first, x_last)| {
-            (acc_first + x_first, acc_last + x_last)
-        });
+        .map(|seq| DifferenceTable::build(seq))
+        .collect::<Result<_, _>>()
+        .map_err(to_io_error)?;
 
-    println!("Part1: {}", sums.1);
+    let part1_result = sum_extrapolated(&tables, DifferenceTable::forward).map_err(to_io_error)?;
+    println!("Part1: {}", part1_result);
 
     // Part2
-    println!("Part2: {}", sums.0);
+    let part2_result = sum_extrapolated(&tables, DifferenceTable::backward).map_err(to_io_error)?;
+    println!("Part2: {}", part2_result);
+
+    // larger steps are done in i128 to get further before overflowing
+    if let Some(count) = to_print {
+        for seq in data.iter() {
+            let seq: Vec<i128> = seq.iter().map(|x| *x as i128).collect();
+            let table = DifferenceTable::build(&seq).map_err(to_io_error)?;
+            let show = |values: Result<Vec<i128>, ExtrapolationError>| match values {
+                Ok(values) => format!("{values:?}"),
+                Err(err) => err.to_string(),
+            };
+            println!(
+                "degree {}: backward {}, forward {}",
+                table.degree(),
+                show(table.backward(count)),
+                show(table.forward(count))
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// One sequence of numbers per line
+pub(crate) fn parse_input(input: &str) -> Result<Vec<Vec<i64>>, ParseIntError> {
+    input
+        .lines()
+        .map(|line| line.split_whitespace().map(|x| x.parse()).collect())
+        .collect()
+}
+
+fn to_io_error(err: ExtrapolationError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+// Sum of the first extrapolated values of every sequence
+fn sum_extrapolated(
+    tables: &[DifferenceTable<i64>],
+    extrapolate: fn(&DifferenceTable<i64>, usize) -> Result<Vec<i64>, ExtrapolationError>,
+) -> Result<i64, ExtrapolationError> {
+    tables.iter().try_fold(0i64, |acc, table| {
+        acc.checked_add(extrapolate(table, 1)?[0])
+            .ok_or(ExtrapolationError::Overflow)
+    })
+}
+
+// Integer types extrapolation can work on, all the arithmetic is checked
+pub(crate) trait Number: Copy + PartialEq + fmt::Debug {
+    const ZERO: Self;
+    const ONE: Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_div(self, other: Self) -> Option<Self>;
+    fn checked_neg(self) -> Option<Self>;
+    fn from_usize(value: usize) -> Option<Self>;
+}
+
+macro_rules! impl_number {
+    ($($t:ty),*) => {$(
+        impl Number for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+            fn checked_sub(self, other: Self) -> Option<Self> {
+                <$t>::checked_sub(self, other)
+            }
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$t>::checked_mul(self, other)
+            }
+            fn checked_div(self, other: Self) -> Option<Self> {
+                <$t>::checked_div(self, other)
+            }
+            fn checked_neg(self) -> Option<Self> {
+                <$t>::checked_neg(self)
+            }
+            fn from_usize(value: usize) -> Option<Self> {
+                Self::try_from(value).ok()
+            }
+        }
+    )*};
+}
+
+impl_number!(i64, i128);
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ExtrapolationError {
+    // the differences never turned into all zeroes,
+    // so the sequence is too short to find its polynomial
+    NoPolynomial,
+    Overflow,
+}
+
+impl fmt::Display for ExtrapolationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExtrapolationError::NoPolynomial => write!(f, "differences never reach zeroes"),
+            ExtrapolationError::Overflow => write!(f, "overflow"),
+        }
+    }
+}
 
-    return Ok(());
+// The edges of the difference table of a sequence:
+// `first[j]` is the j-th forward difference at the first element
+// and `last[j]` is the j-th backward difference at the last one.
+// Only the rows above the first all-zero one are kept.
+pub(crate) struct DifferenceTable<T> {
+    first: Vec<T>,
+    last: Vec<T>,
 }
 
-fn extrapolate(seq: &Vec<i32>) -> (i32, i32) {
-    // build subsequences until we got all zeroes
-    let mut curr: Vec<i32> = seq.to_owned();
-    let mut last_elems: Vec<i32> = vec![];
-    let mut first_elems: Vec<i32> = vec![];
-    loop {
-        first_elems.push(*curr.first().unwrap());
-        last_elems.push(*curr.last().unwrap());
-        if is_all_zeroes(&curr) {
-            break;
+impl<T: Number> DifferenceTable<T> {
+    pub(crate) fn build(seq: &[T]) -> Result<DifferenceTable<T>, ExtrapolationError> {
+        let mut first: Vec<T> = vec![];
+        let mut last: Vec<T> = vec![];
+        let mut curr: Vec<T> = seq.to_vec();
+        while !curr.iter().all(|x| *x == T::ZERO) {
+            if curr.len() < 2 {
+                return Err(ExtrapolationError::NoPolynomial);
+            }
+            first.push(curr[0]);
+            last.push(curr[curr.len() - 1]);
+            curr = build_subsequence(&curr)?;
         }
+        if curr.is_empty() {
+            return Err(ExtrapolationError::NoPolynomial);
+        }
+        Ok(DifferenceTable { first, last })
+    }
+
+    // Degree of the polynomial generating the sequence (0 for the all-zero one)
+    fn degree(&self) -> usize {
+        self.first.len().saturating_sub(1)
+    }
 
-        curr = build_subsequence(&curr);
+    // Next `count` values after the end of the sequence.
+    // By Newton's formula the value `m` steps after the last one is
+    // the sum of C(m + j - 1, j) * last[j].
+    pub(crate) fn forward(&self, count: usize) -> Result<Vec<T>, ExtrapolationError> {
+        (1..=count)
+            .map(|m| newton_sum(&self.last, m, false))
+            .collect()
     }
 
-    // rollup subsequences to find the next value
-    first_elems.reverse();
-    last_elems.reverse();
-    let first = first_elems.iter().fold(0, |acc, x| x - acc);
-    let last = last_elems.iter().fold(0, |acc, x| x + acc);
-    return (first, last);
+    // `count` values before the start of the sequence, the nearest first.
+    // The value `m` steps before the first one is
+    // the sum of (-1)^j * C(m + j - 1, j) * first[j].
+    pub(crate) fn backward(&self, count: usize) -> Result<Vec<T>, ExtrapolationError> {
+        (1..=count)
+            .map(|m| newton_sum(&self.first, m, true))
+            .collect()
+    }
 }
 
-fn is_all_zeroes(seq: &Vec<i32>) -> bool {
-    seq.iter().all(|x| *x == 0)
+fn newton_sum<T: Number>(
+    differences: &[T],
+    m: usize,
+    alternate: bool,
+) -> Result<T, ExtrapolationError> {
+    let overflow = || ExtrapolationError::Overflow;
+    let mut result = T::ZERO;
+    // C(m + j - 1, j), starting from C(m - 1, 0)
+    let mut binomial = T::ONE;
+    for (j, diff) in differences.iter().enumerate() {
+        if j > 0 {
+            // C(m + j - 1, j) = C(m + j - 2, j - 1) * (m + j - 1) / j
+            let factor = T::from_usize(m + j - 1).ok_or_else(overflow)?;
+            binomial = binomial
+                .checked_mul(factor)
+                .and_then(|x| x.checked_div(T::from_usize(j)?))
+                .ok_or_else(overflow)?;
+        }
+        let mut term = binomial.checked_mul(*diff).ok_or_else(overflow)?;
+        if alternate && j % 2 == 1 {
+            term = term.checked_neg().ok_or_else(overflow)?;
+        }
+        result = result.checked_add(term).ok_or_else(overflow)?;
+    }
+    Ok(result)
 }
 
-fn build_subsequence(previous: &Vec<i32>) -> Vec<i32> {
-    let mut result: Vec<i32> = vec![];
-    for i in 0..(previous.len() - 1) {
-        result.push(previous[i + 1] - previous[i]);
+fn build_subsequence<T: Number>(previous: &[T]) -> Result<Vec<T>, ExtrapolationError> {
+    previous
+        .windows(2)
+        .map(|w| w[1].checked_sub(w[0]).ok_or(ExtrapolationError::Overflow))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tables(input: &str) -> Vec<DifferenceTable<i64>> {
+        parse_input(input)
+            .unwrap()
+            .iter()
+            .map(|seq| DifferenceTable::build(seq).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn example() {
+        let tables = tables(include_str!("input_test.txt"));
+        let degrees: Vec<usize> = tables.iter().map(|table| table.degree()).collect();
+        assert_eq!(degrees, vec![1, 2, 3]);
+        assert_eq!(sum_extrapolated(&tables, DifferenceTable::forward), Ok(114));
+        assert_eq!(sum_extrapolated(&tables, DifferenceTable::backward), Ok(2));
+    }
+
+    #[test]
+    fn several_steps() {
+        // the triangular numbers
+        let table = DifferenceTable::build(&[1i64, 3, 6, 10, 15, 21]).unwrap();
+        assert_eq!(table.forward(3), Ok(vec![28, 36, 45]));
+        assert_eq!(table.backward(3), Ok(vec![0, 0, 1]));
+        let table = DifferenceTable::build(&[0i64, 0, 0]).unwrap();
+        assert_eq!(table.degree(), 0);
+        assert_eq!(table.forward(2), Ok(vec![0, 0]));
+    }
+
+    #[test]
+    fn overflow() {
+        let seq = [i64::MAX - 2, i64::MAX - 1, i64::MAX];
+        let table = DifferenceTable::build(&seq).unwrap();
+        assert_eq!(table.forward(1), Err(ExtrapolationError::Overflow));
+        assert_eq!(table.backward(1), Ok(vec![i64::MAX - 3]));
+        // the same sequence goes further in i128
+        let seq: Vec<i128> = seq.iter().map(|x| *x as i128).collect();
+        let table = DifferenceTable::build(&seq).unwrap();
+        assert_eq!(table.forward(1), Ok(vec![i64::MAX as i128 + 1]));
+
+        assert!(matches!(
+            DifferenceTable::build(&[i64::MIN, i64::MAX]),
+            Err(ExtrapolationError::Overflow)
+        ));
+        let tables = tables("9223372036854775807 9223372036854775807\n1 1");
+        assert_eq!(
+            sum_extrapolated(&tables, DifferenceTable::backward),
+            Err(ExtrapolationError::Overflow)
+        );
+    }
+
+    #[test]
+    fn no_polynomial() {
+        assert!(matches!(
+            DifferenceTable::build(&[1i64, 2, 4]),
+            Err(ExtrapolationError::NoPolynomial)
+        ));
+        assert!(matches!(
+            DifferenceTable::<i64>::build(&[]),
+            Err(ExtrapolationError::NoPolynomial)
+        ));
     }
-    return result;
 }
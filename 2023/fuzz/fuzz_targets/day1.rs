@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day1/src/main.rs"]
+#[allow(dead_code)]
+mod day1;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let decoder = day1::CalibrationDecoder::english();
+    for line in input.lines() {
+        decoder.decode(line);
+    }
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use grid::Grid;
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day10/src/main.rs"]
+#[allow(dead_code)]
+mod day10;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(grid) = Grid::parse(data) else {
+        return;
+    };
+    if let Some(start) = day10::find_start(&grid) {
+        let _ = day10::find_main_loop(&grid, start);
+    }
+});
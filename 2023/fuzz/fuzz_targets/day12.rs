@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day12/src/main.rs"]
+#[allow(dead_code)]
+mod day12;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    for line in input.lines() {
+        let _ = day12::parse_row(line);
+    }
+});
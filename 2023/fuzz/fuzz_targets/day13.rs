@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day13/src/main.rs"]
+#[allow(dead_code)]
+mod day13;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    for pattern in input.split("\n\n") {
+        let _ = day13::Pattern::from_str(pattern.trim());
+    }
+});
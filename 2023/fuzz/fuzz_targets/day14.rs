@@ -0,0 +1,14 @@
+#![no_main]
+
+use grid::Grid;
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day14/src/main.rs"]
+#[allow(dead_code)]
+mod day14;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(grid) = Grid::parse(data) {
+        let _ = day14::Platform::from_grid(&grid);
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day15/src/main.rs"]
+#[allow(dead_code)]
+mod day15;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let steps: Vec<&str> = input.trim().split(',').collect();
+    for step in steps.iter() {
+        day15::calc_hash(step);
+    }
+    let _ = day15::parse_initialization_sequence(&steps);
+});
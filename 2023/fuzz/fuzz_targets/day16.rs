@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day16/src/main.rs"]
+#[allow(dead_code)]
+mod day16;
+
+fuzz_target!(|data: &[u8]| {
+    // every tile of a parsed contraption is known to the beams
+    if let Ok(grid) = day16::parse_contraption(data) {
+        let _ = day16::energize(&grid, (0, 0), day16::BeamDirection::FromLeft);
+    }
+});
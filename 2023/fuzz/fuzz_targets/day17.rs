@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day17/src/main.rs"]
+#[allow(dead_code)]
+mod day17;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = day17::parse_city(data);
+});
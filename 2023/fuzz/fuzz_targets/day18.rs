@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day18/src/main.rs"]
+#[allow(dead_code)]
+mod day18;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    for line in input.lines() {
+        let _ = day18::DigInstruction::from_str(line);
+    }
+});
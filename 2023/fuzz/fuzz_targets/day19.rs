@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day19/src/main.rs"]
+#[allow(dead_code)]
+mod day19;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    // unknown workflows and cycles are only found when compiling
+    if let Ok((workflows, _)) = day19::process_input(input) {
+        let _ = day19::DecisionTree::compile(&workflows);
+    }
+});
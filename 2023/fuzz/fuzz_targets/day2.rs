@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day2/src/main.rs"]
+#[allow(dead_code)]
+mod day2;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let bag = day2::Bag::default_bag();
+    for line in input.lines() {
+        let _ = day2::parse_game(line, &bag);
+    }
+    let _ = day2::Bag::from_str(input.trim(), None);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day22/src/main.rs"]
+#[allow(dead_code)]
+mod day22;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    for (id, line) in input.lines().enumerate() {
+        let _ = day22::Brick::from_str(line, id);
+    }
+});
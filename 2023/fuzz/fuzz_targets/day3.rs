@@ -0,0 +1,14 @@
+#![no_main]
+
+use grid::Grid;
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day3/src/main.rs"]
+#[allow(dead_code)]
+mod day3;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(grid) = Grid::parse(data) {
+        let _ = day3::Schematic::index(&grid);
+    }
+});
@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../day9/src/main.rs"]
+#[allow(dead_code)]
+mod day9;
+
+// Building a table is quadratic in the length of the sequence,
+// the puzzle ones have 21 numbers
+const MAX_SEQUENCE_LEN: usize = 100;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(sequences) = day9::parse_input(input) else {
+        return;
+    };
+    // the tables are built from the parsed numbers, and extrapolation checks every step
+    for seq in sequences.iter().filter(|seq| seq.len() <= MAX_SEQUENCE_LEN) {
+        if let Ok(table) = day9::DifferenceTable::build(seq) {
+            let _ = table.forward(1);
+            let _ = table.backward(1);
+        }
+    }
+});
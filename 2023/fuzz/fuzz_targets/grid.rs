@@ -0,0 +1,21 @@
+#![no_main]
+
+use grid::Grid;
+use libfuzzer_sys::fuzz_target;
+
+// Days 11, 21 and 23 only parse a grid, and every grid day reads its cells
+// through these accessors, so a wrong width or stride would panic here
+fuzz_target!(|data: &[u8]| {
+    let Ok(grid) = Grid::parse(data) else {
+        return;
+    };
+    for (i, row) in grid.rows().enumerate() {
+        assert_eq!(row.len(), grid.width());
+        for (j, cell) in row.iter().enumerate() {
+            assert_eq!(grid.get(i, j), Some(*cell));
+            assert_eq!(grid[(i, j)], *cell);
+        }
+    }
+    assert_eq!(grid.rows().count(), grid.height());
+    assert_eq!(grid.get(grid.height(), 0), None);
+});
@@ -0,0 +1,75 @@
+use crate::{Generated, Rng};
+
+// Number of lines
+pub const DEFAULT_SIZE: usize = 1000;
+
+const WORDS: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+// no digit word can be made of these letters
+const FILLER: &[u8] = b"abcdjklmpqyz";
+
+enum Token {
+    Digit(u64),
+    Word(u64),
+    Filler(String),
+}
+
+// Every line is a sequence of digits, digit words and filler, so the first and last
+// digits of both parts are known without reading the line back.
+// Words glued together can make more words in between (like "twone" in "twoone"),
+// but those never change the first or the last one.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut input = String::new();
+    let (mut part1, mut part2) = (0, 0);
+    for _ in 0..size {
+        let mut tokens: Vec<Token> = vec![];
+        // every line has at least one digit for part 1
+        tokens.push(Token::Digit(rng.range(1, 9) as u64));
+        for _ in 0..rng.range(0, 6) {
+            tokens.push(match rng.below(3) {
+                0 => Token::Digit(rng.range(1, 9) as u64),
+                1 => Token::Word(rng.range(1, 9) as u64),
+                _ => Token::Filler(filler(rng)),
+            });
+        }
+        rng.shuffle(&mut tokens);
+
+        let digits: Vec<u64> = tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Digit(value) => Some(*value),
+                _ => None,
+            })
+            .collect();
+        let values: Vec<u64> = tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Digit(value) | Token::Word(value) => Some(*value),
+                Token::Filler(_) => None,
+            })
+            .collect();
+        part1 += digits[0] * 10 + digits[digits.len() - 1];
+        part2 += values[0] * 10 + values[values.len() - 1];
+
+        for token in tokens.iter() {
+            match token {
+                Token::Digit(value) => input.push_str(&value.to_string()),
+                Token::Word(value) => input.push_str(WORDS[*value as usize - 1]),
+                Token::Filler(text) => input.push_str(text),
+            }
+        }
+        input.push('\n');
+    }
+
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
+
+fn filler(rng: &mut Rng) -> String {
+    (0..rng.range(1, 5))
+        .map(|_| *rng.choose(FILLER) as char)
+        .collect()
+}
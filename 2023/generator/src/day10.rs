@@ -0,0 +1,74 @@
+use crate::{outline, random_blob, Generated, Rng};
+
+// Width and height of the maze
+pub const DEFAULT_SIZE: usize = 140;
+
+const JUNK: &[u8] = b"|-LJ7F..";
+
+// The loop is the outline of a random blob of cells of a coarse grid, where every
+// cell is 2x2 tiles. The corners of the outline are on even tiles, which gives
+// the length of the loop directly and the enclosed tiles by Pick's theorem:
+// area = inside + boundary / 2 - 1, where the area is 4 tiles per cell.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let cells = (size.max(5) - 1) / 2;
+    let size = 2 * cells + 1;
+    let blob = random_blob(rng, cells, 45);
+    let cell_count = blob.iter().flatten().filter(|x| **x).count();
+
+    let corners = outline(&blob);
+    // the loop goes through the corners and the tiles halfway between them
+    let mut tiles: Vec<(i64, i64)> = vec![];
+    for idx in 0..corners.len() {
+        let (a, b) = corners[idx];
+        let (next_a, next_b) = corners[(idx + 1) % corners.len()];
+        tiles.push((2 * a, 2 * b));
+        tiles.push((a + next_a, b + next_b));
+    }
+
+    let mut rows: Vec<Vec<u8>> = (0..size)
+        .map(|_| (0..size).map(|_| *rng.choose(JUNK)).collect())
+        .collect();
+    for idx in 0..tiles.len() {
+        let (i, j) = tiles[idx];
+        let prev = tiles[(idx + tiles.len() - 1) % tiles.len()];
+        let next = tiles[(idx + 1) % tiles.len()];
+        let mut exits = [(prev.0 - i, prev.1 - j), (next.0 - i, next.1 - j)];
+        exits.sort();
+        rows[i as usize][j as usize] = match exits {
+            [(-1, 0), (1, 0)] => b'|',
+            [(0, -1), (0, 1)] => b'-',
+            [(-1, 0), (0, 1)] => b'L',
+            [(-1, 0), (0, -1)] => b'J',
+            [(0, -1), (1, 0)] => b'7',
+            [(0, 1), (1, 0)] => b'F',
+            _ => unreachable!("The loop moves one tile at a time"),
+        };
+    }
+    // only the two loop tiles next to the start lead into it
+    let (i, j) = *rng.choose(&tiles);
+    for (di, dj) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
+        let (ni, nj) = (i + di, j + dj);
+        if (0..size as i64).contains(&ni)
+            && (0..size as i64).contains(&nj)
+            && !tiles.contains(&(ni, nj))
+        {
+            rows[ni as usize][nj as usize] = b'.';
+        }
+    }
+    rows[i as usize][j as usize] = b'S';
+
+    let mut input = String::new();
+    for row in rows.iter() {
+        input.push_str(std::str::from_utf8(row).unwrap());
+        input.push('\n');
+    }
+    let area = 4 * cell_count as i64;
+    let boundary = tiles.len() as i64;
+    Generated {
+        input,
+        properties: vec![
+            ("Part1", (boundary / 2).to_string()),
+            ("Part2", (area - boundary / 2 + 1).to_string()),
+        ],
+    }
+}
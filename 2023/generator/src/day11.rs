@@ -0,0 +1,58 @@
+use crate::{Generated, Rng};
+
+// Width and height of the image
+pub const DEFAULT_SIZE: usize = 140;
+
+// Random galaxies, with about a tenth of the rows and columns left empty.
+// The distances are counted pair by pair, going over the rows and columns between them.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let empty_rows: Vec<bool> = (0..size).map(|_| rng.chance(10)).collect();
+    let empty_cols: Vec<bool> = (0..size).map(|_| rng.chance(10)).collect();
+    let mut rows: Vec<Vec<u8>> = vec![vec![b'.'; size]; size];
+    let mut galaxies: Vec<(usize, usize)> = vec![];
+    for (i, row) in rows.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            if !empty_rows[i] && !empty_cols[j] && rng.chance(2) {
+                *cell = b'#';
+                galaxies.push((i, j));
+            }
+        }
+    }
+    // a row or column that got no galaxy by chance is empty too
+    let empty_rows: Vec<bool> = (0..size)
+        .map(|i| !galaxies.iter().any(|g| g.0 == i))
+        .collect();
+    let empty_cols: Vec<bool> = (0..size)
+        .map(|j| !galaxies.iter().any(|g| g.1 == j))
+        .collect();
+
+    let sum_of_distances = |factor: u64| -> u64 {
+        let mut result = 0;
+        for (idx, (i1, j1)) in galaxies.iter().enumerate() {
+            for (i2, j2) in galaxies[idx + 1..].iter() {
+                let rows = *i1.min(i2)..*i1.max(i2);
+                let cols = *j1.min(j2)..*j1.max(j2);
+                result += rows
+                    .map(|i| if empty_rows[i] { factor } else { 1 })
+                    .sum::<u64>();
+                result += cols
+                    .map(|j| if empty_cols[j] { factor } else { 1 })
+                    .sum::<u64>();
+            }
+        }
+        result
+    };
+
+    let mut input = String::new();
+    for row in rows.iter() {
+        input.push_str(std::str::from_utf8(row).unwrap());
+        input.push('\n');
+    }
+    Generated {
+        input,
+        properties: vec![
+            ("Part1", sum_of_distances(2).to_string()),
+            ("Part2", sum_of_distances(1_000_000).to_string()),
+        ],
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{Generated, Rng};
+use std::collections::HashMap;
+
+// Number of rows
+pub const DEFAULT_SIZE: usize = 1000;
+
+const MAX_UNKNOWN: usize = 12;
+const UNFOLD_FACTOR: usize = 5;
+
+// Every row is a random arrangement with some of the springs hidden behind `?`,
+// so it has at least one arrangement. There are at most 12 unknown springs in a row,
+// and part 1 counts the arrangements by trying all of them. Part 2 has too many
+// of them, it runs the unfolded row through a state machine instead.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut input = String::new();
+    let mut part1 = 0;
+    let mut part2: u128 = 0;
+    for _ in 0..size {
+        let len = rng.range(3, 20) as usize;
+        let mut springs: Vec<u8> = (0..len)
+            .map(|_| if rng.chance(50) { b'#' } else { b'.' })
+            .collect();
+        let at = rng.index(len);
+        springs[at] = b'#';
+        let groups = broken_groups(&springs);
+
+        let mut unknown: Vec<usize> = (0..len).filter(|_| rng.chance(50)).collect();
+        rng.shuffle(&mut unknown);
+        unknown.truncate(MAX_UNKNOWN);
+        for idx in unknown.iter() {
+            springs[*idx] = b'?';
+        }
+
+        let mut candidate = springs.clone();
+        for mask in 0..1u32 << unknown.len() {
+            for (bit, idx) in unknown.iter().enumerate() {
+                candidate[*idx] = if mask >> bit & 1 == 1 { b'#' } else { b'.' };
+            }
+            if broken_groups(&candidate) == groups {
+                part1 += 1;
+            }
+        }
+
+        let unfolded_springs = [springs.as_slice(); UNFOLD_FACTOR].join(&b'?');
+        part2 += count_by_states(&unfolded_springs, &groups.repeat(UNFOLD_FACTOR));
+
+        let groups: Vec<String> = groups.iter().map(|x| x.to_string()).collect();
+        input.push_str(&format!(
+            "{} {}\n",
+            String::from_utf8_lossy(&springs),
+            groups.join(",")
+        ));
+    }
+
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
+
+fn broken_groups(springs: &[u8]) -> Vec<usize> {
+    springs
+        .split(|x| *x != b'#')
+        .filter(|group| !group.is_empty())
+        .map(|group| group.len())
+        .collect()
+}
+
+// The state after some springs is the number of finished groups of broken springs
+// and the length of the current one. A working spring after the row closes
+// the last group.
+fn count_by_states(springs: &[u8], groups: &[usize]) -> u128 {
+    let mut states: HashMap<(usize, usize), u128> = HashMap::from([((0, 0), 1)]);
+    for spring in springs.iter().chain(std::iter::once(&b'.')) {
+        let mut next: HashMap<(usize, usize), u128> = HashMap::new();
+        for ((done, run), count) in states {
+            if *spring != b'.' && done < groups.len() && run < groups[done] {
+                *next.entry((done, run + 1)).or_insert(0) += count;
+            }
+            if *spring != b'#' {
+                if run == 0 {
+                    *next.entry((done, 0)).or_insert(0) += count;
+                } else if run == groups[done] {
+                    *next.entry((done + 1, 0)).or_insert(0) += count;
+                }
+            }
+        }
+        states = next;
+    }
+    states.get(&(groups.len(), 0)).copied().unwrap_or(0)
+}
@@ -0,0 +1,111 @@
+use crate::{Generated, Rng};
+
+// Number of patterns
+pub const DEFAULT_SIZE: usize = 100;
+
+// Every pattern is made symmetric around a line between rows and a line between columns.
+// Then one cell outside of the rows mirrored by the first line is flipped, so the first
+// line stays perfect and the second one needs exactly that smudge fixed. Patterns where
+// some other line happens to work too are thrown away. Half of them are transposed,
+// so both kinds of lines show up in both parts.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut input = String::new();
+    let (mut part1, mut part2) = (0, 0);
+    let mut count = 0;
+    while count < size {
+        let (height, width) = (rng.range(5, 17) as usize, rng.range(5, 17) as usize);
+        let mirror_row = rng.range(1, height as i64 - 1) as usize;
+        let mirror_col = rng.range(1, width as i64 - 1) as usize;
+        // rows that the row line doesn't mirror
+        let free_rows: Vec<usize> = (0..height)
+            .filter(|i| *i >= 2 * mirror_row || 2 * mirror_row > height + i)
+            .collect();
+        if free_rows.is_empty() {
+            continue;
+        }
+
+        let mut grid: Vec<Vec<u8>> = (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|_| if rng.chance(50) { b'#' } else { b'.' })
+                    .collect()
+            })
+            .collect();
+        for row in grid.iter_mut() {
+            for j in 0..mirror_col {
+                if 2 * mirror_col - 1 - j < width {
+                    row[2 * mirror_col - 1 - j] = row[j];
+                }
+            }
+        }
+        for i in 0..mirror_row {
+            if 2 * mirror_row - 1 - i < height {
+                grid[2 * mirror_row - 1 - i] = grid[i].clone();
+            }
+        }
+        let i = *rng.choose(&free_rows);
+        let j = rng.range(
+            (2 * mirror_col).saturating_sub(width) as i64,
+            mirror_col as i64 - 1,
+        ) as usize;
+        grid[i][j] = if grid[i][j] == b'#' { b'.' } else { b'#' };
+
+        let transposed = rng.chance(50);
+        if transposed {
+            grid = (0..width)
+                .map(|j| (0..height).map(|i| grid[i][j]).collect())
+                .collect();
+        }
+        // (summary of the line, differences) of all the lines
+        let lines: Vec<(usize, usize)> = line_differences(&grid);
+        let perfect: Vec<usize> = lines.iter().filter(|x| x.1 == 0).map(|x| x.0).collect();
+        let smudged: Vec<usize> = lines.iter().filter(|x| x.1 == 1).map(|x| x.0).collect();
+        let (expected_perfect, expected_smudged) = if transposed {
+            (mirror_row, 100 * mirror_col)
+        } else {
+            (100 * mirror_row, mirror_col)
+        };
+        if perfect != [expected_perfect] || smudged != [expected_smudged] {
+            continue;
+        }
+
+        part1 += expected_perfect;
+        part2 += expected_smudged;
+        count += 1;
+        if !input.is_empty() {
+            input.push('\n');
+        }
+        for row in grid.iter() {
+            input.push_str(std::str::from_utf8(row).unwrap());
+            input.push('\n');
+        }
+    }
+
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
+
+// Cells that differ across every line, compared one by one
+fn line_differences(grid: &[Vec<u8>]) -> Vec<(usize, usize)> {
+    let (height, width) = (grid.len(), grid[0].len());
+    let mut result: Vec<(usize, usize)> = vec![];
+    for line in 1..height {
+        let differences = (0..line)
+            .rev()
+            .zip(line..height)
+            .map(|(a, b)| (0..width).filter(|j| grid[a][*j] != grid[b][*j]).count())
+            .sum();
+        result.push((100 * line, differences));
+    }
+    for line in 1..width {
+        let differences = (0..line)
+            .rev()
+            .zip(line..width)
+            .map(|(a, b)| (0..height).filter(|i| grid[*i][a] != grid[*i][b]).count())
+            .sum();
+        result.push((line, differences));
+    }
+    result
+}
@@ -0,0 +1,85 @@
+use crate::{Generated, Rng};
+use std::collections::HashMap;
+
+// Width and height of the platform
+pub const DEFAULT_SIZE: usize = 100;
+
+const CYCLES: usize = 1_000_000_000;
+
+// Random rocks. The answers move the rocks one cell at a time,
+// and part 2 remembers every state after a spin cycle to skip the repeating ones.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut grid: Vec<Vec<u8>> = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| match rng.below(100) {
+                    0..=19 => b'O',
+                    20..=35 => b'#',
+                    _ => b'.',
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut input = String::new();
+    for row in grid.iter() {
+        input.push_str(std::str::from_utf8(row).unwrap());
+        input.push('\n');
+    }
+
+    let mut north = grid.clone();
+    roll(&mut north, -1, 0);
+    let part1 = load(&north);
+
+    let mut seen: HashMap<Vec<Vec<u8>>, usize> = HashMap::new();
+    let mut cycle = 0;
+    while cycle < CYCLES {
+        if let Some(start) = seen.insert(grid.clone(), cycle) {
+            let len = cycle - start;
+            cycle += (CYCLES - cycle) / len * len;
+            seen.clear();
+            if cycle == CYCLES {
+                break;
+            }
+        }
+        for (di, dj) in [(-1, 0), (0, -1), (1, 0), (0, 1)] {
+            roll(&mut grid, di, dj);
+        }
+        cycle += 1;
+    }
+    let part2 = load(&grid);
+
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
+
+// Every rock slides cell by cell, the ones closer to the edge it rolls to go first
+fn roll(grid: &mut [Vec<u8>], di: i64, dj: i64) {
+    let size = grid.len() as i64;
+    let mut order: Vec<(i64, i64)> = (0..size)
+        .flat_map(|i| (0..size).map(move |j| (i, j)))
+        .collect();
+    order.sort_by_key(|(i, j)| -(i * di + j * dj));
+    for (mut i, mut j) in order {
+        if grid[i as usize][j as usize] != b'O' {
+            continue;
+        }
+        while (0..size).contains(&(i + di))
+            && (0..size).contains(&(j + dj))
+            && grid[(i + di) as usize][(j + dj) as usize] == b'.'
+        {
+            grid[i as usize][j as usize] = b'.';
+            (i, j) = (i + di, j + dj);
+            grid[i as usize][j as usize] = b'O';
+        }
+    }
+}
+
+fn load(grid: &[Vec<u8>]) -> usize {
+    grid.iter()
+        .enumerate()
+        .map(|(i, row)| (grid.len() - i) * row.iter().filter(|x| **x == b'O').count())
+        .sum()
+}
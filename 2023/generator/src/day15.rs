@@ -0,0 +1,58 @@
+use crate::{unique_names, Generated, Rng};
+
+// Number of steps
+pub const DEFAULT_SIZE: usize = 4000;
+
+// Random steps over a pool of labels, so that lenses get replaced and removed.
+// The answers put the lenses into plain vectors of boxes.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let labels: Vec<String> = (1..=6)
+        .flat_map(|len| {
+            let count = (size / 20 + 1).min(26usize.pow(len as u32) / 2);
+            unique_names(rng, count, len, b"abcdefghijklmnopqrstuvwxyz")
+        })
+        .collect();
+    let mut steps: Vec<String> = vec![];
+    let mut boxes: Vec<Vec<(String, u64)>> = vec![vec![]; 256];
+    for _ in 0..size {
+        let label = rng.choose(&labels).clone();
+        let lenses = &mut boxes[hash(&label) as usize];
+        let position = lenses.iter().position(|(l, _)| *l == label);
+        if rng.chance(30) {
+            if let Some(idx) = position {
+                lenses.remove(idx);
+            }
+            steps.push(format!("{label}-"));
+        } else {
+            let focal_length = rng.range(1, 9) as u64;
+            match position {
+                Some(idx) => lenses[idx].1 = focal_length,
+                None => lenses.push((label.clone(), focal_length)),
+            }
+            steps.push(format!("{label}={focal_length}"));
+        }
+    }
+
+    let part1: u64 = steps.iter().map(|step| hash(step)).sum();
+    let part2: u64 = boxes
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, lenses)| {
+            lenses
+                .iter()
+                .enumerate()
+                .map(move |(slot, (_, focal_length))| {
+                    (idx as u64 + 1) * (slot as u64 + 1) * focal_length
+                })
+        })
+        .sum();
+
+    Generated {
+        input: steps.join(",") + "\n",
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
+
+fn hash(text: &str) -> u64 {
+    text.bytes().fold(0, |acc, ch| (acc + ch as u64) * 17 % 256)
+}
@@ -0,0 +1,72 @@
+use crate::{Generated, Rng};
+use std::collections::HashSet;
+
+// Width and height of the contraption
+pub const DEFAULT_SIZE: usize = 110;
+
+// Random mirrors and splitters on an empty floor. The answers follow the beams
+// one (tile, direction) state at a time, from every entry point for part 2.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let grid: Vec<Vec<u8>> = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| match rng.below(100) {
+                    0..=2 => b'/',
+                    3..=5 => b'\\',
+                    6..=8 => b'|',
+                    9..=11 => b'-',
+                    _ => b'.',
+                })
+                .collect()
+        })
+        .collect();
+
+    let size = size as i64;
+    let part1 = energized(&grid, (0, 0, 0, 1));
+    let mut entries: Vec<(i64, i64, i64, i64)> = vec![];
+    for k in 0..size {
+        entries.push((k, 0, 0, 1));
+        entries.push((k, size - 1, 0, -1));
+        entries.push((0, k, 1, 0));
+        entries.push((size - 1, k, -1, 0));
+    }
+    let part2 = entries
+        .iter()
+        .map(|entry| energized(&grid, *entry))
+        .max()
+        .unwrap_or(0);
+
+    let mut input = String::new();
+    for row in grid.iter() {
+        input.push_str(std::str::from_utf8(row).unwrap());
+        input.push('\n');
+    }
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
+
+// Tiles visited by the beam entering at (i, j) going in (di, dj)
+fn energized(grid: &[Vec<u8>], entry: (i64, i64, i64, i64)) -> usize {
+    let size = grid.len() as i64;
+    let mut seen: HashSet<(i64, i64, i64, i64)> = HashSet::new();
+    let mut beams = vec![entry];
+    while let Some((i, j, di, dj)) = beams.pop() {
+        if !(0..size).contains(&i) || !(0..size).contains(&j) || !seen.insert((i, j, di, dj)) {
+            continue;
+        }
+        let directions = match (grid[i as usize][j as usize], di) {
+            (b'/', _) => vec![(-dj, -di)],
+            (b'\\', _) => vec![(dj, di)],
+            (b'|', 0) => vec![(-1, 0), (1, 0)],
+            (b'-', 1 | -1) => vec![(0, -1), (0, 1)],
+            _ => vec![(di, dj)],
+        };
+        for (di, dj) in directions {
+            beams.push((i + di, j + dj, di, dj));
+        }
+    }
+    let tiles: HashSet<(i64, i64)> = seen.iter().map(|(i, j, _, _)| (*i, *j)).collect();
+    tiles.len()
+}
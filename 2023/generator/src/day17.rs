@@ -0,0 +1,42 @@
+use crate::{Generated, Rng};
+
+// Width and height of the map, rounded to 4k + 1
+pub const DEFAULT_SIZE: usize = 141;
+
+// Random heat losses from 1 to 9, except for two staircases of 1s from the top left
+// corner to the bottom right one: one turning after every block for the normal
+// crucible and one turning after every 4 blocks for the ultra crucible.
+// No path is shorter than 2 * (size - 1) blocks and every block loses at least 1,
+// so both staircases are the best paths and both answers are 2 * (size - 1).
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let size = size.max(5) / 4 * 4 + 1;
+    let mut grid: Vec<Vec<u8>> = (0..size)
+        .map(|_| (0..size).map(|_| b'0' + rng.range(1, 9) as u8).collect())
+        .collect();
+    for run in [1, 4] {
+        let (mut i, mut j) = (0, 0);
+        let mut down = rng.chance(50);
+        while (i, j) != (size - 1, size - 1) {
+            for _ in 0..run {
+                if down {
+                    i += 1;
+                } else {
+                    j += 1;
+                }
+                grid[i][j] = b'1';
+            }
+            down = !down;
+        }
+    }
+
+    let mut input = String::new();
+    for row in grid.iter() {
+        input.push_str(std::str::from_utf8(row).unwrap());
+        input.push('\n');
+    }
+    let best = 2 * (size - 1);
+    Generated {
+        input,
+        properties: vec![("Part1", best.to_string()), ("Part2", best.to_string())],
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{outline, random_blob, Generated, Rng};
+
+// Width and height of the grid the lagoon shape is drawn on
+pub const DEFAULT_SIZE: usize = 40;
+
+// The lagoon is a random blob of cells where every row and column of the grid gets
+// a random size, small ones for the plain instructions and large ones for the colors.
+// Both plans dig the same shape, so they have the same number of instructions.
+// The area is the sum of the cells, and the trench adds half of its length plus one
+// (the outer half of every trench block, and the four extra quarters at the corners).
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let cells = size.max(1);
+    let blob = random_blob(rng, cells, 50);
+    let corners = outline(&blob);
+    // only the corners where the trench turns
+    let turns: Vec<(i64, i64)> = (0..corners.len())
+        .filter(|idx| {
+            let prev = corners[(idx + corners.len() - 1) % corners.len()];
+            let next = corners[(idx + 1) % corners.len()];
+            let curr = corners[*idx];
+            (curr.0 - prev.0, curr.1 - prev.1) != (next.0 - curr.0, next.1 - curr.1)
+        })
+        .map(|idx| corners[idx])
+        .collect();
+
+    let plain = Plan::random(rng, cells, 12);
+    let decoded = Plan::random(rng, cells, 20_000);
+
+    let mut input = String::new();
+    for idx in 0..turns.len() {
+        let (from, to) = (turns[idx], turns[(idx + 1) % turns.len()]);
+        let (direction, code) = match ((to.0 - from.0).signum(), (to.1 - from.1).signum()) {
+            (0, 1) => ('R', 0),
+            (1, 0) => ('D', 1),
+            (0, -1) => ('L', 2),
+            _ => ('U', 3),
+        };
+        input.push_str(&format!(
+            "{direction} {} (#{:05x}{code})\n",
+            plain.distance(from, to),
+            decoded.distance(from, to)
+        ));
+    }
+
+    Generated {
+        input,
+        properties: vec![
+            ("Part1", plain.volume(&blob, &turns).to_string()),
+            ("Part2", decoded.volume(&blob, &turns).to_string()),
+        ],
+    }
+}
+
+// Where every line of the grid ends up after the rows and columns got their sizes
+struct Plan {
+    rows: Vec<i64>,
+    cols: Vec<i64>,
+}
+
+impl Plan {
+    fn random(rng: &mut Rng, cells: usize, max_size: i64) -> Plan {
+        let mut lines = || -> Vec<i64> {
+            let mut result = vec![0];
+            for _ in 0..cells {
+                result.push(result[result.len() - 1] + rng.range(1, max_size));
+            }
+            result
+        };
+        Plan {
+            rows: lines(),
+            cols: lines(),
+        }
+    }
+
+    fn distance(&self, from: (i64, i64), to: (i64, i64)) -> i64 {
+        let row = |a: i64| self.rows[a as usize];
+        let col = |b: i64| self.cols[b as usize];
+        (row(to.0) - row(from.0)).abs() + (col(to.1) - col(from.1)).abs()
+    }
+
+    fn volume(&self, blob: &[Vec<bool>], turns: &[(i64, i64)]) -> i64 {
+        let mut area = 0;
+        for (a, row) in blob.iter().enumerate() {
+            for (b, is_in) in row.iter().enumerate() {
+                if *is_in {
+                    area += (self.rows[a + 1] - self.rows[a]) * (self.cols[b + 1] - self.cols[b]);
+                }
+            }
+        }
+        let trench: i64 = (0..turns.len())
+            .map(|idx| self.distance(turns[idx], turns[(idx + 1) % turns.len()]))
+            .sum();
+        area + trench / 2 + 1
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{Generated, Rng};
+use std::collections::{HashMap, HashSet};
+
+// Number of workflows
+pub const DEFAULT_SIZE: usize = 500;
+
+const CATEGORIES: [char; 4] = ['x', 'm', 'a', 's'];
+const PARTS: usize = 200;
+
+// (condition as (category, comparison, value), destination)
+type Rule = (Option<(char, char, u64)>, String);
+// Ranges of the ratings, starts included, ends excluded
+type PartBox = [(u64, u64); 4];
+
+// The workflows make a tree, built from `in` down: every rule splits off a part of
+// the box of ratings that reach the workflow, and sends it to A, to R or to a new
+// workflow that gets that part as its box. The boxes of A are counted while building,
+// which gives part 2. Part 1 runs random parts through the workflows.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut builder = Builder {
+        workflows: vec![],
+        names: HashSet::from(["in".to_owned()]),
+        left: size.max(1),
+        accepted: 0,
+    };
+    builder.workflow(rng, "in".to_owned(), [(1, 4001); 4]);
+    let mut workflows = builder.workflows;
+    rng.shuffle(&mut workflows);
+
+    let by_name: HashMap<&str, &Vec<Rule>> = workflows
+        .iter()
+        .map(|(name, rules)| (name.as_str(), rules))
+        .collect();
+    let mut parts: Vec<[u64; 4]> = vec![];
+    let mut part1 = 0;
+    for _ in 0..PARTS {
+        let part = [0; 4].map(|_| rng.range(1, 4000) as u64);
+        let mut curr = "in";
+        while curr != "A" && curr != "R" {
+            curr = by_name[curr]
+                .iter()
+                .find(|(condition, _)| match condition {
+                    Some((category, '<', value)) => part[index(*category)] < *value,
+                    Some((category, _, value)) => part[index(*category)] > *value,
+                    None => true,
+                })
+                .map(|(_, destination)| destination.as_str())
+                .unwrap();
+        }
+        if curr == "A" {
+            part1 += part.iter().sum::<u64>();
+        }
+        parts.push(part);
+    }
+
+    let mut input = String::new();
+    for (name, rules) in workflows.iter() {
+        let rules: Vec<String> = rules
+            .iter()
+            .map(|(condition, destination)| match condition {
+                Some((category, comparison, value)) => {
+                    format!("{category}{comparison}{value}:{destination}")
+                }
+                None => destination.clone(),
+            })
+            .collect();
+        input.push_str(&format!("{name}{{{}}}\n", rules.join(",")));
+    }
+    input.push('\n');
+    for [x, m, a, s] in parts.iter() {
+        input.push_str(&format!("{{x={x},m={m},a={a},s={s}}}\n"));
+    }
+    Generated {
+        input,
+        properties: vec![
+            ("Part1", part1.to_string()),
+            ("Part2", builder.accepted.to_string()),
+        ],
+    }
+}
+
+struct Builder {
+    workflows: Vec<(String, Vec<Rule>)>,
+    names: HashSet<String>,
+    // workflows that can still be made
+    left: usize,
+    accepted: u64,
+}
+
+impl Builder {
+    fn workflow(&mut self, rng: &mut Rng, name: String, part_box: PartBox) {
+        self.left -= 1;
+        let idx = self.workflows.len();
+        self.workflows.push((name, vec![]));
+
+        let mut rest = part_box;
+        let mut rules: Vec<Rule> = vec![];
+        for _ in 0..rng.range(1, 3) {
+            let splittable: Vec<usize> = (0..4).filter(|c| rest[*c].1 - rest[*c].0 >= 2).collect();
+            if splittable.is_empty() {
+                break;
+            }
+            let category = *rng.choose(&splittable);
+            let (start, end) = rest[category];
+            let mut matching = rest;
+            let condition = if rng.chance(50) {
+                // the value is in start + 1..end, so both sides are not empty
+                let value = rng.range(start as i64 + 1, end as i64 - 1) as u64;
+                matching[category] = (start, value);
+                rest[category] = (value, end);
+                (CATEGORIES[category], '<', value)
+            } else {
+                let value = rng.range(start as i64, end as i64 - 2) as u64;
+                matching[category] = (value + 1, end);
+                rest[category] = (start, value + 1);
+                (CATEGORIES[category], '>', value)
+            };
+            rules.push((Some(condition), self.destination(rng, matching)));
+        }
+        rules.push((None, self.destination(rng, rest)));
+        self.workflows[idx].1 = rules;
+    }
+
+    fn destination(&mut self, rng: &mut Rng, part_box: PartBox) -> String {
+        if self.left > 0 && rng.chance(60) {
+            let name = loop {
+                let len = rng.range(2, 3) as usize;
+                let name: String = (0..len)
+                    .map(|_| (b'a' + rng.below(26) as u8) as char)
+                    .collect();
+                if self.names.insert(name.clone()) {
+                    break name;
+                }
+            };
+            self.workflow(rng, name.clone(), part_box);
+            name
+        } else if rng.chance(50) {
+            self.accepted += part_box
+                .iter()
+                .map(|(start, end)| end - start)
+                .product::<u64>();
+            "A".to_owned()
+        } else {
+            "R".to_owned()
+        }
+    }
+}
+
+fn index(category: char) -> usize {
+    CATEGORIES.iter().position(|c| *c == category).unwrap()
+}
@@ -0,0 +1,62 @@
+use crate::{Generated, Rng};
+
+// Number of games
+pub const DEFAULT_SIZE: usize = 100;
+
+const COLORS: [&str; 3] = ["red", "green", "blue"];
+const BAG: [u64; 3] = [12, 13, 14];
+
+// The largest count of every color is picked first and shown in one of the sets,
+// the other sets never go over it, so the minimal bag of each game is known.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut input = String::new();
+    let (mut part1, mut part2) = (0, 0);
+    for id in 1..=size {
+        let maxima: [u64; 3] = [0, 1, 2].map(|_| rng.range(1, 20) as u64);
+        if maxima.iter().zip(BAG.iter()).all(|(max, bag)| max <= bag) {
+            part1 += id;
+        }
+        part2 += maxima.iter().product::<u64>();
+
+        let sets_count = rng.range(1, 6) as usize;
+        // sets[k][color], a color with zero cubes is not shown
+        let mut sets: Vec<[u64; 3]> = (0..sets_count)
+            .map(|_| {
+                maxima.map(|max| {
+                    if rng.chance(30) {
+                        0
+                    } else {
+                        rng.range(1, max as i64) as u64
+                    }
+                })
+            })
+            .collect();
+        for (color, max) in maxima.iter().enumerate() {
+            let set = rng.index(sets_count);
+            sets[set][color] = *max;
+        }
+        // a set shows at least one cube
+        for set in sets.iter_mut().filter(|set| set.iter().all(|x| *x == 0)) {
+            let color = rng.index(3);
+            set[color] = rng.range(1, maxima[color] as i64) as u64;
+        }
+
+        let sets: Vec<String> = sets
+            .iter()
+            .map(|set| {
+                let mut cubes: Vec<String> = (0..3)
+                    .filter(|color| set[*color] > 0)
+                    .map(|color| format!("{} {}", set[color], COLORS[color]))
+                    .collect();
+                rng.shuffle(&mut cubes);
+                cubes.join(", ")
+            })
+            .collect();
+        input.push_str(&format!("Game {id}: {}\n", sets.join("; ")));
+    }
+
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
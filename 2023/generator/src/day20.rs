@@ -0,0 +1,144 @@
+use crate::{lcm, unique_names, Generated, Rng};
+use std::collections::{HashMap, VecDeque};
+
+// Number of counters feeding rx
+pub const DEFAULT_SIZE: usize = 4;
+
+const BITS: usize = 12;
+
+// The network of the puzzle: the broadcaster starts a few 12-bit binary counters made
+// of flip-flops. Every counter has a hub conjunction that listens to the flip-flops of
+// the set bits of its period and drives the others. When the counter reaches its period
+// the hub sends a low pulse, which resets the counter to zero and gets inverted
+// towards the final conjunction in front of rx. rx gets its low pulse when all the
+// counters reset on the same press, so the answer of part 2 is the lcm of the periods,
+// which are primes here. Part 1 is simulated pulse by pulse.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let size = size.max(1);
+    let mut periods: Vec<u64> = vec![];
+    while periods.len() < size {
+        let period = rng.range(1 << (BITS - 1), (1 << BITS) - 1) as u64;
+        if is_prime(period) && !periods.contains(&period) {
+            periods.push(period);
+        }
+    }
+
+    let mut names = unique_names(rng, size * (BITS + 2) + 2, 2, b"abcdefghijklmnopqrstuvwxyz")
+        .into_iter()
+        .filter(|name| name != "rx");
+    let last = names.next().unwrap();
+    // (kind, name, destinations), the kind is '%', '&' or ' ' for the broadcaster
+    let mut modules: Vec<(char, String, Vec<String>)> = vec![];
+    let mut starts: Vec<String> = vec![];
+    for period in periods.iter() {
+        let bits: Vec<String> = (0..BITS).map(|_| names.next().unwrap()).collect();
+        let hub = names.next().unwrap();
+        let inverter = names.next().unwrap();
+        let mut hub_destinations: Vec<String> = vec![inverter.clone()];
+        for (bit, name) in bits.iter().enumerate() {
+            let mut destinations: Vec<String> = vec![];
+            if bit + 1 < BITS {
+                destinations.push(bits[bit + 1].clone());
+            }
+            if period >> bit & 1 == 1 {
+                destinations.push(hub.clone());
+            }
+            if period >> bit & 1 == 0 || bit == 0 {
+                hub_destinations.push(name.clone());
+            }
+            rng.shuffle(&mut destinations);
+            modules.push(('%', name.clone(), destinations));
+        }
+        rng.shuffle(&mut hub_destinations);
+        modules.push(('&', hub, hub_destinations));
+        modules.push(('&', inverter, vec![last.clone()]));
+        starts.push(bits[0].clone());
+    }
+    modules.push(('&', last, vec!["rx".to_owned()]));
+    modules.push((' ', "broadcaster".to_owned(), starts));
+    rng.shuffle(&mut modules);
+
+    let mut input = String::new();
+    for (kind, name, destinations) in modules.iter() {
+        let name = if *kind == ' ' {
+            name.clone()
+        } else {
+            format!("{kind}{name}")
+        };
+        input.push_str(&format!("{name} -> {}\n", destinations.join(", ")));
+    }
+    Generated {
+        input,
+        properties: vec![
+            ("Part1", count_pulses(&modules, 1000).to_string()),
+            (
+                "Part2",
+                periods.iter().fold(1, |acc, p| lcm(acc, *p)).to_string(),
+            ),
+        ],
+    }
+}
+
+fn is_prime(n: u64) -> bool {
+    n >= 2
+        && (2..)
+            .take_while(|d| d * d <= n)
+            .all(|d| !n.is_multiple_of(d))
+}
+
+// Low pulses times high pulses after the presses
+fn count_pulses(modules: &[(char, String, Vec<String>)], presses: usize) -> u64 {
+    let by_name: HashMap<&str, usize> = modules
+        .iter()
+        .enumerate()
+        .map(|(idx, (_, name, _))| (name.as_str(), idx))
+        .collect();
+    let mut is_on: Vec<bool> = vec![false; modules.len()];
+    // the last pulse from every input of a conjunction
+    let mut memory: Vec<HashMap<usize, bool>> = vec![HashMap::new(); modules.len()];
+    for (idx, (_, _, destinations)) in modules.iter().enumerate() {
+        for destination in destinations.iter() {
+            if let Some(to) = by_name.get(destination.as_str()) {
+                memory[*to].insert(idx, false);
+            }
+        }
+    }
+
+    let (mut low, mut high) = (0, 0);
+    for _ in 0..presses {
+        // (from, to, is high), the button is not a module
+        let mut queue: VecDeque<(usize, usize, bool)> = VecDeque::new();
+        queue.push_back((usize::MAX, by_name["broadcaster"], false));
+        while let Some((from, to, pulse)) = queue.pop_front() {
+            if pulse {
+                high += 1;
+            } else {
+                low += 1;
+            }
+            if to == usize::MAX {
+                continue;
+            }
+            let (kind, _, destinations) = &modules[to];
+            let next = match kind {
+                '%' if pulse => continue,
+                '%' => {
+                    is_on[to] = !is_on[to];
+                    is_on[to]
+                }
+                '&' => {
+                    memory[to].insert(from, pulse);
+                    !memory[to].values().all(|x| *x)
+                }
+                _ => pulse,
+            };
+            for destination in destinations.iter() {
+                let next_to = by_name
+                    .get(destination.as_str())
+                    .copied()
+                    .unwrap_or(usize::MAX);
+                queue.push_back((to, next_to, next));
+            }
+        }
+    }
+    low * high
+}
@@ -0,0 +1,256 @@
+use crate::{Generated, Rng};
+use std::collections::{HashSet, VecDeque};
+
+// Width and height of the garden, rounded to an odd number
+pub const DEFAULT_SIZE: usize = 131;
+
+const STEPS: i64 = 64;
+const PART2_STEPS: u64 = 26501365;
+
+// A garden shaped like the puzzle one: the start in the middle, clear middle row,
+// middle column and border, and random rocks elsewhere. Plots that can't be reached
+// from the start are filled with rocks. Part 1 is counted by a breadth first search
+// in the garden: a plot is reachable in exactly 64 steps when it's at most 64 steps
+// away with the same parity. Part 2 is counted tile by tile in the infinite garden,
+// see `count_tiled`.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let size = size.max(5) / 2 * 2 + 1;
+    let middle = size / 2;
+    let mut grid: Vec<Vec<u8>> = (0..size)
+        .map(|i| {
+            (0..size)
+                .map(|j| {
+                    let is_clear = i == middle
+                        || j == middle
+                        || i == 0
+                        || j == 0
+                        || i == size - 1
+                        || j == size - 1;
+                    if !is_clear && rng.chance(15) {
+                        b'#'
+                    } else {
+                        b'.'
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    // walled in plots
+    let mut reachable = vec![vec![false; size]; size];
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::from([(middle, middle)]);
+    reachable[middle][middle] = true;
+    while let Some((i, j)) = queue.pop_front() {
+        for (ni, nj) in [
+            (i + 1, j),
+            (i.wrapping_sub(1), j),
+            (i, j + 1),
+            (i, j.wrapping_sub(1)),
+        ] {
+            if ni < size && nj < size && grid[ni][nj] == b'.' && !reachable[ni][nj] {
+                reachable[ni][nj] = true;
+                queue.push_back((ni, nj));
+            }
+        }
+    }
+    for (i, row) in grid.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            if !reachable[i][j] {
+                *cell = b'#';
+            }
+        }
+    }
+    grid[middle][middle] = b'S';
+
+    let is_plot = |i: i64, j: i64| {
+        (0..size as i64).contains(&i)
+            && (0..size as i64).contains(&j)
+            && grid[i as usize][j as usize] != b'#'
+    };
+    let start = (middle as i64, middle as i64);
+    let mut seen: HashSet<(i64, i64)> = HashSet::from([start]);
+    let mut frontier = vec![start];
+    let mut part1 = 1;
+    for step in 1..=STEPS {
+        let mut next: Vec<(i64, i64)> = vec![];
+        for (i, j) in frontier {
+            for (ni, nj) in [(i + 1, j), (i - 1, j), (i, j + 1), (i, j - 1)] {
+                if is_plot(ni, nj) && seen.insert((ni, nj)) {
+                    next.push((ni, nj));
+                }
+            }
+        }
+        if step % 2 == STEPS % 2 {
+            part1 += next.len();
+        }
+        frontier = next;
+    }
+
+    let mut input = String::new();
+    for row in grid.iter() {
+        input.push_str(std::str::from_utf8(row).unwrap());
+        input.push('\n');
+    }
+    let part2 = count_tiled(&grid, PART2_STEPS);
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
+
+// Plots reachable in exactly `steps` steps in the garden repeated infinitely.
+// The border, the middle row and the middle column are clear, so every cell
+// on the border of a tile is reached by a shortest path along them, and it's
+// as far as the Manhattan distance. A tile is entered through the border cell
+// closest to the start: the middle of the near edge for the tiles in the same
+// row or column as the start, and the near corner for all the others. The distance
+// to a plot is the distance to the entry point plus the one inside the tile,
+// so only the distances from the 9 entry points are searched.
+fn count_tiled(grid: &[Vec<u8>], steps: u64) -> u64 {
+    let size = grid.len() as u64;
+    let middle = size / 2;
+    let (near, far) = (0, size as usize - 1);
+    let m = middle as usize;
+
+    let mut result = Distances::from(grid, (m, m)).count_within(steps);
+    // tiles in the same row or column as the start, entered at `t * size - middle`
+    for entry in [(near, m), (far, m), (m, near), (m, far)] {
+        let distances = Distances::from(grid, entry);
+        result += distances.count_in_tiles(steps, size, middle, 1, false);
+    }
+    // the other tiles are entered at `k * size - 2 * middle` for `k - 1` tiles
+    // with the sum of the tile coordinates equal to `k`
+    for entry in [(near, near), (near, far), (far, near), (far, far)] {
+        let distances = Distances::from(grid, entry);
+        result += distances.count_in_tiles(steps, size, 2 * middle, 2, true);
+    }
+    result
+}
+
+// Sorted distances to all the plots of a single tile, split by their parity
+struct Distances {
+    by_parity: [Vec<u64>; 2],
+}
+
+impl Distances {
+    fn from(grid: &[Vec<u8>], source: (usize, usize)) -> Distances {
+        let size = grid.len();
+        let mut distance: Vec<Vec<Option<u64>>> = vec![vec![None; size]; size];
+        distance[source.0][source.1] = Some(0);
+        let mut queue: VecDeque<(usize, usize)> = VecDeque::from([source]);
+        let mut by_parity: [Vec<u64>; 2] = [vec![], vec![]];
+        while let Some((i, j)) = queue.pop_front() {
+            let d = distance[i][j].unwrap();
+            by_parity[(d % 2) as usize].push(d);
+            for (ni, nj) in [
+                (i + 1, j),
+                (i.wrapping_sub(1), j),
+                (i, j + 1),
+                (i, j.wrapping_sub(1)),
+            ] {
+                if ni < size && nj < size && grid[ni][nj] != b'#' && distance[ni][nj].is_none() {
+                    distance[ni][nj] = Some(d + 1);
+                    queue.push_back((ni, nj));
+                }
+            }
+        }
+        // a breadth first search finds them in order
+        Distances { by_parity }
+    }
+
+    // Plots that can be the end of a walk with `steps` steps left
+    fn count_within(&self, steps: u64) -> u64 {
+        let distances = &self.by_parity[(steps % 2) as usize];
+        distances.partition_point(|d| *d <= steps) as u64
+    }
+
+    // Plots in the tiles entered at `t * size - offset` steps for `t` from `first`,
+    // with `t - first + 1` tiles for every `t` when `weighted` and one otherwise.
+    // Only the last few tiles are reached partially, all the others have every
+    // plot of the right parity, which flips from one `t` to the next as the size
+    // is odd.
+    fn count_in_tiles(
+        &self,
+        steps: u64,
+        size: u64,
+        offset: u64,
+        first: u64,
+        weighted: bool,
+    ) -> u64 {
+        let weight = |t: u64| if weighted { t - first + 1 } else { 1 };
+        let reach = (steps + offset) / size;
+        let farthest = self.by_parity.iter().flatten().max().copied().unwrap_or(0);
+        // the tiles up to `full` are reached completely
+        let full = ((steps + offset).saturating_sub(farthest) / size).max(first - 1);
+
+        let mut result = 0;
+        for t in (full + 1).max(first)..=reach {
+            result += weight(t) * self.count_within(steps + offset - t * size);
+        }
+        for last in [Some(full), full.checked_sub(1)].into_iter().flatten() {
+            if last < first {
+                continue;
+            }
+            // tiles `last, last - 2, ...` down to `first`
+            let count = (last - first) / 2 + 1;
+            let lowest = last - 2 * (count - 1);
+            let tiles = if weighted {
+                count * (weight(lowest) + weight(last)) / 2
+            } else {
+                count
+            };
+            result += tiles * self.count_within(steps + offset - last * size);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Breadth first search in the repeated garden itself
+    fn count_naive(grid: &[Vec<u8>], steps: u64) -> u64 {
+        let size = grid.len() as i64;
+        let start = (size / 2, size / 2);
+        let mut seen: HashSet<(i64, i64)> = HashSet::from([start]);
+        let mut frontier = vec![start];
+        let mut result = steps.is_multiple_of(2) as u64;
+        for step in 1..=steps {
+            let mut next: Vec<(i64, i64)> = vec![];
+            for (i, j) in frontier {
+                for (ni, nj) in [(i + 1, j), (i - 1, j), (i, j + 1), (i, j - 1)] {
+                    let cell = grid[ni.rem_euclid(size) as usize][nj.rem_euclid(size) as usize];
+                    if cell != b'#' && seen.insert((ni, nj)) {
+                        next.push((ni, nj));
+                    }
+                }
+            }
+            if step % 2 == steps % 2 {
+                result += next.len() as u64;
+            }
+            frontier = next;
+        }
+        result
+    }
+
+    #[test]
+    fn tiled_count_matches_naive() {
+        for seed in 0..10 {
+            let size = 5 + 2 * (seed as usize % 6);
+            let generated = crate::generate(21, seed, Some(size)).unwrap();
+            let grid: Vec<Vec<u8>> = generated
+                .input
+                .lines()
+                .map(|line| line.as_bytes().to_vec())
+                .collect();
+            for steps in [0, 1, 2, 3, 7, 10, 24, 51, 80, 151] {
+                assert_eq!(
+                    count_tiled(&grid, steps),
+                    count_naive(&grid, steps),
+                    "seed {seed}, size {size}, {steps} steps"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{Generated, Rng};
+
+// Number of bricks
+pub const DEFAULT_SIZE: usize = 1200;
+
+const AREA: usize = 10;
+
+// The bricks are dropped one by one onto a 10x10 area, like in a game of Tetris, which
+// gives the settled stack and who supports whom. Then they are lifted, every brick at
+// least as high as the ones dropped before it, so when they fall again every brick
+// lands on the same bricks as the first time.
+// The answers follow the supports naively, brick by brick.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    // top height and brick at every column of the area
+    let mut tops: Vec<Vec<(usize, Option<usize>)>> = vec![vec![(0, None); AREA]; AREA];
+    // (start, end) corners of the settled bricks
+    let mut bricks: Vec<([usize; 3], [usize; 3])> = vec![];
+    let mut supporters: Vec<Vec<usize>> = vec![];
+    for id in 0..size {
+        let len = rng.range(1, 4) as usize;
+        let axis = rng.index(3);
+        let mut start = [rng.index(AREA), rng.index(AREA), 0];
+        if axis < 2 {
+            start[axis] = rng.index(AREA + 1 - len);
+        }
+        let mut end = start;
+        end[axis] += len - 1;
+
+        let footprint: Vec<(usize, usize)> = (start[0]..=end[0])
+            .flat_map(|x| (start[1]..=end[1]).map(move |y| (x, y)))
+            .collect();
+        let rest = footprint.iter().map(|(x, y)| tops[*x][*y].0).max().unwrap();
+        let mut below: Vec<usize> = footprint
+            .iter()
+            .filter(|(x, y)| tops[*x][*y].0 == rest)
+            .filter_map(|(x, y)| tops[*x][*y].1)
+            .collect();
+        below.sort();
+        below.dedup();
+        start[2] += rest + 1;
+        end[2] += rest + 1;
+        for (x, y) in footprint.iter() {
+            tops[*x][*y] = (end[2], Some(id));
+        }
+        bricks.push((start, end));
+        supporters.push(below);
+    }
+
+    let mut supported: Vec<Vec<usize>> = vec![vec![]; size];
+    for (id, below) in supporters.iter().enumerate() {
+        for other in below.iter() {
+            supported[*other].push(id);
+        }
+    }
+    let part1 = (0..size)
+        .filter(|id| {
+            supported[*id]
+                .iter()
+                .all(|above| supporters[*above].len() > 1)
+        })
+        .count();
+    let mut part2 = 0;
+    for id in 0..size {
+        // bricks are dropped after their supporters, so one pass in that order is enough
+        let mut fallen = vec![false; size];
+        fallen[id] = true;
+        for above in id + 1..size {
+            if !supporters[above].is_empty() && supporters[above].iter().all(|x| fallen[*x]) {
+                fallen[above] = true;
+                part2 += 1;
+            }
+        }
+    }
+
+    let mut lift = 0;
+    let mut lines: Vec<String> = vec![];
+    for (start, end) in bricks.iter() {
+        lift += rng.range(0, 3) as usize;
+        lines.push(format!(
+            "{},{},{}~{},{},{}",
+            start[0],
+            start[1],
+            start[2] + lift,
+            end[0],
+            end[1],
+            end[2] + lift
+        ));
+    }
+    rng.shuffle(&mut lines);
+
+    Generated {
+        input: lines.join("\n") + "\n",
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{Generated, Rng};
+
+// Number of junctions on each side of the lattice
+pub const DEFAULT_SIZE: usize = 6;
+
+// Largest lattice where the longest simple path is still searched for
+const MAX_SEARCHED: usize = 6;
+
+// The trails of the puzzle: a lattice of junctions, every one linked to its right and
+// lower neighbors, with slopes pointing right and down around them, so going with
+// the slopes never leads back. The lattice has random spacing, and some trails make
+// a U-shaped detour into the block next to them: trails along rows into the bottom
+// left quarter of the block above, trails along columns into the top right quarter
+// of the block to the left, so detours never meet.
+// Part 1 is the longest path through the lattice going only right and down, and
+// part 2 tries every simple path on small lattices.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let size = size.max(2);
+    let gaps_r: Vec<usize> = (1..size).map(|_| rng.range(10, 22) as usize).collect();
+    let gaps_c: Vec<usize> = (1..size).map(|_| rng.range(10, 22) as usize).collect();
+    let mut rows = vec![rng.range(3, 6) as usize];
+    for gap in gaps_r.iter() {
+        rows.push(rows[rows.len() - 1] + gap);
+    }
+    let mut cols = vec![1];
+    for gap in gaps_c.iter() {
+        cols.push(cols[cols.len() - 1] + gap);
+    }
+    let (height, width) = (
+        rows[size - 1] + rng.range(3, 6) as usize + 1,
+        cols[size - 1] + 2,
+    );
+    let mut grid: Vec<Vec<u8>> = vec![vec![b'#'; width]; height];
+    let mut carve = |points: &[(usize, usize)]| -> usize {
+        let mut len = 0;
+        for pair in points.windows(2) {
+            let ((i1, j1), (i2, j2)) = (pair[0], pair[1]);
+            for row in grid[i1.min(i2)..=i1.max(i2)].iter_mut() {
+                row[j1.min(j2)..=j1.max(j2)].fill(b'.');
+            }
+            len += i1.abs_diff(i2) + j1.abs_diff(j2);
+        }
+        len
+    };
+
+    let entry = carve(&[(0, 1), (rows[0], 1)]);
+    let exit = carve(&[(rows[size - 1], width - 2), (height - 1, width - 2)]);
+    // length of the trail to the right of and below every junction
+    let mut right = vec![vec![0; size]; size];
+    let mut down = vec![vec![0; size]; size];
+    for i in 0..size {
+        for j in 0..size {
+            let (r, c) = (rows[i], cols[j]);
+            if j + 1 < size {
+                let next = cols[j + 1];
+                right[i][j] = if i > 0 && rng.chance(60) {
+                    let a = c + rng.range(3, gaps_c[j] as i64 / 2 - 2) as usize;
+                    let b = a + 2;
+                    let depth = rng.range(1, gaps_r[i - 1] as i64 / 2 - 2) as usize;
+                    carve(&[
+                        (r, c),
+                        (r, a),
+                        (r - depth, a),
+                        (r - depth, b),
+                        (r, b),
+                        (r, next),
+                    ])
+                } else {
+                    carve(&[(r, c), (r, next)])
+                };
+            }
+            if i + 1 < size {
+                let next = rows[i + 1];
+                down[i][j] = if j > 0 && rng.chance(60) {
+                    let a = r + rng.range(3, gaps_r[i] as i64 / 2 - 2) as usize;
+                    let b = a + 2;
+                    let depth = rng.range(1, gaps_c[j - 1] as i64 / 2 - 2) as usize;
+                    carve(&[
+                        (r, c),
+                        (a, c),
+                        (a, c - depth),
+                        (b, c - depth),
+                        (b, c),
+                        (next, c),
+                    ])
+                } else {
+                    carve(&[(r, c), (next, c)])
+                };
+            }
+        }
+    }
+    // the slopes go on after the carving, which would overwrite them
+    for i in 0..size {
+        for j in 0..size {
+            let (r, c) = (rows[i], cols[j]);
+            if j + 1 < size {
+                grid[r][c + 1] = b'>';
+                grid[r][cols[j + 1] - 1] = b'>';
+            }
+            if i + 1 < size {
+                grid[r + 1][c] = b'v';
+                grid[rows[i + 1] - 1][c] = b'v';
+            }
+        }
+    }
+
+    // longest path to every junction going only right and down
+    let mut longest = vec![vec![0; size]; size];
+    for i in 0..size {
+        for j in 0..size {
+            let from_left = if j > 0 {
+                longest[i][j - 1] + right[i][j - 1]
+            } else {
+                0
+            };
+            let from_above = if i > 0 {
+                longest[i - 1][j] + down[i - 1][j]
+            } else {
+                0
+            };
+            longest[i][j] = from_left.max(from_above);
+        }
+    }
+    let mut properties = vec![(
+        "Part1",
+        (entry + longest[size - 1][size - 1] + exit).to_string(),
+    )];
+    if size <= MAX_SEARCHED {
+        let mut visited = vec![vec![false; size]; size];
+        let part2 = longest_simple_path(&right, &down, (0, 0), &mut visited);
+        properties.push(("Part2", (entry + part2.unwrap_or(0) + exit).to_string()));
+    }
+
+    let mut input = String::new();
+    for row in grid.iter() {
+        input.push_str(std::str::from_utf8(row).unwrap());
+        input.push('\n');
+    }
+    Generated { input, properties }
+}
+
+// Longest path from the junction to the last one that never visits a junction twice
+fn longest_simple_path(
+    right: &[Vec<usize>],
+    down: &[Vec<usize>],
+    (i, j): (usize, usize),
+    visited: &mut [Vec<bool>],
+) -> Option<usize> {
+    let size = right.len();
+    if (i, j) == (size - 1, size - 1) {
+        return Some(0);
+    }
+    visited[i][j] = true;
+    let mut neighbors: Vec<((usize, usize), usize)> = vec![];
+    if j + 1 < size {
+        neighbors.push(((i, j + 1), right[i][j]));
+    }
+    if j > 0 {
+        neighbors.push(((i, j - 1), right[i][j - 1]));
+    }
+    if i + 1 < size {
+        neighbors.push(((i + 1, j), down[i][j]));
+    }
+    if i > 0 {
+        neighbors.push(((i - 1, j), down[i - 1][j]));
+    }
+    let mut result: Option<usize> = None;
+    for ((ni, nj), len) in neighbors {
+        if !visited[ni][nj] {
+            if let Some(rest) = longest_simple_path(right, down, (ni, nj), visited) {
+                result = result.max(Some(len + rest));
+            }
+        }
+    }
+    visited[i][j] = false;
+    result
+}
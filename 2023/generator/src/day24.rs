@@ -0,0 +1,79 @@
+use crate::{Generated, Rng};
+
+// Number of hailstones
+pub const DEFAULT_SIZE: usize = 300;
+
+const AREA: (i128, i128) = (200_000_000_000_000, 400_000_000_000_000);
+
+// The rock is thrown first, then every hailstone gets a random velocity and the time
+// it meets the rock, and its position is taken back from there. So the rock is known
+// for part 2. Part 1 intersects every pair of paths exactly, in integers.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let rock = [0; 3].map(|_| rng.range(280_000_000_000_000, 330_000_000_000_000));
+    let rock_velocity = [0; 3].map(|_| rng.range(-300, 300));
+    let mut times: Vec<i64> = vec![];
+    let mut hailstones: Vec<([i64; 3], [i64; 3])> = vec![];
+    while hailstones.len() < size {
+        let time = rng.range(10_000_000_000, 900_000_000_000);
+        let velocity = [0; 3].map(|_| rng.range(-300, 300));
+        let position: [i64; 3] =
+            [0, 1, 2].map(|k| rock[k] + (rock_velocity[k] - velocity[k]) * time);
+        // the hailstone has to move relatively to the rock on every axis, or it would
+        // meet the rock at any time on that axis
+        if (0..3).any(|k| velocity[k] == rock_velocity[k] || position[k] < 0)
+            || times.contains(&time)
+        {
+            continue;
+        }
+        times.push(time);
+        hailstones.push((position, velocity));
+    }
+
+    let mut part1 = 0;
+    for (idx, (p1, v1)) in hailstones.iter().enumerate() {
+        for (p2, v2) in hailstones[idx + 1..].iter() {
+            if crosses_in_area(p1, v1, p2, v2) {
+                part1 += 1;
+            }
+        }
+    }
+
+    let mut input = String::new();
+    for (p, v) in hailstones.iter() {
+        input.push_str(&format!(
+            "{}, {}, {} @ {}, {}, {}\n",
+            p[0], p[1], p[2], v[0], v[1], v[2]
+        ));
+    }
+    Generated {
+        input,
+        properties: vec![
+            ("Part1", part1.to_string()),
+            ("Part2", rock.iter().sum::<i64>().to_string()),
+        ],
+    }
+}
+
+// Whether the paths cross in the future of both hailstones inside the test area,
+// ignoring the z axis. p1 + v1 * t = p2 + v2 * s is solved by Cramer's rule,
+// and the crossing point is compared multiplied by the determinant.
+fn crosses_in_area(p1: &[i64; 3], v1: &[i64; 3], p2: &[i64; 3], v2: &[i64; 3]) -> bool {
+    let [p1x, p1y, _] = p1.map(|x| x as i128);
+    let [v1x, v1y, _] = v1.map(|x| x as i128);
+    let [p2x, p2y, _] = p2.map(|x| x as i128);
+    let [v2x, v2y, _] = v2.map(|x| x as i128);
+    let (dx, dy) = (p2x - p1x, p2y - p1y);
+    let mut det = v2x * v1y - v1x * v2y;
+    if det == 0 {
+        return false;
+    }
+    let mut t = v2x * dy - v2y * dx;
+    let mut s = v1x * dy - v1y * dx;
+    if det < 0 {
+        (det, t, s) = (-det, -t, -s);
+    }
+    let x = p1x * det + v1x * t;
+    let y = p1y * det + v1y * t;
+    let area = AREA.0 * det..=AREA.1 * det;
+    t > 0 && s > 0 && area.contains(&x) && area.contains(&y)
+}
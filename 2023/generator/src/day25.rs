@@ -0,0 +1,59 @@
+use crate::{unique_names, Generated, Rng};
+use std::collections::{BTreeMap, HashSet};
+
+// Number of components
+pub const DEFAULT_SIZE: usize = 1500;
+
+// Two groups of components joined by exactly three wires. Every group is a ring
+// where each component is also wired to the next but one, which takes at least
+// four cuts to split, plus random wires inside the group. So the only three wires
+// to cut are the ones between the groups, and the answer is the product of their sizes.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let size = size.max(10);
+    let first = rng.range(
+        5.max(size as i64 * 3 / 10),
+        (size as i64 * 7 / 10).min(size as i64 - 5),
+    ) as usize;
+    let names = unique_names(rng, size, 3, b"abcdefghijklmnopqrstuvwxyz");
+    let groups = [(0, first), (first, size)];
+
+    let mut wires: HashSet<(usize, usize)> = HashSet::new();
+    let mut add = |a: usize, b: usize| a != b && wires.insert((a.min(b), a.max(b)));
+    for (start, end) in groups {
+        let len = end - start;
+        for idx in 0..len {
+            add(start + idx, start + (idx + 1) % len);
+            add(start + idx, start + (idx + 2) % len);
+            let other = rng.index(len);
+            add(start + idx, start + other);
+        }
+    }
+    let mut cut = 0;
+    while cut < 3 {
+        if add(rng.index(first), first + rng.index(size - first)) {
+            cut += 1;
+        }
+    }
+
+    // every wire is listed once, by either of its components
+    let mut lines: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    let mut wires: Vec<(usize, usize)> = wires.into_iter().collect();
+    wires.sort();
+    for (a, b) in wires {
+        let (from, to) = if rng.chance(50) { (a, b) } else { (b, a) };
+        lines.entry(from).or_default().push(to);
+    }
+    let mut lines: Vec<String> = lines
+        .iter()
+        .map(|(from, to)| {
+            let to: Vec<&str> = to.iter().map(|x| names[*x].as_str()).collect();
+            format!("{}: {}", names[*from], to.join(" "))
+        })
+        .collect();
+    rng.shuffle(&mut lines);
+
+    Generated {
+        input: lines.join("\n") + "\n",
+        properties: vec![("Part1", (first * (size - first)).to_string())],
+    }
+}
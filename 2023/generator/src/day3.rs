@@ -0,0 +1,67 @@
+use crate::{Generated, Rng};
+
+// Width and height of the schematic
+pub const DEFAULT_SIZE: usize = 140;
+
+const SYMBOLS: &[u8] = b"*#+$/@%=&-";
+
+// Random numbers and symbols on a dotted grid. The answers are counted naively:
+// every number looks at the whole box around itself.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut rows: Vec<Vec<u8>> = vec![vec![b'.'; size]; size];
+    // (value, row, first column, last column)
+    let mut numbers: Vec<(u64, usize, usize, usize)> = vec![];
+    for (i, row) in rows.iter_mut().enumerate() {
+        let mut j = rng.index(4);
+        while j < size {
+            if rng.chance(20) {
+                row[j] = *rng.choose(SYMBOLS);
+                j += 1;
+            } else {
+                let len = (rng.range(1, 3) as usize).min(size - j);
+                let value = rng.range(10i64.pow(len as u32 - 1), 10i64.pow(len as u32) - 1);
+                row[j..j + len].copy_from_slice(value.to_string().as_bytes());
+                numbers.push((value as u64, i, j, j + len - 1));
+                j += len;
+            }
+            // numbers on the same row are never glued together
+            j += rng.range(1, 4) as usize;
+        }
+    }
+
+    let mut part1 = 0;
+    // numbers next to every symbol
+    let mut neighbors: Vec<Vec<Vec<u64>>> = vec![vec![vec![]; size]; size];
+    for (value, i, first, last) in numbers.iter() {
+        let mut is_part = false;
+        for row in i.saturating_sub(1)..=(i + 1).min(size - 1) {
+            for col in first.saturating_sub(1)..=(last + 1).min(size - 1) {
+                if SYMBOLS.contains(&rows[row][col]) {
+                    is_part = true;
+                    neighbors[row][col].push(*value);
+                }
+            }
+        }
+        if is_part {
+            part1 += value;
+        }
+    }
+    let mut part2 = 0;
+    for (i, row) in rows.iter().enumerate() {
+        for (j, cell) in row.iter().enumerate() {
+            if *cell == b'*' && neighbors[i][j].len() == 2 {
+                part2 += neighbors[i][j][0] * neighbors[i][j][1];
+            }
+        }
+    }
+
+    let mut input = String::new();
+    for row in rows.iter() {
+        input.push_str(std::str::from_utf8(row).unwrap());
+        input.push('\n');
+    }
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{Generated, Rng};
+
+// Number of cards
+pub const DEFAULT_SIZE: usize = 200;
+
+const WINNING: usize = 10;
+const OWNED: usize = 25;
+
+// The number of matches of every card is picked first, and the owned numbers
+// get exactly that many of the winning ones. Like in the puzzle, no card wins
+// copies of cards past the end of the table. The number of matches is cut
+// when the total number of cards would no longer fit into u64.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut input = String::new();
+    let mut part1 = 0;
+    let mut instances: Vec<u64> = vec![1; size];
+    let mut total = size as u64;
+    for idx in 0..size {
+        let matches = rng.range(0, WINNING.min(size - idx - 1) as i64) as usize;
+        let budget = (u64::MAX - total) / instances[idx];
+        let matches = matches.min(budget.min(WINNING as u64) as usize);
+        total += matches as u64 * instances[idx];
+        if matches > 0 {
+            part1 += 1 << (matches - 1);
+        }
+        for next in idx + 1..=idx + matches {
+            instances[next] += instances[idx];
+        }
+
+        let mut numbers: Vec<u64> = (1..100).collect();
+        rng.shuffle(&mut numbers);
+        let winning = &numbers[..WINNING];
+        let mut owned: Vec<u64> = winning[..matches].to_vec();
+        owned.extend_from_slice(&numbers[WINNING..WINNING + OWNED - matches]);
+        rng.shuffle(&mut owned);
+
+        let to_str = |numbers: &[u64]| -> String {
+            let numbers: Vec<String> = numbers.iter().map(|x| format!("{x:>2}")).collect();
+            numbers.join(" ")
+        };
+        input.push_str(&format!(
+            "Card {:>3}: {} | {}\n",
+            idx + 1,
+            to_str(winning),
+            to_str(&owned)
+        ));
+    }
+
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", total.to_string())],
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{Generated, Rng};
+
+// Number of seed ranges
+pub const DEFAULT_SIZE: usize = 10;
+
+const CATEGORIES: [&str; 8] = [
+    "seed",
+    "soil",
+    "fertilizer",
+    "water",
+    "light",
+    "temperature",
+    "humidity",
+    "location",
+];
+const UNIVERSE: u64 = 1 << 32;
+
+// (source start, destination start, length)
+type Stage = Vec<(u64, u64, u64)>;
+
+// Every mapping cuts the values into pieces and moves them around, so it's a bijection
+// of 0..2^32, and the pieces that don't move are sometimes left out of the almanac.
+// Each stage is increasing inside a piece, so the lowest location of a seed range
+// is at its start or at a seed that some stage maps to the start of a piece.
+// Those seeds are found by going back through the stages.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let stages: Vec<Stage> = (1..CATEGORIES.len())
+        .map(|_| {
+            let pieces = rng.range(3, 40) as usize;
+            random_stage(rng, pieces)
+        })
+        .collect();
+
+    let mut seeds: Vec<(u64, u64)> = vec![];
+    for _ in 0..size {
+        let len = rng.range(1, 1 << 28) as u64;
+        let start = rng.below(UNIVERSE - len);
+        seeds.push((start, len));
+    }
+
+    let location = |seed: u64| stages.iter().fold(seed, |value, stage| apply(stage, value));
+    let part1 = seeds
+        .iter()
+        .flat_map(|(start, len)| [*start, *len])
+        .map(location)
+        .min()
+        .unwrap_or(0);
+
+    let mut candidates: Vec<u64> = seeds.iter().map(|(start, _)| *start).collect();
+    for (idx, stage) in stages.iter().enumerate() {
+        for (source, _, _) in stage.iter() {
+            let seed = stages[..idx]
+                .iter()
+                .rev()
+                .fold(*source, |value, stage| apply_inverse(stage, value));
+            candidates.push(seed);
+        }
+    }
+    let part2 = candidates
+        .iter()
+        .filter(|seed| {
+            seeds
+                .iter()
+                .any(|(start, len)| (*start..start + len).contains(*seed))
+        })
+        .map(|seed| location(*seed))
+        .min()
+        .unwrap_or(0);
+
+    let mut input = String::from("seeds:");
+    for (start, len) in seeds.iter() {
+        input.push_str(&format!(" {start} {len}"));
+    }
+    input.push('\n');
+    for (idx, stage) in stages.iter().enumerate() {
+        input.push_str(&format!(
+            "\n{}-to-{} map:\n",
+            CATEGORIES[idx],
+            CATEGORIES[idx + 1]
+        ));
+        let mut lines: Vec<&(u64, u64, u64)> = stage
+            .iter()
+            .filter(|(source, destination, _)| source != destination || rng.chance(50))
+            .collect();
+        rng.shuffle(&mut lines);
+        for (source, destination, len) in lines {
+            input.push_str(&format!("{destination} {source} {len}\n"));
+        }
+    }
+
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
+
+fn random_stage(rng: &mut Rng, pieces: usize) -> Stage {
+    let mut cuts: Vec<u64> = (1..pieces)
+        .map(|_| rng.range(1, UNIVERSE as i64 - 1) as u64)
+        .collect();
+    cuts.extend([0, UNIVERSE]);
+    cuts.sort();
+    cuts.dedup();
+    let mut pieces: Vec<(u64, u64)> = cuts.windows(2).map(|w| (w[0], w[1] - w[0])).collect();
+    // either all the pieces are shuffled or just two of them swap places
+    if rng.chance(50) {
+        rng.shuffle(&mut pieces);
+    } else {
+        let (a, b) = (rng.index(pieces.len()), rng.index(pieces.len()));
+        pieces.swap(a, b);
+    }
+    let mut result: Stage = vec![];
+    let mut destination = 0;
+    for (source, len) in pieces.iter() {
+        result.push((*source, destination, *len));
+        destination += len;
+    }
+    result
+}
+
+fn apply(stage: &Stage, value: u64) -> u64 {
+    stage
+        .iter()
+        .find(|(source, _, len)| (*source..source + len).contains(&value))
+        .map_or(value, |(source, destination, _)| {
+            destination + (value - source)
+        })
+}
+
+fn apply_inverse(stage: &Stage, value: u64) -> u64 {
+    stage
+        .iter()
+        .find(|(_, destination, len)| (*destination..destination + len).contains(&value))
+        .map_or(value, |(source, destination, _)| {
+            source + (value - destination)
+        })
+}
@@ -0,0 +1,54 @@
+use crate::{Generated, Rng};
+
+// Number of races, at most 4 so that the kerned race of part 2 fits into u64
+pub const DEFAULT_SIZE: usize = 4;
+
+// Every race gets the first winning hold time `s` first, and the record is the distance
+// of holding one less, so `s..=time - s` are the winning hold times.
+// The kerned race of part 2 is counted with a binary search over the hold time.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut races: Vec<(u64, u64)> = vec![];
+    let mut part1 = 1;
+    for _ in 0..size.clamp(1, 4) {
+        let time = rng.range(7, 99) as u64;
+        let first_win = rng.range(1, time as i64 / 2) as u64;
+        races.push((time, (first_win - 1) * (time - first_win + 1)));
+        part1 *= time - 2 * first_win + 1;
+    }
+
+    let kerned = |value: fn(&(u64, u64)) -> u64| -> u128 {
+        let digits: String = races.iter().map(|race| value(race).to_string()).collect();
+        digits.parse().unwrap()
+    };
+    let (time, record) = (kerned(|race| race.0), kerned(|race| race.1));
+    // the distance grows up to the half of the time, the first win is the lowest
+    // hold time beating the record there
+    let beats = |hold: u128| hold * (time - hold) > record;
+    let part2 = if beats(time / 2) {
+        let (mut lo, mut hi) = (0, time / 2);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if beats(mid) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        time - 2 * lo + 1
+    } else {
+        0
+    };
+
+    let line = |name: &str, value: fn(&(u64, u64)) -> u64| -> String {
+        let values: Vec<String> = races
+            .iter()
+            .map(|race| format!("{:>5}", value(race)))
+            .collect();
+        format!("{name}{}\n", values.join(" "))
+    };
+    let input = line("Time:    ", |race| race.0) + &line("Distance:", |race| race.1);
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
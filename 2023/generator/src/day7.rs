@@ -0,0 +1,83 @@
+use crate::{Generated, Rng};
+use std::collections::HashSet;
+
+// Number of hands
+pub const DEFAULT_SIZE: usize = 1000;
+
+const CARDS: &[u8] = b"23456789TJQKA";
+
+// (group sizes, card values) that hands are ranked by
+type HandKey = (Vec<u8>, Vec<usize>);
+
+// Distinct random hands, drawn from a few cards at a time so that every type shows up.
+// The answers rank the hands by a naive key: the sorted group sizes, then the cards.
+// For part 2 every joker becomes the same card, and every card is tried.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut seen: HashSet<Vec<u8>> = HashSet::new();
+    let mut hands: Vec<(Vec<u8>, u64)> = vec![];
+    while hands.len() < size {
+        let kinds: Vec<u8> = (0..rng.range(1, 5)).map(|_| *rng.choose(CARDS)).collect();
+        let cards: Vec<u8> = (0..5).map(|_| *rng.choose(&kinds)).collect();
+        if seen.insert(cards.clone()) {
+            hands.push((cards, rng.range(1, 1000) as u64));
+        }
+    }
+
+    let total_winnings = |key: &dyn Fn(&[u8]) -> HandKey| -> u64 {
+        let mut ranked: Vec<(Vec<u8>, Vec<usize>, u64)> = hands
+            .iter()
+            .map(|(cards, bid)| {
+                let (groups, values) = key(cards);
+                (groups, values, *bid)
+            })
+            .collect();
+        ranked.sort();
+        ranked
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, _, bid))| (idx as u64 + 1) * bid)
+            .sum()
+    };
+    let part1 = total_winnings(&|cards| (groups(cards), values(cards, CARDS)));
+    let part2 = total_winnings(&|cards| {
+        let best = CARDS
+            .iter()
+            .map(|joker| {
+                let replaced: Vec<u8> = cards
+                    .iter()
+                    .map(|card| if *card == b'J' { *joker } else { *card })
+                    .collect();
+                groups(&replaced)
+            })
+            .max()
+            .unwrap();
+        (best, values(cards, b"J23456789TQKA"))
+    });
+
+    let mut input = String::new();
+    for (cards, bid) in hands.iter() {
+        input.push_str(&format!("{} {bid}\n", String::from_utf8_lossy(cards)));
+    }
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
+
+// Sizes of groups of equal cards, the largest first
+fn groups(cards: &[u8]) -> Vec<u8> {
+    let mut result: Vec<u8> = CARDS
+        .iter()
+        .map(|card| cards.iter().filter(|c| *c == card).count() as u8)
+        .filter(|count| *count > 0)
+        .collect();
+    result.sort_by(|a, b| b.cmp(a));
+    result
+}
+
+fn values(cards: &[u8], order: &[u8]) -> Vec<usize> {
+    cards
+        .iter()
+        .map(|card| order.iter().position(|c| c == card).unwrap())
+        .collect()
+}
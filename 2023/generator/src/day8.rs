@@ -0,0 +1,83 @@
+use crate::{lcm, unique_names, Generated, Rng};
+
+// Number of ghosts
+pub const DEFAULT_SIZE: usize = 6;
+
+const PRIMES: [u64; 12] = [11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+// Every ghost walks around its own ring. Each position of the ring has a left and
+// a right node with the same exits, so the instructions choose the nodes, but not
+// how far the ghost gets: after `t` steps it's always `t` positions from the start.
+// The end node is the first position of the ring, so a ghost is on it every `len` steps,
+// and all the ghosts meet at the lcm of the ring lengths. The first ghost goes
+// from AAA to ZZZ. The ring lengths share a factor, so the lcm is not just their product.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let size = size.clamp(1, PRIMES.len());
+    let instructions: String = (0..rng.range(10, 300))
+        .map(|_| if rng.chance(50) { 'L' } else { 'R' })
+        .collect();
+
+    let common = rng.range(2, 20) as u64;
+    let mut primes = PRIMES.to_vec();
+    rng.shuffle(&mut primes);
+    let lengths: Vec<u64> = primes[..size].iter().map(|p| common * p).collect();
+
+    // names of the nodes in the middle of the rings never end with A or Z
+    let inner_count: u64 = lengths.iter().map(|len| 2 * (len - 1)).sum();
+    let middle: Vec<u8> = ALPHABET
+        .iter()
+        .copied()
+        .filter(|ch| *ch != b'A' && *ch != b'Z')
+        .collect();
+    let mut inner_names = unique_names(rng, inner_count as usize, 3, &middle).into_iter();
+    let prefixes: Vec<String> = unique_names(rng, size + 2, 2, ALPHABET)
+        .into_iter()
+        .filter(|prefix| prefix != "AA" && prefix != "ZZ")
+        .collect();
+
+    // (name, left, right)
+    let mut nodes: Vec<(String, String, String)> = vec![];
+    for (ghost, len) in lengths.iter().enumerate() {
+        let (start, end) = if ghost == 0 {
+            ("AAA".to_owned(), "ZZZ".to_owned())
+        } else {
+            (
+                format!("{}A", prefixes[ghost]),
+                format!("{}Z", prefixes[ghost]),
+            )
+        };
+        // (left node, right node) at every position, both are the end node at 0
+        let mut ring: Vec<(String, String)> = vec![(end.clone(), end)];
+        for _ in 1..*len {
+            ring.push((inner_names.next().unwrap(), inner_names.next().unwrap()));
+        }
+        for position in 0..ring.len() {
+            let (left, right) = ring[(position + 1) % ring.len()].clone();
+            nodes.push((ring[position].0.clone(), left.clone(), right.clone()));
+            if position > 0 {
+                nodes.push((ring[position].1.clone(), left, right));
+            }
+        }
+        nodes.push((start, ring[1].0.clone(), ring[1].1.clone()));
+    }
+    rng.shuffle(&mut nodes);
+
+    let mut input = format!("{instructions}\n\n");
+    for (name, left, right) in nodes.iter() {
+        input.push_str(&format!("{name} = ({left}, {right})\n"));
+    }
+    Generated {
+        input,
+        properties: vec![
+            ("Part1", lengths[0].to_string()),
+            (
+                "Part2",
+                lengths
+                    .iter()
+                    .fold(1, |acc, len| lcm(acc, *len))
+                    .to_string(),
+            ),
+        ],
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{Generated, Rng};
+
+// Number of histories
+pub const DEFAULT_SIZE: usize = 200;
+
+const LEN: i64 = 21;
+
+// Every history is a polynomial in Newton form, sum of c_k * binomial(x, k),
+// with random small c_k up to a random degree, taken at x = 0..21.
+// Both extrapolations are just the polynomial at x = 21 and at x = -1.
+pub fn generate(rng: &mut Rng, size: usize) -> Generated {
+    let mut input = String::new();
+    let (mut part1, mut part2) = (0, 0);
+    for _ in 0..size {
+        let degree = rng.range(0, LEN - 2);
+        let coefficients: Vec<i64> = (0..=degree).map(|_| rng.range(-9, 9)).collect();
+        let value = |x: i64| -> i64 {
+            coefficients
+                .iter()
+                .enumerate()
+                .map(|(k, c)| c * binomial(x, k as i64))
+                .sum()
+        };
+        let values: Vec<String> = (0..LEN).map(|x| value(x).to_string()).collect();
+        input.push_str(&values.join(" "));
+        input.push('\n');
+        part1 += value(LEN);
+        part2 += value(-1);
+    }
+
+    Generated {
+        input,
+        properties: vec![("Part1", part1.to_string()), ("Part2", part2.to_string())],
+    }
+}
+
+// x * (x - 1) * ... * (x - k + 1) / k!, for negative x too
+fn binomial(x: i64, k: i64) -> i64 {
+    (0..k).fold(1, |acc, i| acc * (x - i) / (i + 1))
+}
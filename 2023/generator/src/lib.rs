@@ -0,0 +1,244 @@
+// Synthetic puzzle inputs for every day.
+// Each day has a module with `generate(rng, size)`, which builds a valid input
+// of the given size from the seeded random generator, and records what is known
+// about it: usually the answers, either fixed by the construction itself or counted
+// by a naive method that is independent of the solvers.
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+
+pub struct Generated {
+    pub input: String,
+    // (name, value), for example ("Part1", "142")
+    pub properties: Vec<(&'static str, String)>,
+}
+
+// Input for the day, `size` is the default one of the day when not given
+pub fn generate(day: u32, seed: u64, size: Option<usize>) -> Option<Generated> {
+    let mut rng = Rng::new(seed);
+    let (generate, default_size): (fn(&mut Rng, usize) -> Generated, usize) = match day {
+        1 => (day1::generate, day1::DEFAULT_SIZE),
+        2 => (day2::generate, day2::DEFAULT_SIZE),
+        3 => (day3::generate, day3::DEFAULT_SIZE),
+        4 => (day4::generate, day4::DEFAULT_SIZE),
+        5 => (day5::generate, day5::DEFAULT_SIZE),
+        6 => (day6::generate, day6::DEFAULT_SIZE),
+        7 => (day7::generate, day7::DEFAULT_SIZE),
+        8 => (day8::generate, day8::DEFAULT_SIZE),
+        9 => (day9::generate, day9::DEFAULT_SIZE),
+        10 => (day10::generate, day10::DEFAULT_SIZE),
+        11 => (day11::generate, day11::DEFAULT_SIZE),
+        12 => (day12::generate, day12::DEFAULT_SIZE),
+        13 => (day13::generate, day13::DEFAULT_SIZE),
+        14 => (day14::generate, day14::DEFAULT_SIZE),
+        15 => (day15::generate, day15::DEFAULT_SIZE),
+        16 => (day16::generate, day16::DEFAULT_SIZE),
+        17 => (day17::generate, day17::DEFAULT_SIZE),
+        18 => (day18::generate, day18::DEFAULT_SIZE),
+        19 => (day19::generate, day19::DEFAULT_SIZE),
+        20 => (day20::generate, day20::DEFAULT_SIZE),
+        21 => (day21::generate, day21::DEFAULT_SIZE),
+        22 => (day22::generate, day22::DEFAULT_SIZE),
+        23 => (day23::generate, day23::DEFAULT_SIZE),
+        24 => (day24::generate, day24::DEFAULT_SIZE),
+        25 => (day25::generate, day25::DEFAULT_SIZE),
+        _ => return None,
+    };
+    Some(generate(&mut rng, size.unwrap_or(default_size)))
+}
+
+// SplitMix64: tiny, fast and good enough for building inputs,
+// and the same seed gives the same input on every platform
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in 0..n, n > 0
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    // Uniform in lo..=hi
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        lo + self.below((hi - lo) as u64 + 1) as i64
+    }
+
+    pub fn index(&mut self, len: usize) -> usize {
+        self.below(len as u64) as usize
+    }
+
+    // True with the probability of `percent` / 100
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.index(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+// Distinct names of `len` characters from the alphabet, which has to have enough of them
+pub fn unique_names(rng: &mut Rng, count: usize, len: usize, alphabet: &[u8]) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    let mut result: Vec<String> = vec![];
+    while result.len() < count {
+        let name: String = (0..len).map(|_| *rng.choose(alphabet) as char).collect();
+        if seen.insert(name.clone()) {
+            result.push(name);
+        }
+    }
+    result
+}
+
+// A random blob of cells on a square grid, filling about `percent` of it.
+// It grows one cell at a time from the middle, and a cell is only added when the blob
+// stays simply connected and never touches itself diagonally, so its outline
+// is a simple polygon.
+pub fn random_blob(rng: &mut Rng, cells: usize, percent: usize) -> Vec<Vec<bool>> {
+    let mut blob: Vec<Vec<bool>> = vec![vec![false; cells]; cells];
+    blob[cells / 2][cells / 2] = true;
+    let mut members: Vec<(usize, usize)> = vec![(cells / 2, cells / 2)];
+    let target = (cells * cells * percent / 100).max(1);
+    for _ in 0..100 * target {
+        if members.len() >= target {
+            break;
+        }
+        let (a, b) = *rng.choose(&members);
+        let (da, db) = *rng.choose(&[(0, 1), (1, 0), (0, -1), (-1, 0)]);
+        let (a, b) = (a as i64 + da, b as i64 + db);
+        if can_grow(&blob, a, b) {
+            blob[a as usize][b as usize] = true;
+            members.push((a as usize, b as usize));
+        }
+    }
+    blob
+}
+
+fn is_in_blob(blob: &[Vec<bool>], a: i64, b: i64) -> bool {
+    a >= 0
+        && b >= 0
+        && (a as usize) < blob.len()
+        && (b as usize) < blob.len()
+        && blob[a as usize][b as usize]
+}
+
+fn can_grow(blob: &[Vec<bool>], a: i64, b: i64) -> bool {
+    let is_in = |a: i64, b: i64| is_in_blob(blob, a, b);
+    if a < 0 || b < 0 || a as usize >= blob.len() || b as usize >= blob.len() || is_in(a, b) {
+        return false;
+    }
+    // no diagonal touches
+    for (da, db) in [(-1, -1), (-1, 1), (1, -1), (1, 1)] {
+        if is_in(a + da, b + db) && !is_in(a + da, b) && !is_in(a, b + db) {
+            return false;
+        }
+    }
+    // the neighbors in the blob are one run around the cell, or it would close a hole
+    let ring = [
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+        (1, 0),
+        (1, -1),
+        (0, -1),
+    ];
+    let runs = (0..ring.len())
+        .filter(|k| {
+            let (da, db) = ring[*k];
+            let (prev_a, prev_b) = ring[(k + ring.len() - 1) % ring.len()];
+            is_in(a + da, b + db) && !is_in(a + prev_a, b + prev_b)
+        })
+        .count();
+    runs == 1
+}
+
+// Corners of the blob outline, clockwise from the top left one.
+// The cell (a, b) has the corners (a, b) and (a + 1, b + 1), and every corner
+// on the way is listed, even in the middle of a straight side.
+pub fn outline(blob: &[Vec<bool>]) -> Vec<(i64, i64)> {
+    let is_in = |a: i64, b: i64| is_in_blob(blob, a, b);
+    let has_edge = |from: (i64, i64), to: (i64, i64)| {
+        let (a, b) = (from.0.min(to.0), from.1.min(to.1));
+        if from.0 == to.0 {
+            is_in(a - 1, b) != is_in(a, b)
+        } else {
+            is_in(a, b - 1) != is_in(a, b)
+        }
+    };
+
+    // the top edge of the top left cell of the blob is on the outline
+    let top = (0..blob.len())
+        .flat_map(|a| (0..blob.len()).map(move |b| (a as i64, b as i64)))
+        .find(|(a, b)| is_in(*a, *b))
+        .unwrap();
+    let mut result: Vec<(i64, i64)> = vec![top];
+    let mut prev = top;
+    let mut curr = (top.0, top.1 + 1);
+    while curr != top {
+        result.push(curr);
+        let next = [(0, 1), (1, 0), (0, -1), (-1, 0)]
+            .iter()
+            .map(|(da, db)| (curr.0 + da, curr.1 + db))
+            .find(|next| *next != prev && has_edge(curr, *next))
+            .unwrap();
+        prev = curr;
+        curr = next;
+    }
+    result
+}
+
+pub fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+pub fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
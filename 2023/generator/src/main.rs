@@ -0,0 +1,49 @@
+use std::fs;
+use std::io::{self, Write};
+
+// Prints a synthetic input of the day and its known properties.
+// The input goes to stdout (or to the `--out` file) and the properties to stderr,
+// so the output can be piped straight into a day's input file.
+//
+// Usage: cargo run --release -p generator -- <day> [--seed N] [--size N] [--out path]
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|idx| args.get(idx + 1))
+    };
+    let number = |name: &str| -> io::Result<Option<u64>> {
+        option(name)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{name} expects a number, got \"{value}\""),
+                    )
+                })
+            })
+            .transpose()
+    };
+
+    let Some(day) = args.first().and_then(|arg| arg.parse::<u32>().ok()) else {
+        eprintln!("Usage: generator <day> [--seed N] [--size N] [--out path]");
+        return Ok(());
+    };
+    let seed = number("--seed")?.unwrap_or(0);
+    let size = number("--size")?.map(|size| size as usize);
+
+    let Some(generated) = generator::generate(day, seed, size) else {
+        eprintln!("There is no day {day}");
+        return Ok(());
+    };
+    match option("--out") {
+        Some(path) => fs::write(path, &generated.input)?,
+        None => io::stdout().write_all(generated.input.as_bytes())?,
+    }
+    for (name, value) in generated.properties.iter() {
+        eprintln!("{name}: {value}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,288 @@
+use memmap2::Mmap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::ops::{Deref, Index};
+use std::path::Path;
+
+// A rectangular grid of ASCII cells borrowed from the raw input.
+// Nothing is copied: a cell is a byte of the input and a row is a slice of it,
+// so the grid takes no memory on top of the input itself.
+#[derive(Clone, Copy)]
+pub struct Grid<'a> {
+    bytes: &'a [u8],
+    width: usize,
+    height: usize,
+    // distance between the starts of two rows, including the line break
+    stride: usize,
+}
+
+// The input file mapped into memory. Its pages are read on demand and the grid
+// borrows them directly, so even a huge input is never copied into the heap.
+// An empty file can't be mapped, it's just an empty input.
+pub struct Input {
+    map: Option<Mmap>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GridError {
+    Empty,
+    // the row doesn't have the width of the first one
+    Ragged {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    // the row ends with a different line break than the first one
+    MixedLineBreaks {
+        row: usize,
+    },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "The grid is empty"),
+            GridError::Ragged {
+                row,
+                expected,
+                found,
+            } => write!(f, "Row {row} has {found} cells instead of {expected}"),
+            GridError::MixedLineBreaks { row } => {
+                write!(f, "Row {row} has a different line break")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+impl Input {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Input> {
+        let file = File::open(path)?;
+        if file.metadata()?.len() == 0 {
+            return Ok(Input { map: None });
+        }
+        // the map is only read, and the input files aren't changed while the days run
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Input { map: Some(map) })
+    }
+}
+
+impl Deref for Input {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.map.as_deref().unwrap_or(&[])
+    }
+}
+
+impl<'a> Grid<'a> {
+    // Rows are separated by `\n` or `\r\n` and the last line break is optional.
+    // Every row is checked to have the same width, the input is read only once.
+    pub fn parse(bytes: &'a [u8]) -> Result<Grid<'a>, GridError> {
+        let Some(first_row) = split_rows(bytes).next().filter(|row| !row.is_empty()) else {
+            return Err(GridError::Empty);
+        };
+        let width = first_row.len();
+        let has_cr = bytes.get(width) == Some(&b'\r');
+        let stride = width + 1 + has_cr as usize;
+
+        let mut height = 0;
+        for (row, line) in split_rows(bytes).enumerate() {
+            if line.len() != width {
+                return Err(GridError::Ragged {
+                    row,
+                    expected: width,
+                    found: line.len(),
+                });
+            }
+            // rows are found by their index, so all the line breaks have to be the same
+            if line.as_ptr() as usize - bytes.as_ptr() as usize != row * stride {
+                return Err(GridError::MixedLineBreaks { row });
+            }
+            height += 1;
+        }
+        Ok(Grid {
+            bytes,
+            width,
+            height,
+            stride,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn row(&self, i: usize) -> &'a [u8] {
+        let start = i * self.stride;
+        &self.bytes[start..start + self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        (0..self.height).map(|i| self.row(i))
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Option<u8> {
+        if i < self.height && j < self.width {
+            Some(self.bytes[i * self.stride + j])
+        } else {
+            None
+        }
+    }
+
+    // Position of the first cell with the given value, row by row
+    pub fn find(&self, cell: u8) -> Option<(usize, usize)> {
+        self.positions(cell).next()
+    }
+
+    // Positions of all the cells with the given value, row by row
+    pub fn positions(&self, cell: u8) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.rows().enumerate().flat_map(move |(i, row)| {
+            row.iter()
+                .enumerate()
+                .filter(move |(_, x)| **x == cell)
+                .map(move |(j, _)| (i, j))
+        })
+    }
+}
+
+impl Index<(usize, usize)> for Grid<'_> {
+    type Output = u8;
+
+    fn index(&self, (i, j): (usize, usize)) -> &u8 {
+        assert!(
+            i < self.height && j < self.width,
+            "({i}, {j}) is out of the grid"
+        );
+        &self.bytes[i * self.stride + j]
+    }
+}
+
+// Lines without their line breaks, the empty line after the last line break is skipped
+fn split_rows(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    bytes
+        .split(|x| *x == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The cells of the synthetic grid are a hash of their position,
+    // so any of them can be checked without keeping a copy of the grid
+    fn cell(i: usize, j: usize) -> u8 {
+        let hash = (i as u64)
+            .wrapping_mul(0x9E3779B97F4A7C15)
+            .wrapping_add((j as u64).wrapping_mul(0xC2B2AE3D27D4EB4F))
+            .rotate_left(31)
+            .wrapping_mul(0x165667B19E3779F9);
+        match hash >> 61 {
+            0 => b'#',
+            1 => b'O',
+            _ => b'.',
+        }
+    }
+
+    fn generate(width: usize, height: usize, start: (usize, usize), line_break: &str) -> Vec<u8> {
+        let mut result: Vec<u8> = Vec::with_capacity(height * (width + line_break.len()));
+        for i in 0..height {
+            for j in 0..width {
+                result.push(if (i, j) == start { b'S' } else { cell(i, j) });
+            }
+            result.extend_from_slice(line_break.as_bytes());
+        }
+        result
+    }
+
+    #[test]
+    fn large_grid() {
+        let (width, height) = (1000, 4000);
+        let start = (height / 3, width / 2);
+        for line_break in ["\n", "\r\n"] {
+            let input = generate(width, height, start, line_break);
+            let grid = Grid::parse(&input).unwrap();
+            assert_eq!((grid.width(), grid.height()), (width, height));
+
+            assert_eq!(grid.find(b'S'), Some(start));
+            let expected_rocks = (0..height)
+                .flat_map(|i| (0..width).map(move |j| (i, j)))
+                .filter(|(i, j)| (*i, *j) != start && cell(*i, *j) == b'#')
+                .count();
+            assert_eq!(grid.positions(b'#').count(), expected_rocks);
+            for k in 0..100_000 {
+                let (i, j) = ((k * 7919) % height, (k * 104729) % width);
+                let expected = if (i, j) == start { b'S' } else { cell(i, j) };
+                assert_eq!(grid[(i, j)], expected);
+                assert_eq!(grid.row(i)[j], expected);
+                assert_eq!(grid.get(i, j), Some(expected));
+            }
+            assert_eq!(grid.get(height, 0), None);
+            assert_eq!(grid.get(0, width), None);
+
+            // without the last line break it's the same grid
+            let trimmed = input.strip_suffix(line_break.as_bytes()).unwrap();
+            assert_eq!(Grid::parse(trimmed).unwrap().height(), height);
+        }
+    }
+
+    // broken inputs are rejected, not misread
+    #[test]
+    fn broken_grids() {
+        assert_eq!(Grid::parse(b"").err(), Some(GridError::Empty));
+        assert_eq!(Grid::parse(b"\n...").err(), Some(GridError::Empty));
+        assert_eq!(
+            Grid::parse(b"..#\n.#\n...\n").err(),
+            Some(GridError::Ragged {
+                row: 1,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            Grid::parse(b"..#\r\n.#.\n...\n").err(),
+            Some(GridError::MixedLineBreaks { row: 2 })
+        );
+        assert_eq!(
+            Grid::parse(b"..#\n\n").err(),
+            Some(GridError::Ragged {
+                row: 1,
+                expected: 3,
+                found: 0
+            })
+        );
+    }
+
+    #[test]
+    fn mapped_input() {
+        let dir = std::env::temp_dir().join(format!("grid-input-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("input.txt");
+
+        std::fs::write(&path, b"..#\n#..\n").unwrap();
+        let input = Input::open(&path).unwrap();
+        let grid = Grid::parse(&input).unwrap();
+        assert_eq!(grid.positions(b'#').collect::<Vec<_>>(), [(0, 2), (1, 0)]);
+
+        std::fs::write(&path, b"").unwrap();
+        let input = Input::open(&path).unwrap();
+        assert_eq!(Grid::parse(&input).err(), Some(GridError::Empty));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(Input::open(&path).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of the grid")]
+    fn index_out_of_the_grid() {
+        let grid = Grid::parse(b"..\n..").unwrap();
+        let _ = grid[(0, 2)];
+    }
+}
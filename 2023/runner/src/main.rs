@@ -0,0 +1,117 @@
+use std::env::consts::EXE_SUFFIX;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use wait_timeout::ChildExt;
+
+// How long a day can run before it's killed, `--timeout=<seconds>` changes it
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Runs the days of the workspace and prints their outputs in the day order.
+// The days are run concurrently unless `--sequential` is given, but every output
+// is collected first, so the order never depends on which day finishes first.
+// `--answers` keeps only the `Part...` lines, so the runs of the workspace built
+// with and without `--features parallel` can be compared, tests/determinism.rs does that.
+//
+// A day that doesn't finish in time is killed and reported as timed out.
+//
+// Usage (from the workspace root, where the days look for their inputs):
+//   cargo build --release --workspace && target/release/runner [--sequential] [--answers]
+//     [--timeout=<seconds>] [days...]
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let sequential = args.iter().any(|arg| arg == "--sequential");
+    let answers_only = args.iter().any(|arg| arg == "--answers");
+    let timeout = match args.iter().find_map(|arg| arg.strip_prefix("--timeout=")) {
+        Some(value) => value.parse().map(Duration::from_secs).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Wrong timeout {value}"),
+            )
+        })?,
+        None => DEFAULT_TIMEOUT,
+    };
+    let mut days: Vec<u32> = args.iter().filter_map(|arg| arg.parse().ok()).collect();
+    if days.is_empty() {
+        days = (1..=25).collect();
+    }
+
+    // the days are built into the same directory as the runner
+    let exe_path = std::env::current_exe()?;
+    let bin_dir = exe_path.parent().unwrap_or(Path::new("."));
+
+    let outputs: Vec<Result<String, String>> = if sequential {
+        days.iter()
+            .map(|day| run_day(bin_dir, *day, timeout))
+            .collect()
+    } else {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = days
+                .iter()
+                .map(|day| scope.spawn(move || run_day(bin_dir, *day, timeout)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    };
+
+    for (day, output) in days.iter().zip(outputs.iter()) {
+        println!("Day {day}");
+        match output {
+            Ok(output) => {
+                for line in output.lines() {
+                    if !answers_only || line.starts_with("Part") {
+                        println!("  {line}");
+                    }
+                }
+            }
+            Err(err) => println!("  {err}"),
+        }
+    }
+
+    Ok(())
+}
+
+// Output of the day, or what went wrong if it failed or didn't finish in time
+fn run_day(bin_dir: &Path, day: u32, timeout: Duration) -> Result<String, String> {
+    let path: PathBuf = bin_dir.join(format!("day{day}{EXE_SUFFIX}"));
+    let mut child = Command::new(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Can't run {}: {err}", path.display()))?;
+    // the pipes are drained while waiting, so a day with a long output doesn't block on them
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let status = match child.wait_timeout(timeout) {
+        Ok(Some(status)) => status,
+        Ok(None) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("Timed out after {} s", timeout.as_secs()));
+        }
+        Err(err) => return Err(format!("Can't wait for {}: {err}", path.display())),
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if status.success() {
+        Ok(stdout)
+    } else {
+        Err(format!("Failed with {}: {}", status, stderr.trim()))
+    }
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut buf: Vec<u8> = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).into_owned()
+    })
+}
@@ -0,0 +1,84 @@
+// The answers must not depend on the `parallel` feature or on the days running
+// concurrently. The days having the feature are built with and without it
+// into a separate target directory and run by the runner on generated inputs.
+// The generator is built along with them, in the test profile it's too slow.
+use std::env::consts::EXE_SUFFIX;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PARALLEL_DAYS: [u32; 5] = [11, 12, 16, 22, 23];
+const SEED: u64 = 1;
+
+// Directory with the release binaries of the runner and the days
+fn build(target_dir: &Path, parallel: bool) -> PathBuf {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("../Cargo.toml");
+    let mut command = Command::new(cargo);
+    command
+        .args(["build", "--release", "--quiet", "--manifest-path"])
+        .arg(&manifest)
+        .arg("--target-dir")
+        .arg(target_dir)
+        .args(["-p", "runner", "-p", "generator"]);
+    for day in PARALLEL_DAYS {
+        command.args(["-p", &format!("day{day}")]);
+    }
+    if parallel {
+        let features: Vec<String> = PARALLEL_DAYS
+            .iter()
+            .map(|day| format!("day{day}/parallel"))
+            .collect();
+        command.arg("--features").arg(features.join(","));
+    }
+    let status = command.status().unwrap();
+    assert!(status.success(), "Build failed with {status}");
+    target_dir.join("release")
+}
+
+fn run(bin_dir: &Path, work_dir: &Path, args: &[&str]) -> String {
+    let output = Command::new(bin_dir.join(format!("runner{EXE_SUFFIX}")))
+        .current_dir(work_dir)
+        .arg("--answers")
+        .args(args)
+        .args(PARALLEL_DAYS.map(|day| day.to_string()))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn parallel_and_sequential_answers_are_equal() {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("determinism");
+    let sequential = build(&target_dir.join("sequential"), false);
+    let parallel = build(&target_dir.join("parallel"), true);
+
+    // the days look for their inputs relative to the working directory
+    let work_dir = std::env::temp_dir().join(format!("determinism-{}", std::process::id()));
+    for day in PARALLEL_DAYS {
+        let input_dir = work_dir.join(format!("day{day}/src"));
+        fs::create_dir_all(&input_dir).unwrap();
+        let status = Command::new(sequential.join(format!("generator{EXE_SUFFIX}")))
+            .args([day.to_string(), "--seed".to_string(), SEED.to_string()])
+            .arg("--out")
+            .arg(input_dir.join("input.txt"))
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "Can't generate the input of day {day}");
+    }
+
+    let expected = run(&sequential, &work_dir, &["--sequential"]);
+    for day in PARALLEL_DAYS {
+        assert!(
+            expected.contains(&format!("Day {day}\n  Part1")),
+            "Day {day} has no answers:\n{expected}"
+        );
+    }
+    assert_eq!(run(&sequential, &work_dir, &[]), expected);
+    assert_eq!(run(&parallel, &work_dir, &["--sequential"]), expected);
+    assert_eq!(run(&parallel, &work_dir, &[]), expected);
+
+    fs::remove_dir_all(&work_dir).unwrap();
+}
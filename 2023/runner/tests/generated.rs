@@ -0,0 +1,104 @@
+// The solvers must find the answers the generator knows about its inputs.
+// Every day is run by the runner on generated inputs for a few seeds, and its
+// `Part...` lines are compared with the properties of the same name.
+// Day 20 only prints the presses of a single module until it's killed.
+use std::env::consts::EXE_SUFFIX;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SKIPPED_DAYS: [u32; 1] = [20];
+const SEEDS: [u64; 2] = [1, 2];
+
+// Directory with the release binaries of the whole workspace
+fn build(target_dir: &Path) -> PathBuf {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("../Cargo.toml");
+    let status = Command::new(cargo)
+        .args([
+            "build",
+            "--release",
+            "--quiet",
+            "--workspace",
+            "--manifest-path",
+        ])
+        .arg(&manifest)
+        .arg("--target-dir")
+        .arg(target_dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "Build failed with {status}");
+    target_dir.join("release")
+}
+
+// `Part...` properties of the generated input of the day, written to `input_path`
+fn generate(bin_dir: &Path, day: u32, seed: u64, input_path: &Path) -> Vec<String> {
+    let output = Command::new(bin_dir.join(format!("generator{EXE_SUFFIX}")))
+        .args([day.to_string(), "--seed".to_string(), seed.to_string()])
+        .arg("--out")
+        .arg(input_path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "Can't generate the input of day {day}"
+    );
+    String::from_utf8(output.stderr)
+        .unwrap()
+        .lines()
+        .filter(|line| line.starts_with("Part"))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[test]
+fn solvers_find_generated_answers() {
+    let bin_dir = build(&Path::new(env!("CARGO_TARGET_TMPDIR")).join("generated"));
+    let days: Vec<u32> = (1..=25).filter(|day| !SKIPPED_DAYS.contains(day)).collect();
+
+    for seed in SEEDS {
+        // the days look for their inputs relative to the working directory
+        let work_dir =
+            std::env::temp_dir().join(format!("generated-{}-{seed}", std::process::id()));
+        let mut expected: Vec<(u32, Vec<String>)> = vec![];
+        for day in days.iter().copied() {
+            let input_dir = work_dir.join(format!("day{day}/src"));
+            fs::create_dir_all(&input_dir).unwrap();
+            expected.push((
+                day,
+                generate(&bin_dir, day, seed, &input_dir.join("input.txt")),
+            ));
+        }
+
+        let output = Command::new(bin_dir.join(format!("runner{EXE_SUFFIX}")))
+            .current_dir(&work_dir)
+            .arg("--answers")
+            .args(days.iter().map(|day| day.to_string()))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let output = String::from_utf8(output.stdout).unwrap();
+
+        for (day, properties) in expected {
+            let answers: Vec<String> = output
+                .split(&format!("Day {day}\n"))
+                .nth(1)
+                .unwrap_or_default()
+                .lines()
+                .map_while(|line| line.strip_prefix("  "))
+                // some days print both parts on one line, the second one as `part2`
+                .flat_map(|line| line.split(", "))
+                .map(|answer| answer.replacen("part", "Part", 1))
+                .collect();
+            assert!(!properties.is_empty(), "Day {day} has no known answers");
+            for property in properties {
+                assert!(
+                    answers.contains(&property),
+                    "Day {day}, seed {seed}: expected {property}, got {answers:?}"
+                );
+            }
+        }
+
+        fs::remove_dir_all(&work_dir).unwrap();
+    }
+}